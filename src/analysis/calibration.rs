@@ -0,0 +1,293 @@
+use crate::sampler::Sample;
+use std::collections::BTreeMap;
+
+/// Throttle below this counts as a lift.
+const FULL_THROTTLE: f32 = 0.95;
+/// The throttle must have stayed pinned this long up to the shift.
+const MIN_PULL_SECONDS: f64 = 0.5;
+/// Only the rpm peak this close to the shift counts as the shift point.
+const PEAK_WINDOW_SECONDS: f64 = 0.3;
+/// Rpm drop near the limiter, at full throttle in the same gear, that marks a bounce.
+const BOUNCE_DROP_RPM: i32 = 100;
+const LIMITER_BAND_RPM: i32 = 300;
+pub const MIN_SHIFTS: usize = 3;
+
+/// Collects upshifts per gear and turns them into recommended shift rpms.
+pub struct ShiftCalibration {
+    /// Accepted shift rpms by forward gear shifted from (1 = first).
+    shifts: BTreeMap<i32, Vec<i32>>,
+    rejected: BTreeMap<i32, usize>,
+    pull: Option<Pull>,
+}
+
+/// Full-throttle run in one gear, tracked sample by sample.
+struct Pull {
+    gear: i32,
+    /// Current or last full-throttle stretch.
+    full_since: f64,
+    full_until: Option<f64>,
+    was_full: bool,
+    /// (t, rpm) of recent samples, for the peak just before the shift.
+    recent: Vec<(f64, i32)>,
+    last_rpm: i32,
+    bounced: bool,
+}
+
+pub struct GearResult {
+    pub gear: i32,
+    pub accepted: usize,
+    /// Lift-offs, limiter bounces and outliers.
+    pub rejected: usize,
+    /// Median of the accepted shifts once at least `MIN_SHIFTS` remain
+    /// after outlier removal.
+    pub recommended: Option<i32>,
+}
+
+impl ShiftCalibration {
+    pub fn new() -> Self {
+        Self {
+            shifts: BTreeMap::new(),
+            rejected: BTreeMap::new(),
+            pull: None,
+        }
+    }
+
+    pub fn shift_count(&self) -> usize {
+        self.shifts.values().map(Vec::len).sum()
+    }
+
+    pub fn observe(&mut self, sample: &Sample) {
+        let telemetry = sample.frame.telemetry(0.0);
+        let gear = telemetry.gear;
+        let rpm = telemetry.rpm as i32;
+        let t = sample.t;
+
+        if let Some(pull) = &self.pull
+            && pull.gear != gear
+        {
+            let pull = self.pull.take().unwrap();
+            if gear == pull.gear + 1 {
+                self.finish(pull, t);
+            }
+        }
+        if gear < 1 {
+            return;
+        }
+
+        let pull = self.pull.get_or_insert(Pull {
+            gear,
+            full_since: t,
+            full_until: None,
+            was_full: false,
+            recent: Vec::new(),
+            last_rpm: rpm,
+            bounced: false,
+        });
+
+        let full = telemetry.throttle >= FULL_THROTTLE;
+        if full {
+            if !pull.was_full {
+                pull.full_since = t;
+                pull.bounced = false;
+            }
+            pull.full_until = Some(t);
+            let near_limiter = rpm >= telemetry.max_rpm as i32 - LIMITER_BAND_RPM;
+            if near_limiter && pull.last_rpm - rpm >= BOUNCE_DROP_RPM {
+                pull.bounced = true;
+            }
+        }
+        pull.was_full = full;
+        pull.last_rpm = rpm;
+        pull.recent.push((t, rpm));
+        pull.recent.retain(|(at, _)| t - at <= PEAK_WINDOW_SECONDS);
+    }
+
+    fn finish(&mut self, pull: Pull, t: f64) {
+        // Lifting for the shift itself is fine, lifting earlier in the pull is not
+        let pulled_to_shift = pull.full_until.is_some_and(|until| {
+            t - until <= PEAK_WINDOW_SECONDS && until - pull.full_since >= MIN_PULL_SECONDS
+        });
+        if !pulled_to_shift || pull.bounced {
+            *self.rejected.entry(pull.gear).or_default() += 1;
+            return;
+        }
+        let rpm = pull.recent.iter().map(|(_, rpm)| *rpm).max().unwrap_or(0);
+        self.shifts.entry(pull.gear).or_default().push(rpm);
+    }
+
+    /// Per-gear summary with outliers (beyond 3 median absolute deviations)
+    /// left out of the recommendation.
+    pub fn results(&self) -> Vec<GearResult> {
+        let gears: std::collections::BTreeSet<i32> = self
+            .shifts
+            .keys()
+            .chain(self.rejected.keys())
+            .copied()
+            .collect();
+        gears
+            .into_iter()
+            .map(|gear| {
+                let shifts = self.shifts.get(&gear).map(Vec::as_slice).unwrap_or(&[]);
+                let kept: Vec<i32> = match median(shifts) {
+                    Some(center) => {
+                        let deviations: Vec<i32> =
+                            shifts.iter().map(|rpm| (rpm - center).abs()).collect();
+                        let limit = (median(&deviations).unwrap_or(0) * 3).max(50);
+                        shifts
+                            .iter()
+                            .copied()
+                            .filter(|rpm| (rpm - center).abs() <= limit)
+                            .collect()
+                    }
+                    None => Vec::new(),
+                };
+                let recommended = (kept.len() >= MIN_SHIFTS)
+                    .then(|| median(&kept))
+                    .flatten()
+                    .map(|rpm| (rpm + 25) / 50 * 50);
+                GearResult {
+                    gear,
+                    accepted: kept.len(),
+                    rejected: self.rejected.get(&gear).copied().unwrap_or(0)
+                        + (shifts.len() - kept.len()),
+                    recommended,
+                }
+            })
+            .collect()
+    }
+}
+
+impl Default for ShiftCalibration {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn median(values: &[i32]) -> Option<i32> {
+    if values.is_empty() {
+        return None;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    Some(sorted[sorted.len() / 2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::TelemetryFrame;
+    use crate::source::Frame;
+
+    const MAX_RPM: f32 = 7500.0;
+    const DT: f64 = 0.05;
+
+    /// Feeds samples at 20 Hz, continuing from the calibration's last one.
+    struct Driver {
+        calibration: ShiftCalibration,
+        t: f64,
+    }
+
+    impl Driver {
+        fn new() -> Self {
+            Self {
+                calibration: ShiftCalibration::new(),
+                t: 0.0,
+            }
+        }
+
+        fn sample(&mut self, gear: i32, rpm: i32, throttle: f32) {
+            let telemetry = TelemetryFrame {
+                gear,
+                rpm: rpm as f32,
+                max_rpm: MAX_RPM,
+                throttle,
+                ..TelemetryFrame::default()
+            };
+            let frame = Frame::from_telemetry(&telemetry, 0, "test", "car", "track");
+            self.calibration.observe(&Sample { t: self.t, frame });
+            self.t += DT;
+        }
+
+        /// A second at full throttle in 1st, rpm climbing to `peak`, then the
+        /// upshift and a drop back to 1st for the next one.
+        fn pull(&mut self, peak: i32) {
+            self.pull_with(peak, |_, rpm| (rpm, 1.0));
+        }
+
+        /// Like [`pull`](Self::pull), with `f` changing the rpm and throttle
+        /// of each sample by its index.
+        fn pull_with(&mut self, peak: i32, f: impl Fn(usize, i32) -> (i32, f32)) {
+            for i in 0..=20 {
+                let (rpm, throttle) = f(i, peak - (20 - i as i32) * 100);
+                self.sample(1, rpm, throttle);
+            }
+            self.sample(2, peak - 2000, 1.0);
+            self.sample(1, 3000, 0.0);
+        }
+
+        fn first_gear(&self) -> GearResult {
+            let mut results = self.calibration.results();
+            assert_eq!(results.len(), 1);
+            results.remove(0)
+        }
+    }
+
+    #[test]
+    fn recommends_the_median_shift() {
+        let mut driver = Driver::new();
+        for peak in [7000, 7080, 6960] {
+            driver.pull(peak);
+        }
+        let result = driver.first_gear();
+        assert_eq!((result.gear, result.accepted, result.rejected), (1, 3, 0));
+        // 7000 rounded to 50 rpm
+        assert_eq!(result.recommended, Some(7000));
+        assert_eq!(driver.calibration.shift_count(), 3);
+    }
+
+    #[test]
+    fn needs_min_shifts() {
+        let mut driver = Driver::new();
+        for peak in [7000, 7000] {
+            driver.pull(peak);
+        }
+        let result = driver.first_gear();
+        assert_eq!(result.accepted, MIN_SHIFTS - 1);
+        assert_eq!(result.recommended, None);
+    }
+
+    #[test]
+    fn leaves_out_outliers() {
+        let mut driver = Driver::new();
+        for peak in [7000, 7010, 6990, 7020, 5000] {
+            driver.pull(peak);
+        }
+        let result = driver.first_gear();
+        assert_eq!((result.accepted, result.rejected), (4, 1));
+        // Median of 6990, 7000, 7010, 7020
+        assert_eq!(result.recommended, Some(7000));
+    }
+
+    #[test]
+    fn rejects_lift_offs() {
+        let mut driver = Driver::new();
+        // Lifted half a second before the shift
+        driver.pull_with(7000, |i, rpm| (rpm, if i < 10 { 1.0 } else { 0.3 }));
+        // Back on the throttle only for the last 0.2 s
+        driver.pull_with(7000, |i, rpm| {
+            (rpm, if (10..16).contains(&i) { 0.3 } else { 1.0 })
+        });
+        let result = driver.first_gear();
+        assert_eq!((result.accepted, result.rejected), (0, 2));
+    }
+
+    #[test]
+    fn rejects_limiter_bounces() {
+        let mut driver = Driver::new();
+        driver.pull_with(7500, |i, rpm| (if i == 19 { rpm - 200 } else { rpm }, 1.0));
+        // The same drop well below the limiter is not a bounce
+        driver.pull_with(6500, |i, rpm| (if i == 19 { rpm - 200 } else { rpm }, 1.0));
+        let result = driver.first_gear();
+        assert_eq!((result.accepted, result.rejected), (1, 1));
+    }
+}
@@ -0,0 +1,404 @@
+use super::laps::LapTimer;
+use crate::data::{SECTORS, TelemetryFrame, parse_static_string};
+use crate::recording::SessionReader;
+use std::collections::VecDeque;
+use std::path::Path;
+
+/// Resolution of a lap trace: points along the lap.
+const TRACE_POINTS: usize = 1000;
+/// A trace with a hole wider than this (in lap fraction) is not used: the
+/// sim paused, or a replay jumped.
+const MAX_TRACE_GAP: f32 = 0.02;
+/// The delta trend is the change over this many seconds.
+const TREND_SECONDS: f64 = 1.0;
+
+/// Time into a lap against the position along it, for comparing laps at
+/// the same point of the track.
+#[derive(Clone, Debug)]
+pub struct LapTrace {
+    /// Seconds at `i / TRACE_POINTS` of the lap, from 0 to the lap time.
+    times: Vec<f64>,
+    /// Lap fraction at the end of each sector but the last.
+    sector_ends: [f32; SECTORS - 1],
+}
+
+impl LapTrace {
+    pub fn lap_time(&self) -> f64 {
+        self.times[TRACE_POINTS]
+    }
+
+    /// Seconds into the lap at `fraction` of it.
+    pub fn time_at(&self, fraction: f32) -> f64 {
+        let x = fraction.clamp(0.0, 1.0) as f64 * TRACE_POINTS as f64;
+        let i = (x as usize).min(TRACE_POINTS - 1);
+        let (a, b) = (self.times[i], self.times[i + 1]);
+        a + (b - a) * (x - i as f64)
+    }
+
+    /// Lap fractions where sector `i` starts and ends.
+    fn sector_range(&self, i: usize) -> (f32, f32) {
+        let start = if i == 0 { 0.0 } else { self.sector_ends[i - 1] };
+        let end = self.sector_ends.get(i).copied().unwrap_or(1.0);
+        (start, end)
+    }
+
+    /// One lap made of sector `i` of `parts[i]`, each given with its time.
+    pub fn join_sectors(parts: &[(&LapTrace, f64)]) -> Self {
+        let mut offsets = Vec::with_capacity(parts.len());
+        let mut offset = 0.0;
+        for (_, sector_time) in parts {
+            offsets.push(offset);
+            offset += sector_time;
+        }
+        let times = (0..=TRACE_POINTS)
+            .map(|k| {
+                let fraction = k as f32 / TRACE_POINTS as f32;
+                // Each part's own sector lines, which differ by a sample at most
+                let i = (0..parts.len())
+                    .find(|&i| fraction < parts[i].0.sector_range(i).1)
+                    .unwrap_or(parts.len() - 1);
+                let (trace, _) = parts[i];
+                let start = trace.sector_range(i).0;
+                offsets[i] + trace.time_at(fraction) - trace.time_at(start)
+            })
+            .collect();
+        let sector_ends =
+            std::array::from_fn(|i| parts.get(i).map_or(1.0, |(t, _)| t.sector_range(i).1));
+        let mut trace = Self { times, sector_ends };
+        // End exactly on the sum of the sectors
+        trace.times[TRACE_POINTS] = offset;
+        trace
+    }
+}
+
+/// Collects the samples of one lap, from the line, into a [`LapTrace`].
+pub(super) struct TraceBuilder {
+    times: Vec<f64>,
+    last: (f32, f64),
+    sector_ends: [Option<f32>; SECTORS - 1],
+    broken: bool,
+}
+
+impl TraceBuilder {
+    pub(super) fn new() -> Self {
+        let mut times = Vec::with_capacity(TRACE_POINTS + 1);
+        times.push(0.0);
+        Self {
+            times,
+            last: (0.0, 0.0),
+            sector_ends: [None; SECTORS - 1],
+            broken: false,
+        }
+    }
+
+    pub(super) fn push(&mut self, fraction: f32, time: f64) {
+        let (last_fraction, last_time) = self.last;
+        // Right after the line some sims still report the end of the lap,
+        // and backwards is no use for a trace
+        if fraction <= last_fraction || fraction - last_fraction > 0.5 {
+            return;
+        }
+        if fraction - last_fraction > MAX_TRACE_GAP {
+            self.broken = true;
+        }
+        while self.times.len() <= TRACE_POINTS {
+            let at = self.times.len() as f32 / TRACE_POINTS as f32;
+            if at > fraction {
+                break;
+            }
+            let share = ((at - last_fraction) / (fraction - last_fraction)) as f64;
+            self.times.push(last_time + (time - last_time) * share);
+        }
+        self.last = (fraction, time);
+    }
+
+    pub(super) fn end_sector(&mut self, sector: usize, fraction: f32) {
+        if let Some(end) = self.sector_ends.get_mut(sector) {
+            *end = Some(fraction);
+        }
+    }
+
+    pub(super) fn finish(mut self, lap_time: f64) -> Option<LapTrace> {
+        if 1.0 - self.last.0 > MAX_TRACE_GAP {
+            return None;
+        }
+        self.push(1.0, lap_time);
+        if self.broken || self.times.len() != TRACE_POINTS + 1 {
+            return None;
+        }
+        let mut sector_ends = [0.0; SECTORS - 1];
+        for (end, recorded) in sector_ends.iter_mut().zip(self.sector_ends) {
+            *end = recorded?;
+        }
+        self.times[TRACE_POINTS] = lap_time;
+        Some(LapTrace {
+            times: self.times,
+            sector_ends,
+        })
+    }
+}
+
+/// The lap the delta is against.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum DeltaReference {
+    /// The driver's own fastest valid lap of the current session.
+    OwnBest,
+    /// Fastest valid lap ever recorded with the car on the track.
+    PersonalBest,
+    /// The best sectors joined into one lap.
+    Optimal,
+}
+
+impl DeltaReference {
+    pub fn name(self) -> &'static str {
+        match self {
+            DeltaReference::OwnBest => "OWN BEST",
+            DeltaReference::PersonalBest => "PB",
+            DeltaReference::Optimal => "OPTIMAL",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            DeltaReference::OwnBest => DeltaReference::PersonalBest,
+            DeltaReference::PersonalBest => DeltaReference::Optimal,
+            DeltaReference::Optimal => DeltaReference::OwnBest,
+        }
+    }
+
+    /// The reference lap; the personal best is the recorded one or the own
+    /// best of this session, whichever is faster.
+    pub fn trace<'a>(
+        self,
+        timer: &'a LapTimer,
+        recorded_best: Option<&'a LapTrace>,
+    ) -> Option<&'a LapTrace> {
+        match self {
+            DeltaReference::OwnBest => timer.best_trace(),
+            DeltaReference::PersonalBest => [timer.best_trace(), recorded_best]
+                .into_iter()
+                .flatten()
+                .min_by(|a, b| a.lap_time().total_cmp(&b.lap_time())),
+            DeltaReference::Optimal => timer.optimal_lap(),
+        }
+    }
+}
+
+/// Time gained or lost against a reference lap, at the same position on
+/// track, updated with every sample.
+pub struct LapDelta {
+    delta: Option<f64>,
+    /// (sample time, delta) over the last [`TREND_SECONDS`].
+    history: VecDeque<(f64, f64)>,
+    last_fraction: f32,
+}
+
+impl LapDelta {
+    pub fn new() -> Self {
+        Self {
+            delta: None,
+            history: VecDeque::new(),
+            last_fraction: 0.0,
+        }
+    }
+
+    /// `t` is the sample time, `reference` the lap to compare with.
+    pub fn observe(&mut self, t: f64, frame: &TelemetryFrame, reference: Option<&LapTrace>) {
+        // A new lap, or a jump: the trend would mix two laps
+        if frame.lap_fraction < self.last_fraction {
+            self.history.clear();
+        }
+        self.last_fraction = frame.lap_fraction;
+
+        self.delta = reference
+            .zip(frame.current_lap_time)
+            .and_then(|(reference, time)| {
+                let delta = time - reference.time_at(frame.lap_fraction);
+                // Around the line the position and the lap time do not always
+                // switch laps on the same sample
+                (delta.abs() < reference.lap_time() / 2.0).then_some(delta)
+            });
+        match self.delta {
+            Some(delta) => {
+                if self.history.back().is_some_and(|&(last, _)| t < last) {
+                    self.history.clear();
+                }
+                self.history.push_back((t, delta));
+                while self
+                    .history
+                    .front()
+                    .is_some_and(|&(first, _)| t - first > TREND_SECONDS)
+                {
+                    self.history.pop_front();
+                }
+            }
+            None => self.history.clear(),
+        }
+    }
+
+    /// Seconds behind (positive) or ahead of the reference.
+    pub fn delta(&self) -> Option<f64> {
+        self.delta
+    }
+
+    /// Seconds lost (positive) or gained per second, over the last second.
+    pub fn trend(&self) -> Option<f64> {
+        let (&(t0, d0), &(t1, d1)) = (self.history.front()?, self.history.back()?);
+        (t1 - t0 >= TREND_SECONDS / 2.0).then(|| (d1 - d0) / (t1 - t0))
+    }
+}
+
+impl Default for LapDelta {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Trace of the fastest valid lap in the recorded sessions of `car_model`
+/// on `track`. Reads every session in `dir`, so best run off the UI thread.
+pub fn recorded_best(dir: &Path, car_model: &str, track: &str) -> Option<LapTrace> {
+    let entries = std::fs::read_dir(dir).ok()?;
+    let mut best: Option<LapTrace> = None;
+    for path in entries.flatten().map(|e| e.path()) {
+        if path.extension().is_none_or(|ext| ext != "stm") {
+            continue;
+        }
+        let Ok(mut reader) = SessionReader::open(&path) else {
+            continue;
+        };
+        let mut timer = LapTimer::new();
+        for i in 0..reader.chunks.len() {
+            let Ok(samples) = reader.decode_chunk(i) else {
+                break;
+            };
+            // Sessions end when the car or track changes
+            if let Some(first) = samples.first()
+                && (parse_static_string(&first.frame.statics.car_model) != car_model
+                    || parse_static_string(&first.frame.statics._track) != track)
+            {
+                break;
+            }
+            for sample in &samples {
+                timer.observe(&sample.frame.telemetry(0.0));
+            }
+        }
+        if let Some(trace) = timer.best_trace()
+            && best
+                .as_ref()
+                .is_none_or(|b| trace.lap_time() < b.lap_time())
+        {
+            best = Some(trace.clone());
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Samples per sector, at most [`MAX_TRACE_GAP`] apart.
+    const STEPS: usize = 100;
+
+    /// A trace of a lap with the sectors ending at thirds of it, each driven
+    /// at a constant pace.
+    fn trace(sectors: [f64; SECTORS]) -> LapTrace {
+        let mut builder = TraceBuilder::new();
+        let mut start = 0.0;
+        for (i, sector) in sectors.into_iter().enumerate() {
+            for step in 0..STEPS {
+                let k = i * STEPS + step;
+                let fraction = k as f32 / (SECTORS * STEPS) as f32;
+                if step == 0 && i > 0 {
+                    builder.end_sector(i - 1, fraction);
+                }
+                builder.push(fraction, start + sector * step as f64 / STEPS as f64);
+            }
+            start += sector;
+        }
+        builder.finish(start).expect("no trace")
+    }
+
+    /// Within what a trace point apart can be off by, where the pace changes.
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 0.01, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn trace_interpolates_between_samples() {
+        let trace = trace([30.0, 40.0, 35.0]);
+        assert_eq!(trace.lap_time(), 105.0);
+        assert_close(trace.time_at(0.0), 0.0);
+        assert_close(trace.time_at(1.0 / 6.0), 15.0);
+        assert_close(trace.time_at(1.0 / 3.0), 30.0);
+        assert_close(trace.time_at(0.5), 50.0);
+        assert_close(trace.time_at(5.0 / 6.0), 87.5);
+        assert_close(trace.time_at(1.0), 105.0);
+
+        // Samples further apart than the trace points, with the pace
+        // changing halfway
+        let time = |fraction: f64| 120.0 * fraction.min(0.5) + 40.0 * (fraction - 0.5).max(0.0);
+        let mut builder = TraceBuilder::new();
+        builder.end_sector(0, 0.3);
+        builder.end_sector(1, 0.6);
+        for k in 1..=99 {
+            let fraction = k as f32 * 0.01;
+            builder.push(fraction, time(fraction as f64));
+        }
+        let trace = builder.finish(80.0).expect("no trace");
+        assert_close(trace.time_at(0.255), 30.6);
+        assert_close(trace.time_at(0.755), 70.2);
+        assert_close(trace.time_at(0.995), 79.8);
+    }
+
+    #[test]
+    fn trace_needs_the_whole_lap() {
+        let mut builder = TraceBuilder::new();
+        builder.end_sector(0, 0.3);
+        builder.end_sector(1, 0.6);
+        for fraction in [0.01, 0.02, 0.5, 0.99] {
+            builder.push(fraction, 100.0 * fraction as f64);
+        }
+        assert!(builder.finish(100.0).is_none());
+
+        let mut builder = TraceBuilder::new();
+        for k in 1..=99 {
+            builder.push(k as f32 * 0.01, k as f64);
+        }
+        assert!(builder.finish(100.0).is_none(), "sector ends missing");
+    }
+
+    #[test]
+    fn optimal_lap_is_the_sum_of_the_best_sectors() {
+        let (a, b) = (trace([30.0, 40.0, 35.0]), trace([29.0, 41.0, 34.5]));
+        let optimal = LapTrace::join_sectors(&[(&b, 29.0), (&a, 40.0), (&b, 34.5)]);
+        assert_eq!(optimal.lap_time(), 103.5);
+        assert_close(optimal.time_at(1.0 / 6.0), 14.5);
+        assert_close(optimal.time_at(1.0 / 3.0), 29.0);
+        assert_close(optimal.time_at(0.5), 49.0);
+        assert_close(optimal.time_at(2.0 / 3.0), 69.0);
+        assert!(optimal.times.windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[test]
+    fn trend_is_positive_while_losing_time() {
+        let reference = trace([30.0, 30.0, 30.0]);
+        for (pace, losing) in [(1.05, true), (0.95, false)] {
+            let mut delta = LapDelta::new();
+            for k in 1..=120 {
+                let fraction = k as f32 * 0.005;
+                let time = reference.time_at(fraction) * pace;
+                let frame = TelemetryFrame {
+                    current_lap_time: Some(time),
+                    lap_fraction: fraction,
+                    ..TelemetryFrame::default()
+                };
+                delta.observe(time, &frame, Some(&reference));
+            }
+            let (d, trend) = (delta.delta().unwrap(), delta.trend().unwrap());
+            assert_eq!(d > 0.0, losing, "delta {}", d);
+            assert_eq!(trend > 0.0, losing, "trend {}", trend);
+            assert_close(trend, (pace - 1.0) / pace);
+        }
+    }
+}
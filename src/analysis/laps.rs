@@ -0,0 +1,416 @@
+use super::delta::{LapTrace, TraceBuilder};
+use crate::data::{SECTORS, Standings, TelemetryFrame, sector_times};
+
+/// Lap and sector times are reported in whole ms at best; times this close
+/// count as equal.
+const TIME_EPSILON: f64 = 0.0005;
+
+/// A lap the driver completed.
+#[derive(Clone, Debug)]
+pub struct Lap {
+    /// Lap number from 1.
+    pub number: i32,
+    /// Seconds.
+    pub time: f64,
+    /// Seconds, `None` for sectors whose start or end was missed.
+    pub sectors: [Option<f64>; SECTORS],
+    pub invalid: bool,
+    /// `None` unless the lap was followed from line to line.
+    pub trace: Option<LapTrace>,
+}
+
+/// How a lap or sector time compares, as coloured on timing screens.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Rating {
+    /// Fastest of anyone in the session (purple).
+    SessionBest,
+    /// The driver's own best (green).
+    PersonalBest,
+    /// Slower than the driver's best (yellow).
+    Slower,
+}
+
+/// Times the player's laps and sectors from the lap counter, the lap time
+/// and the sector index, which every sim reports, so splits work the same
+/// everywhere.
+///
+/// A sector ends when the sector index moves on: the time into the lap at
+/// that sample is its split. The last sector ends with the lap, whose time
+/// the sim reports as the last lap. Only valid laps count towards the bests.
+pub struct LapTimer {
+    laps: Vec<Lap>,
+    /// `completed_laps` of the lap being timed; `None` before the first sample.
+    completed_laps: Option<i32>,
+    sector: i32,
+    /// Time into the current lap at the end of each sector but the last.
+    splits: [Option<f64>; SECTORS - 1],
+    invalid: bool,
+    /// `None` for a lap joined halfway.
+    trace: Option<TraceBuilder>,
+    /// Laps that set the best time of each sector.
+    best_sector_laps: [Option<usize>; SECTORS],
+    best_lap: Option<usize>,
+    optimal_lap: Option<LapTrace>,
+    /// Bests of every car, from the standings.
+    session_sectors: [Option<f64>; SECTORS],
+    session_lap: Option<f64>,
+}
+
+impl LapTimer {
+    pub fn new() -> Self {
+        Self {
+            laps: Vec::new(),
+            completed_laps: None,
+            sector: 0,
+            splits: [None; SECTORS - 1],
+            invalid: false,
+            trace: None,
+            best_sector_laps: [None; SECTORS],
+            best_lap: None,
+            optimal_lap: None,
+            session_sectors: [None; SECTORS],
+            session_lap: None,
+        }
+    }
+
+    pub fn observe(&mut self, t: &TelemetryFrame) {
+        let Some(completed) = self.completed_laps else {
+            self.start_lap(t);
+            return;
+        };
+        // The counter only goes back in a new session (or a replay jump)
+        if t.completed_laps < completed {
+            *self = Self::new();
+            self.start_lap(t);
+            return;
+        }
+        if t.completed_laps > completed {
+            if t.completed_laps == completed + 1
+                && let Some(time) = t.last_lap_time
+            {
+                self.finish_lap(t.completed_laps, time);
+            }
+            self.start_lap(t);
+            // Validity is reset with the lap but may lag it by a sample
+            self.invalid = false;
+            self.trace = Some(TraceBuilder::new());
+            return;
+        }
+        if t.current_sector == self.sector + 1
+            && let Some(split) = self.splits.get_mut(self.sector as usize)
+        {
+            *split = t.current_lap_time;
+            if let Some(trace) = &mut self.trace {
+                trace.end_sector(self.sector as usize, t.lap_fraction);
+            }
+        }
+        self.sector = t.current_sector;
+        self.invalid |= t.lap_invalid;
+        if let Some(trace) = &mut self.trace
+            && let Some(time) = t.current_lap_time
+        {
+            trace.push(t.lap_fraction, time);
+        }
+    }
+
+    fn start_lap(&mut self, t: &TelemetryFrame) {
+        self.completed_laps = Some(t.completed_laps);
+        self.sector = t.current_sector;
+        self.splits = [None; SECTORS - 1];
+        self.invalid = t.lap_invalid;
+        self.trace = None;
+    }
+
+    fn finish_lap(&mut self, number: i32, time: f64) {
+        let lap = Lap {
+            number,
+            time,
+            sectors: sector_times(self.splits, Some(time)),
+            invalid: self.invalid,
+            trace: self.trace.take().and_then(|trace| trace.finish(time)),
+        };
+        let index = self.laps.len();
+        let best_sectors = self.best_sectors();
+        self.laps.push(lap);
+        let lap = &self.laps[index];
+        if lap.invalid {
+            return;
+        }
+        for (i, sector) in lap.sectors.into_iter().enumerate() {
+            if let Some(sector) = sector
+                && best_sectors[i].is_none_or(|b| sector < b)
+            {
+                self.best_sector_laps[i] = Some(index);
+            }
+        }
+        if self.best_lap().is_none_or(|b| time < b.time) {
+            self.best_lap = Some(index);
+        }
+        self.optimal_lap = self.build_optimal_lap();
+    }
+
+    /// The best sectors joined into one lap, from the traces of the laps
+    /// they were set on. Laps without a trace are left out.
+    fn build_optimal_lap(&self) -> Option<LapTrace> {
+        let mut parts = Vec::with_capacity(SECTORS);
+        for i in 0..SECTORS {
+            let best = self
+                .laps
+                .iter()
+                .filter(|lap| !lap.invalid)
+                .filter_map(|lap| Some((lap.trace.as_ref()?, lap.sectors[i]?)))
+                .min_by(|a, b| a.1.total_cmp(&b.1))?;
+            parts.push(best);
+        }
+        Some(LapTrace::join_sectors(&parts))
+    }
+
+    /// Takes the bests of the other cars from the standings, for rating
+    /// against the whole session rather than the player alone.
+    pub fn set_standings(&mut self, standings: &Standings) {
+        self.session_sectors = standings.best_sectors();
+        self.session_lap = standings.best_lap_time();
+    }
+
+    /// Every lap completed since the session started, oldest first.
+    pub fn laps(&self) -> &[Lap] {
+        &self.laps
+    }
+
+    pub fn last_lap(&self) -> Option<&Lap> {
+        self.laps.last()
+    }
+
+    /// Fastest valid lap.
+    pub fn best_lap(&self) -> Option<&Lap> {
+        self.best_lap.map(|i| &self.laps[i])
+    }
+
+    /// Fastest valid time in each sector.
+    pub fn best_sectors(&self) -> [Option<f64>; SECTORS] {
+        std::array::from_fn(|i| self.laps[self.best_sector_laps[i]?].sectors[i])
+    }
+
+    /// Trace of the fastest valid lap that has one.
+    pub fn best_trace(&self) -> Option<&LapTrace> {
+        self.laps
+            .iter()
+            .filter(|lap| !lap.invalid)
+            .filter_map(|lap| lap.trace.as_ref())
+            .min_by(|a, b| a.lap_time().total_cmp(&b.lap_time()))
+    }
+
+    /// The theoretical best lap: every best sector in one lap. `None` until
+    /// each best sector comes from a lap with a trace.
+    pub fn optimal_lap(&self) -> Option<&LapTrace> {
+        self.optimal_lap.as_ref()
+    }
+
+    /// Times of the sectors completed so far this lap.
+    pub fn current_sectors(&self) -> [Option<f64>; SECTORS] {
+        sector_times(self.splits, None)
+    }
+
+    /// Whether the current lap was invalidated.
+    pub fn invalid(&self) -> bool {
+        self.invalid
+    }
+
+    pub fn rate_sector(&self, sector: usize, time: f64) -> Rating {
+        rate(
+            time,
+            self.best_sectors()[sector],
+            self.session_sectors[sector],
+        )
+    }
+
+    pub fn rate_lap(&self, time: f64) -> Rating {
+        rate(time, self.best_lap().map(|l| l.time), self.session_lap)
+    }
+}
+
+impl Default for LapTimer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn rate(time: f64, personal_best: Option<f64>, session_best: Option<f64>) -> Rating {
+    let at_most = |best: Option<f64>| best.is_none_or(|b| time <= b + TIME_EPSILON);
+    if at_most(personal_best) && at_most(session_best) {
+        Rating::SessionBest
+    } else if at_most(personal_best) {
+        Rating::PersonalBest
+    } else {
+        Rating::Slower
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::CarStanding;
+
+    /// Samples per sector.
+    const STEPS: usize = 100;
+
+    /// Samples of one lap from the line, each sector driven at a constant
+    /// pace. `last_lap_time` is what the sim reports during the lap.
+    fn lap(
+        completed_laps: i32,
+        sectors: [f64; SECTORS],
+        last_lap_time: Option<f64>,
+    ) -> Vec<TelemetryFrame> {
+        (0..SECTORS * STEPS)
+            .map(|k| {
+                let (sector, step) = (k / STEPS, k % STEPS);
+                let start: f64 = sectors[..sector].iter().sum();
+                TelemetryFrame {
+                    completed_laps,
+                    current_sector: sector as i32,
+                    current_lap_time: Some(start + sectors[sector] * step as f64 / STEPS as f64),
+                    last_lap_time,
+                    lap_fraction: k as f32 / (SECTORS * STEPS) as f32,
+                    ..TelemetryFrame::default()
+                }
+            })
+            .collect()
+    }
+
+    /// Drives `laps` one after the other from the line of lap 1, then
+    /// crosses the line once more.
+    fn drive(timer: &mut LapTimer, laps: &[[f64; SECTORS]]) {
+        let mut last_lap_time = None;
+        for (i, sectors) in laps.iter().enumerate() {
+            for t in lap(i as i32, *sectors, last_lap_time) {
+                timer.observe(&t);
+            }
+            last_lap_time = Some(sectors.iter().sum());
+        }
+        timer.observe(&lap(laps.len() as i32, [30.0; SECTORS], last_lap_time)[0]);
+    }
+
+    fn assert_times(actual: [Option<f64>; SECTORS], expected: [Option<f64>; SECTORS]) {
+        for (a, e) in actual.into_iter().zip(expected) {
+            match (a, e) {
+                (Some(a), Some(e)) => {
+                    assert!((a - e).abs() < 1e-9, "{:?} != {:?}", actual, expected)
+                }
+                _ => assert_eq!(a, e),
+            }
+        }
+    }
+
+    #[test]
+    fn times_sectors_and_bests() {
+        let mut timer = LapTimer::new();
+        drive(
+            &mut timer,
+            &[[30.0, 40.0, 35.0], [29.0, 41.0, 34.5], [31.0, 39.0, 36.0]],
+        );
+
+        let laps = timer.laps();
+        assert_eq!(laps.iter().map(|l| l.number).collect::<Vec<_>>(), [1, 2, 3]);
+        assert_times(laps[1].sectors, [Some(29.0), Some(41.0), Some(34.5)]);
+        assert_eq!(timer.best_lap().map(|l| l.number), Some(2));
+        assert_times(timer.best_sectors(), [Some(29.0), Some(39.0), Some(34.5)]);
+        // The first lap was joined on the line, not followed to it
+        assert!(laps[0].trace.is_none() && laps[1].trace.is_some());
+        let optimal = timer.optimal_lap().expect("no optimal lap");
+        assert!((optimal.lap_time() - 102.5).abs() < 1e-9);
+
+        assert_eq!(timer.rate_sector(1, 39.0), Rating::SessionBest);
+        assert_eq!(timer.rate_sector(1, 39.0004), Rating::SessionBest);
+        assert_eq!(timer.rate_sector(1, 40.0), Rating::Slower);
+        assert_eq!(timer.rate_lap(104.5), Rating::SessionBest);
+
+        let faster = CarStanding {
+            best_lap_time: Some(104.0),
+            best_sectors: [Some(28.5), None, Some(35.0)],
+            ..CarStanding::default()
+        };
+        timer.set_standings(&Standings {
+            cars: vec![faster],
+            ..Standings::default()
+        });
+        assert_eq!(timer.rate_lap(104.5), Rating::PersonalBest);
+        assert_eq!(timer.rate_sector(0, 29.0), Rating::PersonalBest);
+        assert_eq!(timer.rate_sector(1, 39.0), Rating::SessionBest);
+        assert_eq!(timer.rate_sector(2, 34.5), Rating::SessionBest);
+    }
+
+    #[test]
+    fn invalid_laps_do_not_count_and_validity_may_lag() {
+        let mut timer = LapTimer::new();
+        let mut last_lap_time = None;
+        for (i, sectors) in [[30.0, 40.0, 35.0], [29.0, 39.0, 34.0], [30.5, 40.5, 35.5]]
+            .into_iter()
+            .enumerate()
+        {
+            for (k, mut t) in lap(i as i32, sectors, last_lap_time)
+                .into_iter()
+                .enumerate()
+            {
+                // Lap 2 goes off in the middle; the sim still reports it on
+                // the first sample of lap 3
+                t.lap_invalid = (i == 1 && k >= STEPS * 3 / 2) || (i == 2 && k == 0);
+                timer.observe(&t);
+                if i == 2 && k == 0 {
+                    assert!(!timer.invalid());
+                }
+            }
+            last_lap_time = Some(sectors.iter().sum());
+        }
+        timer.observe(&lap(3, [30.0; SECTORS], last_lap_time)[0]);
+
+        let invalid: Vec<_> = timer.laps().iter().map(|l| l.invalid).collect();
+        assert_eq!(invalid, [false, true, false]);
+        assert_eq!(timer.best_lap().map(|l| l.number), Some(1));
+        assert_times(timer.best_sectors(), [Some(30.0), Some(40.0), Some(35.0)]);
+    }
+
+    #[test]
+    fn joining_halfway_keeps_the_complete_sectors() {
+        let mut timer = LapTimer::new();
+        let first = lap(0, [30.0, 40.0, 35.0], None);
+        for t in &first[STEPS * 3 / 2..] {
+            timer.observe(t);
+        }
+        assert_times(timer.current_sectors(), [None, None, None]);
+        timer.observe(&lap(1, [30.0; SECTORS], Some(105.0))[0]);
+
+        let lap = timer.last_lap().expect("no lap");
+        assert_eq!(lap.time, 105.0);
+        assert_times(lap.sectors, [None, None, Some(35.0)]);
+        assert!(lap.trace.is_none());
+        assert_eq!(timer.best_lap().map(|l| l.number), Some(1));
+    }
+
+    #[test]
+    fn new_session_resets() {
+        let mut timer = LapTimer::new();
+        drive(&mut timer, &[[30.0, 40.0, 35.0], [29.0, 41.0, 34.5]]);
+        assert_eq!(timer.laps().len(), 2);
+
+        timer.observe(&lap(0, [30.0; SECTORS], None)[STEPS]);
+        assert!(timer.laps().is_empty());
+        assert!(timer.best_lap().is_none());
+        assert_times(timer.best_sectors(), [None; SECTORS]);
+        assert!(timer.optimal_lap().is_none());
+    }
+
+    #[test]
+    fn skipped_laps_are_not_timed() {
+        let mut timer = LapTimer::new();
+        drive(&mut timer, &[[30.0, 40.0, 35.0]]);
+        // A replay jump or a long stall: the counter moves on by two
+        for t in lap(3, [29.0, 39.0, 34.0], Some(106.0)) {
+            timer.observe(&t);
+        }
+        timer.observe(&lap(4, [30.0; SECTORS], Some(102.0))[0]);
+
+        let numbers: Vec<_> = timer.laps().iter().map(|l| l.number).collect();
+        assert_eq!(numbers, [1, 4]);
+        assert_eq!(timer.best_lap().map(|l| l.time), Some(102.0));
+    }
+}
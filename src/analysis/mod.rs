@@ -0,0 +1,8 @@
+mod calibration;
+pub use calibration::{GearResult, ShiftCalibration};
+mod shift_points;
+pub use shift_points::{GearCurve, ShiftAnalysis, ShiftPoint};
+mod laps;
+pub use laps::{Lap, LapTimer, Rating};
+mod delta;
+pub use delta::{DeltaReference, LapDelta, LapTrace, recorded_best};
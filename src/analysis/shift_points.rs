@@ -0,0 +1,266 @@
+use crate::sampler::Sample;
+use std::collections::BTreeMap;
+
+/// Only samples at (nearly) full throttle and off the brake count.
+const FULL_THROTTLE: f32 = 0.98;
+const MAX_BRAKE: f32 = 0.02;
+const MIN_SPEED_KMH: f32 = 20.0;
+/// Skip this long after a gear change, while the drivetrain settles.
+const SETTLE_SECONDS: f64 = 0.3;
+/// Samples this close to the limiter are cut by it and say nothing about the gear.
+const LIMITER_MARGIN_RPM: i32 = 100;
+/// Width of the speed buckets the acceleration curves are built from.
+const BIN_KMH: f32 = 2.0;
+const MIN_BIN_SAMPLES: usize = 5;
+const G: f32 = 9.81;
+
+/// Builds longitudinal acceleration vs speed curves per gear from full-throttle
+/// running and derives the upshift points from where they cross.
+pub struct ShiftAnalysis {
+    gears: BTreeMap<i32, GearData>,
+    last_gear: i32,
+    gear_since: f64,
+}
+
+#[derive(Default)]
+struct GearData {
+    /// Longitudinal acceleration in g, by speed bucket.
+    bins: BTreeMap<i32, Vec<f32>>,
+    rpm_per_kmh: Vec<f32>,
+    max_rpm: i32,
+}
+
+/// Median acceleration (g) against speed (km/h) in one forward gear.
+pub struct GearCurve {
+    pub gear: i32,
+    pub points: Vec<[f64; 2]>,
+}
+
+pub struct ShiftPoint {
+    /// Forward gear shifted from (1 = first).
+    pub gear: i32,
+    pub speed_kmh: f32,
+    pub rpm: i32,
+    /// False when the next gear never pulls harder in the recorded range,
+    /// so the shift is at the top of the gear instead.
+    pub crossover: bool,
+}
+
+impl ShiftAnalysis {
+    pub fn new() -> Self {
+        Self {
+            gears: BTreeMap::new(),
+            last_gear: 0,
+            gear_since: 0.0,
+        }
+    }
+
+    /// Number of samples that made it into the curves.
+    pub fn sample_count(&self) -> usize {
+        self.gears.values().map(|g| g.rpm_per_kmh.len()).sum()
+    }
+
+    pub fn observe(&mut self, sample: &Sample) {
+        let p = sample.frame.telemetry(0.0);
+        let gear = p.gear;
+        let (rpm, max_rpm) = (p.rpm as i32, p.max_rpm as i32);
+        let speed_kmh = p.speed_kmh();
+        // Time restarts with every session
+        if gear != self.last_gear || sample.t < self.gear_since {
+            self.last_gear = gear;
+            self.gear_since = sample.t;
+        }
+        let near_limiter = max_rpm > 0 && rpm >= max_rpm - LIMITER_MARGIN_RPM;
+        if gear < 1
+            || p.throttle < FULL_THROTTLE
+            || p.brake > MAX_BRAKE
+            || speed_kmh < MIN_SPEED_KMH
+            || p.tc.active
+            || near_limiter
+            || sample.t - self.gear_since < SETTLE_SECONDS
+        {
+            return;
+        }
+
+        let data = self.gears.entry(gear).or_default();
+        let bin = (speed_kmh / BIN_KMH) as i32;
+        data.bins
+            .entry(bin)
+            .or_default()
+            .push(p.acceleration[2] / G);
+        data.rpm_per_kmh.push(rpm as f32 / speed_kmh);
+        data.max_rpm = data.max_rpm.max(rpm);
+    }
+
+    pub fn curves(&self) -> Vec<GearCurve> {
+        self.gears
+            .iter()
+            .map(|(&gear, data)| GearCurve {
+                gear,
+                points: data
+                    .curve()
+                    .into_iter()
+                    .map(|(kmh, g)| [kmh as f64, g as f64])
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// Upshift point of every gear that has data for the next one as well.
+    pub fn shift_points(&self) -> Vec<ShiftPoint> {
+        let mut points = Vec::new();
+        for (&gear, data) in &self.gears {
+            let Some(next) = self.gears.get(&(gear + 1)) else {
+                continue;
+            };
+            let ratio = median(&mut data.rpm_per_kmh.clone());
+            if ratio <= 0.0 {
+                continue;
+            }
+            let current = data.curve();
+            let next: BTreeMap<_, _> = next
+                .curve()
+                .into_iter()
+                .map(|(kmh, g)| (kmh as i32, g))
+                .collect();
+
+            // Advantage of staying in this gear over the overlapping speed
+            // range; the shift is where it first turns negative
+            let mut previous: Option<(f32, f32)> = None;
+            let mut crossing = None;
+            for (kmh, g) in current {
+                let Some(&g_next) = next.get(&(kmh as i32)) else {
+                    continue;
+                };
+                let advantage = g - g_next;
+                if advantage <= 0.0 {
+                    crossing = Some(match previous {
+                        Some((kmh0, a0)) => kmh0 + (kmh - kmh0) * a0 / (a0 - advantage),
+                        None => kmh,
+                    });
+                    break;
+                }
+                previous = Some((kmh, advantage));
+            }
+
+            let top_rpm = data.max_rpm / 50 * 50;
+            let rpm = match crossing {
+                Some(kmh) => ((kmh * ratio / 50.0).round() as i32 * 50).min(top_rpm),
+                None => top_rpm,
+            };
+            points.push(ShiftPoint {
+                gear,
+                speed_kmh: rpm as f32 / ratio,
+                rpm,
+                crossover: crossing.is_some(),
+            });
+        }
+        points
+    }
+}
+
+impl Default for ShiftAnalysis {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GearData {
+    /// (bucket centre in km/h, median acceleration in g) for every bucket
+    /// with enough samples.
+    fn curve(&self) -> Vec<(f32, f32)> {
+        self.bins
+            .iter()
+            .filter(|(_, g)| g.len() >= MIN_BIN_SAMPLES)
+            .map(|(&bin, g)| ((bin as f32 + 0.5) * BIN_KMH, median(&mut g.clone())))
+            .collect()
+    }
+}
+
+fn median(values: &mut [f32]) -> f32 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.sort_by(f32::total_cmp);
+    values[values.len() / 2]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::TelemetryFrame;
+    use crate::source::Frame;
+
+    /// Full-throttle running in `gear` at `rpm_per_kmh`, five samples at the
+    /// centre of every speed bucket from `from_kmh` to `to_kmh` with the
+    /// acceleration (g) given by `accel`.
+    fn run_gear(
+        analysis: &mut ShiftAnalysis,
+        t: &mut f64,
+        gear: i32,
+        rpm_per_kmh: f32,
+        (from_kmh, to_kmh): (i32, i32),
+        accel: impl Fn(f32) -> f32,
+    ) {
+        let mut sample = |kmh: f32| {
+            let telemetry = TelemetryFrame {
+                gear,
+                throttle: 1.0,
+                speed: kmh / 3.6,
+                rpm: kmh * rpm_per_kmh,
+                acceleration: [0.0, 0.0, accel(kmh) * G],
+                ..TelemetryFrame::default()
+            };
+            let frame = Frame::from_telemetry(&telemetry, 0, "test", "car", "track");
+            analysis.observe(&Sample { t: *t, frame });
+            *t += 0.01;
+        };
+        // Settling after the gear change, too slow to count
+        for _ in 0..50 {
+            sample(10.0);
+        }
+        for kmh in (from_kmh..to_kmh).step_by(BIN_KMH as usize) {
+            for _ in 0..MIN_BIN_SAMPLES {
+                sample(kmh as f32 + 1.0);
+            }
+        }
+    }
+
+    #[test]
+    fn shifts_where_the_next_gear_pulls_harder() {
+        let mut analysis = ShiftAnalysis::new();
+        let mut t = 0.0;
+        run_gear(&mut analysis, &mut t, 1, 100.0, (20, 80), |kmh| {
+            1.0 - kmh / 100.0
+        });
+        run_gear(&mut analysis, &mut t, 2, 70.0, (40, 120), |kmh| {
+            0.6 - kmh / 250.0
+        });
+
+        // 1 - v/100 = 0.6 - v/250 at 66.7 km/h, 6667 rpm in 1st
+        let shifts = analysis.shift_points();
+        assert_eq!(shifts.len(), 1);
+        let shift = &shifts[0];
+        assert_eq!(shift.gear, 1);
+        assert!(shift.crossover);
+        assert_eq!(shift.rpm, 6650);
+        assert!((shift.speed_kmh - 66.5).abs() < 0.01, "{}", shift.speed_kmh);
+
+        let curves = analysis.curves();
+        assert_eq!(curves.len(), 2);
+        assert_eq!(curves[0].points.len(), 30);
+    }
+
+    #[test]
+    fn shifts_at_the_top_of_the_gear_without_a_crossing() {
+        let mut analysis = ShiftAnalysis::new();
+        let mut t = 0.0;
+        run_gear(&mut analysis, &mut t, 1, 100.0, (20, 60), |_| 1.0);
+        run_gear(&mut analysis, &mut t, 2, 70.0, (40, 120), |_| 0.5);
+
+        let shift = &analysis.shift_points()[0];
+        assert!(!shift.crossover);
+        // Top of 1st: 59 km/h
+        assert_eq!(shift.rpm, 5900);
+    }
+}
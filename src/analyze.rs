@@ -0,0 +1,159 @@
+use eframe::egui;
+use simtem::analysis::{GearCurve, ShiftAnalysis, ShiftPoint};
+use simtem::data::{self, CarDatabase};
+use simtem::paths;
+use simtem::recording::SessionReader;
+use simtem::widgets;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Runs the shift point analysis over recorded sessions, prints the result
+/// per car and opens a window with the tables and acceleration charts.
+pub fn run(files: &[PathBuf]) -> eframe::Result<()> {
+    let analyses: BTreeMap<_, _> = analyze(files)
+        .into_iter()
+        .map(|(car_model, analysis)| (car_model, CarAnalysis::new(&analysis)))
+        .collect();
+    if analyses.is_empty() {
+        eprintln!("No full-throttle running found in the given sessions");
+        std::process::exit(1);
+    }
+    for (car_model, analysis) in &analyses {
+        println!("{} ({} samples)", car_model, analysis.sample_count);
+        for s in &analysis.shifts {
+            println!(
+                "  {} > {}: {} RPM at {:.0} km/h{}",
+                s.gear,
+                s.gear + 1,
+                s.rpm,
+                s.speed_kmh,
+                if s.crossover { "" } else { " (top of gear)" }
+            );
+        }
+    }
+
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default().with_inner_size([900.0, 600.0]),
+        ..Default::default()
+    };
+    let selected = analyses.keys().next().cloned().unwrap_or_default();
+    eframe::run_native(
+        "SimTem shift analysis",
+        options,
+        Box::new(|_| {
+            Ok(Box::new(AnalyzeApp {
+                analyses,
+                selected,
+                cars: CarDatabase::load(paths::config_dir().join("cars")),
+                status: String::new(),
+            }))
+        }),
+    )
+}
+
+/// Feeds every sample of every session to the analysis of its car.
+fn analyze(files: &[PathBuf]) -> BTreeMap<String, ShiftAnalysis> {
+    let mut analyses: BTreeMap<String, ShiftAnalysis> = BTreeMap::new();
+    for path in files {
+        let mut reader = match SessionReader::open(path) {
+            Ok(reader) => reader,
+            Err(e) => {
+                eprintln!("Skipping {}: {}", path.display(), e);
+                continue;
+            }
+        };
+        let mut raw_model = [0u16; 33];
+        let mut car_model = String::new();
+        for i in 0..reader.chunks.len() {
+            let samples = match reader.decode_chunk(i) {
+                Ok(samples) => samples,
+                Err(e) => {
+                    eprintln!("{}: chunk {}: {}", path.display(), i, e);
+                    break;
+                }
+            };
+            for sample in &samples {
+                if sample.frame.statics.car_model != raw_model {
+                    raw_model = sample.frame.statics.car_model;
+                    car_model = data::parse_static_string(&raw_model);
+                }
+                if car_model.is_empty() {
+                    continue;
+                }
+                analyses
+                    .entry(car_model.clone())
+                    .or_default()
+                    .observe(sample);
+            }
+        }
+    }
+    analyses.retain(|_, a| a.sample_count() > 0);
+    analyses
+}
+
+/// Results of one car's analysis, worked out once rather than every frame.
+struct CarAnalysis {
+    sample_count: usize,
+    shifts: Vec<ShiftPoint>,
+    curves: Vec<GearCurve>,
+}
+
+impl CarAnalysis {
+    fn new(analysis: &ShiftAnalysis) -> Self {
+        Self {
+            sample_count: analysis.sample_count(),
+            shifts: analysis.shift_points(),
+            curves: analysis.curves(),
+        }
+    }
+}
+
+struct AnalyzeApp {
+    analyses: BTreeMap<String, CarAnalysis>,
+    selected: String,
+    cars: CarDatabase,
+    status: String,
+}
+
+impl eframe::App for AnalyzeApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        let Some(analysis) = self.analyses.get(&self.selected) else {
+            return;
+        };
+        let shifts = &analysis.shifts;
+
+        egui::SidePanel::left("shift_table_panel").show(ctx, |ui| {
+            egui::ComboBox::from_id_salt("car")
+                .selected_text(self.selected.as_str())
+                .show_ui(ui, |ui| {
+                    for car_model in self.analyses.keys() {
+                        ui.selectable_value(&mut self.selected, car_model.clone(), car_model);
+                    }
+                });
+            ui.label(format!("{} samples", analysis.sample_count));
+            ui.separator();
+
+            let profile = self.cars.get_by_name(&self.selected);
+            widgets::shift_table(ui, shifts, &|gear| {
+                profile.and_then(|p| p.upshift_rpm(gear))
+            });
+            ui.separator();
+
+            if ui
+                .add_enabled(!shifts.is_empty(), egui::Button::new("Save to profile"))
+                .clicked()
+            {
+                let table: Vec<_> = shifts.iter().map(|s| (s.gear, s.rpm)).collect();
+                self.status = match self.cars.save_upshift_rpm(&self.selected, &table) {
+                    Ok(path) => format!("Saved to {}", path.display()),
+                    Err(e) => format!("Saving failed: {}", e),
+                };
+            }
+            ui.label(&self.status);
+        });
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            widgets::shift_chart(ui, &analysis.curves, shifts);
+        });
+    }
+}
@@ -0,0 +1,501 @@
+//! Writes synthetic ACC shared memory files, so the full SHM pipeline can
+//! run on a machine without the sim or the Datalink bridge. With `--udp` it
+//! sends F1, Forza or OutGauge/OutSim UDP packets instead.
+
+use memmap2::MmapMut;
+use simtem::data::broadcast::{
+    BroadcastingEvent, CarEntry, DriverInfo, LapInfo, Message, RealtimeCarUpdate, RealtimeUpdate,
+    RegistrationResult, Request, TrackData,
+};
+use simtem::data::f1::{self, CarMotionData, CarStatusData, CarTelemetryData, Header, LapData};
+use simtem::data::forza::{self, Dash, Format, Sled};
+use simtem::data::outgauge::{self, OutGauge, OutSim};
+use simtem::data::{Graphics, Physics, ShmPage, Statics, TelemetryFrame};
+use simtem::source::SyntheticCar;
+use std::fs::OpenOptions;
+use std::net::{SocketAddr, UdpSocket};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const USAGE: &str = "Usage: simtem-fake [--dir <dir>] [--rate <hz>] [--broadcast <port>]
+       simtem-fake --udp <f1|forza|outgauge> [--target <addr>] [--rate <hz>]
+
+Options:
+  --dir <dir>       Where to create the acpmf_* files (default: $TMPDIR/simtem-fake-shm)
+  --udp <game>      Send F1 24, Forza (dash format) or OutGauge and OutSim
+                    UDP packets instead
+  --target <addr>   Where to send them (default 127.0.0.1 on the game's port)
+  --rate <hz>       Update rate (default 333, 60 for UDP)
+  --broadcast <port>
+                    Also answer as ACC's broadcasting API on 127.0.0.1:<port>,
+                    with a few AI cars around the synthetic one";
+
+/// Length of the made up F1 track, in metres.
+const F1_TRACK_LENGTH: u16 = 5000;
+
+#[derive(Copy, Clone)]
+enum Game {
+    F1,
+    Forza,
+    OutGauge,
+}
+
+fn main() {
+    let mut dir = std::env::temp_dir().join("simtem-fake-shm");
+    let mut rate_hz = None;
+    let mut game = None;
+    let mut target = None;
+    let mut broadcast_port = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match (arg.as_str(), args.next()) {
+            ("--dir", Some(value)) => dir = PathBuf::from(value),
+            ("--rate", Some(value)) => match value.parse::<f64>() {
+                Ok(hz) if hz >= 1.0 => rate_hz = Some(hz),
+                _ => exit_usage(),
+            },
+            ("--udp", Some(value)) => match value.as_str() {
+                "f1" => game = Some(Game::F1),
+                "forza" => game = Some(Game::Forza),
+                "outgauge" => game = Some(Game::OutGauge),
+                _ => exit_usage(),
+            },
+            ("--target", Some(value)) => match value.parse::<SocketAddr>() {
+                Ok(addr) => target = Some(addr),
+                _ => exit_usage(),
+            },
+            ("--broadcast", Some(value)) => match value.parse::<u16>() {
+                Ok(port) => broadcast_port = Some(port),
+                _ => exit_usage(),
+            },
+            _ => exit_usage(),
+        }
+    }
+
+    let result = match game {
+        Some(game) => {
+            let port = match game {
+                Game::F1 => f1::DEFAULT_PORT,
+                Game::Forza => forza::DEFAULT_PORT,
+                Game::OutGauge => outgauge::DEFAULT_OUTGAUGE_PORT,
+            };
+            let target = target.unwrap_or(SocketAddr::from(([127, 0, 0, 1], port)));
+            run_udp(game, target, rate_hz.unwrap_or(60.0))
+        }
+        None => run(&dir, rate_hz.unwrap_or(333.0), broadcast_port),
+    };
+    if let Err(e) = result {
+        eprintln!("simtem-fake: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run(dir: &Path, rate_hz: f64, broadcast_port: Option<u16>) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let mut physics = create::<Physics>(&dir.join("acpmf_physics"))?;
+    let mut graphics = create::<Graphics>(&dir.join("acpmf_graphics"))?;
+    let mut statics = create::<Statics>(&dir.join("acpmf_static"))?;
+    println!("Writing synthetic ACC telemetry to {}", dir.display());
+    let mut server = match broadcast_port {
+        Some(port) => {
+            println!(
+                "Run: simtem --shm-dir {} --broadcast {}",
+                dir.display(),
+                port
+            );
+            Some(FakeBroadcast::bind(port)?)
+        }
+        None => {
+            println!("Run: simtem --shm-dir {}", dir.display());
+            None
+        }
+    };
+
+    let mut car = SyntheticCar::new();
+    let period = Duration::from_secs_f64(1.0 / rate_hz);
+    let mut last = Instant::now();
+    let mut next = Instant::now();
+    loop {
+        car.advance(last.elapsed().as_secs_f64());
+        last = Instant::now();
+
+        let frame = car.frame();
+        statics.copy_from_slice(frame.statics.as_bytes());
+        graphics.copy_from_slice(frame.graphics.as_bytes());
+        physics.copy_from_slice(frame.physics.as_bytes());
+        if let Some(server) = &mut server {
+            server.poll(&frame.telemetry(0.0))?;
+        }
+
+        next += period;
+        if let Some(wait) = next.checked_duration_since(Instant::now()) {
+            std::thread::sleep(wait);
+        } else {
+            next = Instant::now();
+        }
+    }
+}
+
+fn run_udp(game: Game, target: SocketAddr, rate_hz: f64) -> std::io::Result<()> {
+    let socket = UdpSocket::bind(("0.0.0.0", 0))?;
+    println!("Sending synthetic telemetry to {}", target);
+    match game {
+        Game::F1 => println!("Run: simtem --sim f1 --udp {}", target.port()),
+        Game::Forza => println!("Run: simtem --sim forza --udp {}", target.port()),
+        // Both packets go to one port, which simtem accepts
+        Game::OutGauge => println!("Run: simtem --sim outgauge --udp {}", target.port()),
+    }
+
+    let mut car = SyntheticCar::new();
+    let period = Duration::from_secs_f64(1.0 / rate_hz);
+    let mut last = Instant::now();
+    let mut next = Instant::now();
+    let mut frame_id: u32 = 0;
+    loop {
+        car.advance(last.elapsed().as_secs_f64());
+        last = Instant::now();
+
+        let telemetry = car.frame().telemetry(0.0);
+        let packets = match game {
+            Game::F1 => f1_packets(&telemetry, frame_id),
+            Game::Forza => vec![forza_packet(&telemetry)],
+            Game::OutGauge => outgauge_packets(&telemetry),
+        };
+        for packet in packets {
+            socket.send_to(&packet, target)?;
+        }
+        frame_id = frame_id.wrapping_add(1);
+
+        next += period;
+        if let Some(wait) = next.checked_duration_since(Instant::now()) {
+            std::thread::sleep(wait);
+        } else {
+            next = Instant::now();
+        }
+    }
+}
+
+/// One F1 24 frame of a single car session: motion, lap data, telemetry and
+/// status every frame, session and participants about once a second.
+fn f1_packets(t: &TelemetryFrame, frame_id: u32) -> Vec<Vec<u8>> {
+    let packet = |packet_id: u8, body: &[u8]| {
+        let mut header = Header::zeroed();
+        header.packet_format = 2024;
+        header.packet_id = packet_id;
+        header.session_uid = 1;
+        header.frame_identifier = frame_id;
+        let mut bytes = header.as_bytes().to_vec();
+        bytes.extend_from_slice(body);
+        bytes
+    };
+    // Per car packets: the player in slot 0, the other slots unused
+    let cars = |car: &[u8]| {
+        let mut body = vec![0; car.len() * f1::MAX_CARS];
+        body[..car.len()].copy_from_slice(car);
+        body
+    };
+
+    let mut motion = CarMotionData::zeroed();
+    motion.g_force_lateral = t.acceleration[0] / 9.81;
+    motion.g_force_vertical = t.acceleration[1] / 9.81;
+    motion.g_force_longitudinal = t.acceleration[2] / 9.81;
+
+    let mut lap = LapData::zeroed();
+    lap.current_lap_time_ms = t.current_lap_time.map_or(0, |s| (s * 1000.0) as u32);
+    lap.last_lap_time_ms = t.last_lap_time.map_or(0, |s| (s * 1000.0) as u32);
+    lap.lap_distance = t.lap_fraction * F1_TRACK_LENGTH as f32;
+    lap.car_position = 1;
+    lap.current_lap_num = (t.completed_laps + 1) as u8;
+    lap.sector = t.current_sector as u8;
+    lap.driver_status = 4;
+    lap.result_status = 2;
+
+    let mut telemetry = CarTelemetryData::zeroed();
+    telemetry.speed = (t.speed * 3.6) as u16;
+    telemetry.throttle = t.throttle;
+    telemetry.brake = t.brake;
+    telemetry.steer = t.steering;
+    telemetry.clutch = (t.clutch * 100.0) as u8;
+    telemetry.gear = t.gear as i8;
+    telemetry.engine_rpm = t.rpm as u16;
+
+    let mut status = CarStatusData::zeroed();
+    status.traction_control = t.tc.level as u8;
+    status.anti_lock_brakes = t.abs.level as u8;
+    status.front_brake_bias = t.brake_bias.unwrap_or(0.0) as u8;
+    status.pit_limiter_status = t.pit_limiter_on as u8;
+    status.fuel_in_tank = t.fuel.unwrap_or(0.0);
+    status.max_rpm = t.max_rpm as u16;
+
+    let mut packets = vec![
+        packet(f1::PACKET_MOTION, &cars(motion.as_bytes())),
+        packet(f1::PACKET_LAP_DATA, &cars(lap.as_bytes())),
+        packet(f1::PACKET_CAR_TELEMETRY, &cars(telemetry.as_bytes())),
+        packet(f1::PACKET_CAR_STATUS, &cars(status.as_bytes())),
+    ];
+    if frame_id.is_multiple_of(60) {
+        let mut session = f1::SessionData::zeroed();
+        session.track_length = F1_TRACK_LENGTH;
+        // Race
+        session.session_type = 15;
+        packets.push(packet(f1::PACKET_SESSION, session.as_bytes()));
+
+        let mut participant = f1::ParticipantData::zeroed();
+        participant.name[..9].copy_from_slice(b"Synthetic");
+        let mut body = vec![1];
+        body.extend(cars(participant.as_bytes()));
+        packets.push(packet(f1::PACKET_PARTICIPANTS, &body));
+    }
+    packets
+}
+
+/// A Forza dash format packet.
+fn forza_packet(t: &TelemetryFrame) -> Vec<u8> {
+    let mut sled = Sled::zeroed();
+    sled.is_race_on = 1;
+    sled.engine_max_rpm = t.max_rpm;
+    sled.current_engine_rpm = t.rpm;
+    sled.acceleration = t.acceleration;
+    sled.velocity = [0.0, 0.0, t.speed];
+
+    let mut dash = Dash::zeroed();
+    dash.speed = t.speed;
+    dash.accel = (t.throttle * 255.0) as u8;
+    dash.brake = (t.brake * 255.0) as u8;
+    dash.clutch = (t.clutch * 255.0) as u8;
+    dash.steer = (t.steering * 127.0) as i8;
+    dash.gear = match t.gear {
+        -1 => 0,
+        0 => 11,
+        g => g as u8,
+    };
+    dash.fuel = t.fuel_percent.unwrap_or(0.0) / 100.0;
+    dash.lap_number = t.completed_laps as u16;
+    dash.current_lap = t.current_lap_time.unwrap_or(0.0) as f32;
+    dash.last_lap = t.last_lap_time.unwrap_or(0.0) as f32;
+    dash.best_lap = t.best_lap_time.unwrap_or(0.0) as f32;
+
+    forza::Packet {
+        format: Format::Dash,
+        sled,
+        dash: Some(dash),
+    }
+    .encode(Format::Dash)
+}
+
+fn create<T: ShmPage>(path: &Path) -> std::io::Result<MmapMut> {
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)?;
+    file.set_len(size_of::<T>() as u64)?;
+    unsafe { MmapMut::map_mut(&file) }
+}
+
+fn exit_usage() -> ! {
+    eprintln!("{}", USAGE);
+    std::process::exit(2);
+}
+
+/// An OutGauge and an OutSim packet, the car driving north on flat ground.
+fn outgauge_packets(t: &TelemetryFrame) -> Vec<Vec<u8>> {
+    let mut gauge = OutGauge::zeroed();
+    gauge.car = *b"SYN\0";
+    gauge.gear = (t.gear + 1) as u8;
+    gauge.speed = t.speed;
+    gauge.rpm = t.rpm;
+    gauge.fuel = t.fuel_percent.unwrap_or(0.0) / 100.0;
+    gauge.show_lights = outgauge::DL_TC | outgauge::DL_ABS | outgauge::DL_PITSPEED;
+    gauge.dash_lights = (t.tc.active as u32 * outgauge::DL_TC)
+        | (t.abs.active as u32 * outgauge::DL_ABS)
+        | (t.pit_limiter_on as u32 * outgauge::DL_PITSPEED);
+    gauge.throttle = t.throttle;
+    gauge.brake = t.brake;
+    gauge.clutch = t.clutch;
+
+    let mut sim = OutSim::zeroed();
+    let [lateral, vertical, longitudinal] = t.acceleration;
+    sim.acceleration = [lateral, longitudinal, vertical];
+
+    vec![gauge.as_bytes().to_vec(), sim.as_bytes().to_vec()]
+}
+
+/// Car model id (as in ACC's car table), driver and lap time of the AI cars
+/// of the fake broadcasting server.
+const AI_CARS: [(u8, &str, f32); 5] = [
+    (30, "Ada Lovelace", 101.0),
+    (2, "Bruce McLaren", 102.5),
+    (61, "Clay Regazzoni", 109.0),
+    (50, "Denise McCluggage", 111.5),
+    (60, "Emerson Fittipaldi", 113.0),
+];
+const PLAYER_CAR_MODEL: u8 = 53;
+
+/// Just enough of ACC's broadcasting API to drive a client: registration,
+/// entry list, track data and realtime updates at the requested interval.
+/// The synthetic car is car 0, the AI cars lap at constant speed.
+struct FakeBroadcast {
+    socket: UdpSocket,
+    client: Option<(SocketAddr, Duration)>,
+    start: Instant,
+    last_update: Instant,
+    /// Completed laps seen per car, for the lap completed events.
+    laps: Vec<u16>,
+}
+
+impl FakeBroadcast {
+    fn bind(port: u16) -> std::io::Result<Self> {
+        let socket = UdpSocket::bind(("127.0.0.1", port))?;
+        socket.set_nonblocking(true)?;
+        Ok(Self {
+            socket,
+            client: None,
+            start: Instant::now(),
+            last_update: Instant::now(),
+            laps: vec![0; AI_CARS.len() + 1],
+        })
+    }
+
+    fn poll(&mut self, player: &TelemetryFrame) -> std::io::Result<()> {
+        let mut buf = [0; 1024];
+        loop {
+            let (len, from) = match self.socket.recv_from(&mut buf) {
+                Ok(received) => received,
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            };
+            match Request::parse(&buf[..len]) {
+                Some(Request::Register {
+                    update_interval_ms, ..
+                }) => {
+                    println!("Broadcasting client registered from {}", from);
+                    let interval = Duration::from_millis(update_interval_ms.max(10) as u64);
+                    self.client = Some((from, interval));
+                    self.send(Message::RegistrationResult(RegistrationResult {
+                        connection_id: 1,
+                        success: true,
+                        read_only: true,
+                        error: String::new(),
+                    }))?;
+                }
+                Some(Request::EntryList { connection_id }) => {
+                    self.send(Message::EntryList {
+                        connection_id,
+                        car_indices: (0..=AI_CARS.len() as u16).collect(),
+                    })?;
+                    for (index, &(model, name, _)) in AI_CARS.iter().enumerate() {
+                        self.send(Message::EntryListCar(entry(index as u16 + 1, model, name)))?;
+                    }
+                    self.send(Message::EntryListCar(entry(
+                        0,
+                        PLAYER_CAR_MODEL,
+                        "Synthetic Driver",
+                    )))?;
+                }
+                Some(Request::TrackData { connection_id }) => {
+                    self.send(Message::TrackData(TrackData {
+                        connection_id,
+                        name: "synthetic".to_string(),
+                        id: 0,
+                        length: 4840,
+                    }))?;
+                }
+                Some(Request::Unregister { .. }) => self.client = None,
+                None => {}
+            }
+        }
+
+        let Some((_, interval)) = self.client else {
+            return Ok(());
+        };
+        if self.last_update.elapsed() < interval {
+            return Ok(());
+        }
+        self.last_update = Instant::now();
+
+        // (car index, laps, spline position, last lap in ms)
+        let elapsed = self.start.elapsed().as_secs_f32();
+        let mut cars = vec![(
+            0,
+            player.completed_laps as u16,
+            player.lap_fraction,
+            player.last_lap_time.map(|s| (s * 1000.0) as i32),
+        )];
+        for (index, &(_, _, lap_time)) in AI_CARS.iter().enumerate() {
+            // Staggered as on a rolling start, ahead of the player
+            let distance = elapsed / lap_time + 0.05 * (index + 1) as f32;
+            let laps = distance as u16;
+            let last = (laps > 0).then_some((lap_time * 1000.0) as i32);
+            cars.push((index as u16 + 1, laps, distance.fract(), last));
+        }
+        let mut order: Vec<usize> = (0..cars.len()).collect();
+        order.sort_by(|&a, &b| {
+            let distance = |i: usize| cars[i].1 as f32 + cars[i].2;
+            distance(b).total_cmp(&distance(a))
+        });
+
+        self.send(Message::RealtimeUpdate(RealtimeUpdate {
+            session_type: 10,
+            phase: 5,
+            session_time: elapsed * 1000.0,
+            focused_car_index: 0,
+            ..RealtimeUpdate::default()
+        }))?;
+        for (position, &i) in order.iter().enumerate() {
+            let (car_index, laps, spline_position, last) = cars[i];
+            let lap = |lap_time_ms| LapInfo {
+                lap_time_ms,
+                car_index,
+                ..LapInfo::default()
+            };
+            self.send(Message::RealtimeCarUpdate(RealtimeCarUpdate {
+                car_index,
+                driver_count: 1,
+                car_location: 1,
+                position: position as u16 + 1,
+                track_position: position as u16 + 1,
+                spline_position,
+                laps,
+                best_session_lap: lap(last),
+                last_lap: lap(last),
+                current_lap: lap(None),
+                ..RealtimeCarUpdate::default()
+            }))?;
+            if laps > self.laps[car_index as usize] {
+                self.laps[car_index as usize] = laps;
+                self.send(Message::BroadcastingEvent(BroadcastingEvent {
+                    kind: 5,
+                    message: "Lap completed".to_string(),
+                    time_ms: (elapsed * 1000.0) as i32,
+                    car_index: car_index as i32,
+                }))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn send(&self, message: Message) -> std::io::Result<()> {
+        if let Some((client, _)) = self.client {
+            self.socket.send_to(&message.encode(), client)?;
+        }
+        Ok(())
+    }
+}
+
+fn entry(car_index: u16, car_model_type: u8, name: &str) -> CarEntry {
+    let (first_name, last_name) = name.split_once(' ').unwrap_or((name, ""));
+    CarEntry {
+        car_index,
+        car_model_type,
+        race_number: car_index as i32 + 1,
+        drivers: vec![DriverInfo {
+            first_name: first_name.to_string(),
+            last_name: last_name.to_string(),
+            short_name: last_name.chars().take(3).collect::<String>().to_uppercase(),
+            ..DriverInfo::default()
+        }],
+        ..CarEntry::default()
+    }
+}
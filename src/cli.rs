@@ -0,0 +1,174 @@
+use simtem::sampler::DEFAULT_RATE_HZ;
+use simtem::source::SHM_DIR;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+
+const USAGE: &str =
+    "Usage: simtem [--rate <hz>] [--replay <file> | --synthetic | [--sim <sim>] [--shm-dir <dir>]
+                     [--udp <addr>] [--outsim <addr>]
+                     [--broadcast <addr>] [--broadcast-password <password>]]
+       simtem --analyze <file>...
+
+Options:
+  --rate <hz>       Telemetry sampling rate (default 333)
+  --replay <file>   Play back a recorded session instead of the live sim
+  --synthetic       Drive the overlay from a built-in simulated car
+  --sim <sim>       Live sim to read: acc (ACC or Assetto Corsa, default) or
+                    rf2 (rFactor 2 or Le Mans Ultimate) or pcars2
+                    (Project CARS 2 or Automobilista 2) or f1 (F1 23/24
+                    UDP) or forza (Forza Data Out UDP) or outgauge
+                    (OutGauge/OutSim UDP: Live for Speed, BeamNG.drive)
+  --shm-dir <dir>   Read the shared memory files from <dir> (default /dev/shm)
+  --udp <addr>      Address or port to receive UDP telemetry on (default
+                    0.0.0.0:20777 for f1, 0.0.0.0:5300 for forza,
+                    0.0.0.0:4444 for outgauge)
+  --outsim <addr>   Address or port to receive OutSim on (default 0.0.0.0:4123)
+  --broadcast <addr>
+                    With acc: also read every car's standings from ACC's
+                    broadcasting API at <addr>, or a port on 127.0.0.1
+                    (ACC's broadcasting.json sets the port, e.g. 9000)
+  --broadcast-password <password>
+                    connectionPassword of broadcasting.json (default asd)
+  --analyze <file>...
+                    Work out the optimal shift points from recorded sessions
+  -h, --help        Show this help";
+
+/// The live telemetry backend.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Sim {
+    Acc,
+    Rf2,
+    Pcars2,
+    F1,
+    Forza,
+    OutGauge,
+}
+
+pub struct Args {
+    pub rate_hz: f64,
+    pub replay: Option<PathBuf>,
+    pub synthetic: bool,
+    pub sim: Sim,
+    pub shm_dir: PathBuf,
+    /// Where the UDP backends listen, if not on their default port.
+    pub udp: Option<SocketAddr>,
+    pub outsim: Option<SocketAddr>,
+    /// ACC's broadcasting API, when enabled.
+    pub broadcast: Option<SocketAddr>,
+    pub broadcast_password: String,
+    pub analyze: Option<Vec<PathBuf>>,
+}
+
+impl Args {
+    /// Parses the process arguments, exiting with usage on error or `--help`.
+    pub fn parse() -> Self {
+        match Self::try_parse(std::env::args().skip(1)) {
+            Ok(args) => args,
+            Err(e) => {
+                if !e.is_empty() {
+                    eprintln!("{}\n", e);
+                }
+                eprintln!("{}", USAGE);
+                std::process::exit(if e.is_empty() { 0 } else { 2 });
+            }
+        }
+    }
+
+    fn try_parse(args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Self {
+            rate_hz: DEFAULT_RATE_HZ,
+            replay: None,
+            synthetic: false,
+            sim: Sim::Acc,
+            shm_dir: PathBuf::from(SHM_DIR),
+            udp: None,
+            outsim: None,
+            broadcast: None,
+            broadcast_password: "asd".to_string(),
+            analyze: None,
+        };
+        let mut args = args.peekable();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--rate" => {
+                    parsed.rate_hz = value(&mut args, &arg)?
+                        .parse()
+                        .ok()
+                        .filter(|hz: &f64| *hz >= 1.0 && *hz <= 1000.0)
+                        .ok_or("--rate must be between 1 and 1000")?;
+                }
+                "--replay" => parsed.replay = Some(value(&mut args, &arg)?.into()),
+                "--synthetic" => parsed.synthetic = true,
+                "--sim" => {
+                    parsed.sim = match value(&mut args, &arg)?.as_str() {
+                        "acc" => Sim::Acc,
+                        "rf2" => Sim::Rf2,
+                        "pcars2" | "ams2" => Sim::Pcars2,
+                        "f1" => Sim::F1,
+                        "forza" => Sim::Forza,
+                        "outgauge" | "lfs" | "beamng" => Sim::OutGauge,
+                        other => return Err(format!("Unknown sim '{}'", other)),
+                    }
+                }
+                "--shm-dir" => parsed.shm_dir = value(&mut args, &arg)?.into(),
+                "--udp" => parsed.udp = Some(socket_addr(&value(&mut args, &arg)?, &arg)?),
+                "--outsim" => parsed.outsim = Some(socket_addr(&value(&mut args, &arg)?, &arg)?),
+                "--broadcast" => {
+                    let addr = value(&mut args, &arg)?;
+                    parsed.broadcast = Some(match addr.parse::<u16>() {
+                        Ok(port) => SocketAddr::from((Ipv4Addr::LOCALHOST, port)),
+                        Err(_) => socket_addr(&addr, &arg)?,
+                    });
+                }
+                "--broadcast-password" => parsed.broadcast_password = value(&mut args, &arg)?,
+                "--analyze" => {
+                    let files = parsed.analyze.get_or_insert_with(Vec::new);
+                    while let Some(file) = args.next_if(|a| !a.starts_with('-')) {
+                        files.push(file.into());
+                    }
+                    if files.is_empty() {
+                        return Err("--analyze needs at least one session file".to_string());
+                    }
+                }
+                "-h" | "--help" => return Err(String::new()),
+                other => return Err(format!("Unknown argument '{}'", other)),
+            }
+        }
+        if parsed.replay.is_some() && parsed.synthetic {
+            return Err("--replay and --synthetic are mutually exclusive".to_string());
+        }
+        if parsed.broadcast.is_some() && parsed.sim != Sim::Acc {
+            return Err("--broadcast only works with --sim acc".to_string());
+        }
+        if parsed.analyze.is_some() && (parsed.replay.is_some() || parsed.synthetic) {
+            return Err("--analyze cannot be combined with --replay or --synthetic".to_string());
+        }
+        Ok(parsed)
+    }
+
+    /// Where the UDP backend of `sim` listens.
+    pub fn udp_addr(&self, default_port: u16) -> SocketAddr {
+        self.udp
+            .unwrap_or(SocketAddr::from((Ipv4Addr::UNSPECIFIED, default_port)))
+    }
+
+    /// Where OutSim is received.
+    pub fn outsim_addr(&self, default_port: u16) -> SocketAddr {
+        self.outsim
+            .unwrap_or(SocketAddr::from((Ipv4Addr::UNSPECIFIED, default_port)))
+    }
+}
+
+/// `addr:port`, or only a port to listen on every interface.
+fn socket_addr(addr: &str, flag: &str) -> Result<SocketAddr, String> {
+    match addr.parse::<u16>() {
+        Ok(port) => Ok(SocketAddr::from((Ipv4Addr::UNSPECIFIED, port))),
+        Err(_) => addr
+            .parse()
+            .map_err(|_| format!("Invalid {} address '{}'", flag, addr)),
+    }
+}
+
+fn value(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<String, String> {
+    args.next().ok_or_else(|| format!("{} needs a value", flag))
+}
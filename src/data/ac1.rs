@@ -0,0 +1,241 @@
+//! Shared memory layout of the original Assetto Corsa (SM 1.5 to 1.7).
+//!
+//! The physics and static pages are the prefix ACC later extended, so they
+//! convert by copying that prefix; the graphics page has a different tail.
+//! Converted frames keep AC's `_sm_version`, so consumers can tell them apart.
+
+use super::ShmPage;
+use std::mem::{offset_of, size_of};
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct Physics {
+    pub _packet_id: i32,
+    pub gas: f32,
+    pub brake: f32,
+    pub fuel: f32,
+    pub gear: i32,
+    pub rpms: i32,
+    pub _steer_angle: f32,
+    pub speed_kmh: f32,
+    pub _velocity: [f32; 3],
+    pub _acc_g: [f32; 3],
+    pub _wheel_slip: [f32; 4],
+    pub _wheel_load: [f32; 4],
+    pub _wheels_pressure: [f32; 4],
+    pub _wheel_angular_speed: [f32; 4],
+    pub _tyre_wear: [f32; 4],
+    pub _tyre_dirty_level: [f32; 4],
+    pub _tyre_core_temp: [f32; 4],
+    pub _camber_rad: [f32; 4],
+    pub _suspension_travel: [f32; 4],
+    /// A float in AC, an int in ACC.
+    pub _drs: f32,
+    pub tc: f32,
+    pub _heading: f32,
+    pub _pitch: f32,
+    pub _roll: f32,
+    pub _cg_height: f32,
+    pub _car_damage: [f32; 5],
+    pub _number_of_tyres_out: i32,
+    pub _pit_limiter_on: i32,
+    pub abs: f32,
+    pub _kers_charge: f32,
+    pub _kers_input: f32,
+    pub _auto_shifter_on: i32,
+    pub _ride_height: [f32; 2],
+    pub _turbo_boost: f32,
+    pub _ballast: f32,
+    pub _air_density: f32,
+    pub _air_temp: f32,
+    pub _road_temp: f32,
+    pub _local_angular_vel: [f32; 3],
+    pub _final_ff: f32,
+    pub _performance_meter: f32,
+    pub _engine_brake: i32,
+    pub _ers_recovery_level: i32,
+    pub _ers_power_level: i32,
+    pub _ers_heat_charging: i32,
+    pub _ers_is_charging: i32,
+    pub _kers_current_kj: f32,
+    pub _drs_available: i32,
+    pub _drs_enabled: i32,
+    pub _brake_temp: [f32; 4],
+    pub _clutch: f32,
+    pub _tyre_temp_i: [f32; 4],
+    pub _tyre_temp_m: [f32; 4],
+    pub _tyre_temp_o: [f32; 4],
+    pub _is_ai_controlled: i32,
+    pub _tyre_contact_point: [[f32; 3]; 4],
+    pub _tyre_contact_normal: [[f32; 3]; 4],
+    pub _tyre_contact_heading: [[f32; 3]; 4],
+    pub brake_bias: f32,
+    pub _local_velocity: [f32; 3],
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct Graphics {
+    pub _packet_id: i32,
+    pub _status: i32,
+    pub _session_type: i32,
+    pub _current_time: [u16; 15],
+    pub _last_time: [u16; 15],
+    pub _best_time: [u16; 15],
+    pub _split: [u16; 15],
+    pub _completed_laps: i32,
+    pub _position: i32,
+    pub i_current_time: i32,
+    pub _i_last_time: i32,
+    pub _i_best_time: i32,
+    pub _session_time_left: f32,
+    pub _distance_traveled: f32,
+    pub _is_in_pit: i32,
+    pub current_sector_index: i32,
+    pub _last_sector_time: i32,
+    pub _number_of_laps: i32,
+    pub _tyre_compound: [u16; 33],
+    pub _padding_1: u16,
+    pub _replay_time_multiplier: f32,
+    pub normalized_car_position: f32,
+    pub _car_coordinates: [f32; 3],
+    pub _penalty_time: f32,
+    pub _flag: i32,
+    pub _ideal_line_on: i32,
+    pub _is_in_pit_lane: i32,
+    pub _surface_grip: f32,
+    pub _mandatory_pit_done: i32,
+    pub _wind_speed: f32,
+    pub _wind_direction: f32,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct Statics {
+    pub _sm_version: [u16; 15],
+    pub _ac_version: [u16; 15],
+    pub _number_of_sessions: i32,
+    pub _num_cars: i32,
+    pub car_model: [u16; 33],
+    pub _track: [u16; 33],
+    pub _player_name: [u16; 33],
+    pub _player_surname: [u16; 33],
+    pub _player_nick: [u16; 33],
+    pub _padding_1: u16,
+    pub _sector_count: i32,
+    pub _max_torque: f32,
+    pub _max_power: f32,
+    pub _max_rpm: i32,
+    pub _max_fuel: f32,
+    pub _suspension_max_travel: [f32; 4],
+    pub _tyre_radius: [f32; 4],
+    pub _max_turbo_boost: f32,
+    pub _deprecated_1: f32,
+    pub _deprecated_2: f32,
+    pub _penalty_enabled: i32,
+    pub _aid_fuel_rate: f32,
+    pub _aid_tyre_rate: f32,
+    pub _aid_mechanical_damage: f32,
+    pub _allow_tyre_blankets: i32,
+    pub _aid_stability: f32,
+    pub _aid_auto_clutch: i32,
+    pub _aid_auto_blip: i32,
+    pub _has_drs: i32,
+    pub _has_ers: i32,
+    pub _has_kers: i32,
+    pub _kers_max_j: f32,
+    pub _engine_brake_settings_count: i32,
+    pub _ers_power_controller_count: i32,
+    pub _track_spline_length: f32,
+    pub _track_configuration: [u16; 33],
+    pub _padding_2: u16,
+    pub _ers_max_j: f32,
+    pub _is_timed_race: i32,
+    pub _has_extra_lap: i32,
+    pub _car_skin: [u16; 33],
+    pub _padding_3: u16,
+    pub _reversed_grid_positions: i32,
+    pub _pit_window_start: i32,
+    pub _pit_window_end: i32,
+}
+
+// SPageFilePhysics / SPageFileGraphic / SPageFileStatic as shipped with AC 1.16
+const _: () = assert!(size_of::<Physics>() == 580);
+const _: () = assert!(size_of::<Graphics>() == 296);
+const _: () = assert!(size_of::<Statics>() == 684);
+// The prefix shared with ACC
+const _: () = assert!(offset_of!(Physics, brake_bias) == offset_of!(super::Physics, brake_bias));
+const _: () =
+    assert!(offset_of!(Physics, _local_velocity) == offset_of!(super::Physics, _local_velocity));
+const _: () = assert!(offset_of!(Statics, _max_rpm) == offset_of!(super::Statics, _max_rpm));
+const _: () =
+    assert!(offset_of!(Statics, _pit_window_end) == offset_of!(super::Statics, _pit_window_end));
+
+unsafe impl ShmPage for Physics {}
+unsafe impl ShmPage for Graphics {}
+unsafe impl ShmPage for Statics {}
+
+/// `_sm_version` values of AC releases.
+pub const SM_VERSIONS: [&str; 3] = ["1.5", "1.6", "1.7"];
+
+impl Physics {
+    pub fn to_acc(&self, statics: &Statics) -> super::Physics {
+        let mut acc = super::Physics::zeroed();
+        acc.as_bytes_mut()[..size_of::<Self>()].copy_from_slice(self.as_bytes());
+        acc._drs = (self._drs > 0.0) as i32;
+        // AC has no ignition or live rev limit
+        acc.current_max_rpm = statics._max_rpm;
+        acc.ignition_on = 1;
+        acc._is_engine_running = (self.rpms > 0) as i32;
+        acc
+    }
+}
+
+impl Graphics {
+    pub fn to_acc(&self) -> super::Graphics {
+        let mut acc = super::Graphics::zeroed();
+        acc._packet_id = self._packet_id;
+        acc._status = self._status;
+        acc._session_type = self._session_type;
+        acc._current_time = self._current_time;
+        acc._last_time = self._last_time;
+        acc._best_time = self._best_time;
+        acc._split = self._split;
+        acc._completed_laps = self._completed_laps;
+        acc._position = self._position;
+        acc.i_current_time = self.i_current_time;
+        acc._i_last_time = self._i_last_time;
+        acc._i_best_time = self._i_best_time;
+        acc._session_time_left = self._session_time_left;
+        acc._distance_traveled = self._distance_traveled;
+        acc._is_in_pit = self._is_in_pit;
+        acc.current_sector_index = self.current_sector_index;
+        acc._last_sector_time = self._last_sector_time;
+        // AC does not export lap validity
+        acc.is_valid_lap = 1;
+        acc._number_of_laps = self._number_of_laps;
+        acc._tyre_compound = self._tyre_compound;
+        acc._replay_time_multiplier = self._replay_time_multiplier;
+        acc.normalized_car_position = self.normalized_car_position;
+        // AC only exports the player's car
+        acc._active_cars = 1;
+        acc._car_coordinates[0] = self._car_coordinates;
+        acc._penalty_time = self._penalty_time;
+        acc._flag = self._flag;
+        acc._ideal_line_on = self._ideal_line_on;
+        acc._is_in_pit_lane = self._is_in_pit_lane;
+        acc._surface_grip = self._surface_grip;
+        acc._mandatory_pit_done = self._mandatory_pit_done;
+        acc._wind_speed = self._wind_speed;
+        acc._wind_direction = self._wind_direction;
+        acc
+    }
+}
+
+impl Statics {
+    pub fn to_acc(&self) -> super::Statics {
+        let mut acc = super::Statics::zeroed();
+        acc.as_bytes_mut()[..size_of::<Self>()].copy_from_slice(self.as_bytes());
+        acc
+    }
+}
@@ -0,0 +1,182 @@
+//! ACC adapter: maps the shared memory pages, decoded as a [`Snapshot`], onto
+//! [`TelemetryFrame`], and back for the other sims, whose frames are carried
+//! in the ACC layout.
+
+use super::telemetry::{DriverAid, TelemetryFrame};
+use super::{Graphics, Physics, ShmPage, Snapshot, Statics};
+
+/// What ACC reports for laps without a time yet.
+const NO_TIME: i32 = i32::MAX;
+const G: f32 = 9.81;
+/// Written for enums the sim does not report. Reads back as `None`, except
+/// for the session type, where ACC itself uses -1 for `SessionType::Unknown`.
+const UNKNOWN: i32 = -1;
+/// Written to `_max_fuel` for sims that only report how full the tank is;
+/// `fuel` then holds the percentage instead of litres.
+const FUEL_IN_PERCENT: f32 = -1.0;
+
+impl TelemetryFrame {
+    /// `bb_offset` is the car's brake bias offset from its profile: ACC
+    /// reports the raw bias, which is off from the value shown in the car.
+    pub fn from_acc(p: &Physics, g: &Graphics, s: &Statics, bb_offset: f32) -> Self {
+        let snapshot = Snapshot::new(p, g);
+        let (fuel, fuel_percent) = if s._max_fuel == FUEL_IN_PERCENT {
+            (None, Some(snapshot.fuel))
+        } else {
+            let percent = (s._max_fuel > 0.0).then(|| snapshot.fuel / s._max_fuel * 100.0);
+            (Some(snapshot.fuel), percent)
+        };
+        Self {
+            status: snapshot.status,
+            session_type: snapshot.session_type,
+            flag: snapshot.flag,
+            penalty: snapshot.penalty,
+            track_grip: snapshot.track_grip,
+            rain: snapshot.rain,
+            rain_in_10min: snapshot.rain_in_10min,
+            rain_in_30min: snapshot.rain_in_30min,
+            in_pit: snapshot.in_pit,
+            in_pit_lane: snapshot.in_pit_lane,
+
+            throttle: snapshot.gas,
+            brake: snapshot.brake,
+            clutch: p._clutch,
+            steering: p._steer_angle,
+            gear: snapshot.gear,
+            speed: snapshot.speed_kmh / 3.6,
+            rpm: snapshot.rpm as f32,
+            max_rpm: if snapshot.max_rpm > 0 {
+                snapshot.max_rpm
+            } else {
+                s._max_rpm
+            } as f32,
+            fuel,
+            fuel_percent,
+            brake_bias: (snapshot.brake_bias > 0.0)
+                .then_some(snapshot.brake_bias * 100.0 + bb_offset),
+            acceleration: p._acc_g.map(|a| a * G),
+            orientation: [p._heading, p._pitch, p._roll],
+            tc: DriverAid {
+                level: snapshot.tc_level,
+                active: snapshot.tc_active,
+            },
+            abs: DriverAid {
+                level: snapshot.abs_level,
+                active: snapshot.abs_active,
+            },
+            ignition_on: snapshot.ignition_on,
+            engine_running: p._is_engine_running == 1,
+            pit_limiter_on: p._pit_limiter_on == 1,
+
+            completed_laps: g._completed_laps,
+            current_lap_time: lap_time(g.i_current_time),
+            last_lap_time: lap_time(g._i_last_time),
+            best_lap_time: lap_time(g._i_best_time),
+            current_sector: g.current_sector_index,
+            lap_invalid: g.is_valid_lap == 0,
+            lap_fraction: g.normalized_car_position,
+        }
+    }
+}
+
+impl TelemetryFrame {
+    /// The inverse of [`from_acc`](Self::from_acc) with no brake bias offset,
+    /// for backends of other sims. Fields the frame has no equivalent for
+    /// stay zero.
+    pub fn to_acc(&self, packet_id: i32) -> (Physics, Graphics, Statics) {
+        let mut p = Physics::zeroed();
+        p._packet_id = packet_id;
+        p.gas = self.throttle;
+        p.brake = self.brake;
+        p._clutch = self.clutch;
+        p._steer_angle = self.steering;
+        p.gear = self.gear + 1;
+        p.speed_kmh = self.speed_kmh();
+        p.rpms = self.rpm as i32;
+        p.current_max_rpm = self.max_rpm as i32;
+        let mut s = Statics::zeroed();
+        s._max_rpm = self.max_rpm as i32;
+        match (self.fuel, self.fuel_percent) {
+            (Some(fuel), percent) => {
+                p.fuel = fuel;
+                s._max_fuel = percent
+                    .filter(|&pct| pct > 0.0)
+                    .map_or(0.0, |pct| fuel / pct * 100.0);
+            }
+            (None, Some(percent)) => {
+                p.fuel = percent;
+                s._max_fuel = FUEL_IN_PERCENT;
+            }
+            (None, None) => {}
+        }
+        p.brake_bias = self.brake_bias.map_or(0.0, |bb| bb / 100.0);
+        p._acc_g = self.acceleration.map(|a| a / G);
+        [p._heading, p._pitch, p._roll] = self.orientation;
+        p.tc = self.tc.active as i32 as f32;
+        p.abs = self.abs.active as i32 as f32;
+        p.ignition_on = self.ignition_on as i32;
+        p._is_engine_running = self.engine_running as i32;
+        p._pit_limiter_on = self.pit_limiter_on as i32;
+
+        let mut g = Graphics::zeroed();
+        g._packet_id = packet_id;
+        g._status = self.status.map_or(UNKNOWN, |v| v as i32);
+        g._session_type = self.session_type.map_or(UNKNOWN, |v| v as i32);
+        g._flag = self.flag.map_or(UNKNOWN, |v| v as i32);
+        g._penalty = self.penalty.map_or(UNKNOWN, |v| v as i32);
+        g._track_grip_status = self.track_grip.map_or(UNKNOWN, |v| v as i32);
+        g._rain_intensity = self.rain.map_or(UNKNOWN, |v| v as i32);
+        g._rain_intensity_in_10min = self.rain_in_10min.map_or(UNKNOWN, |v| v as i32);
+        g._rain_intensity_in_30min = self.rain_in_30min.map_or(UNKNOWN, |v| v as i32);
+        g._is_in_pit = self.in_pit as i32;
+        g._is_in_pit_lane = self.in_pit_lane as i32;
+        g.tc = self.tc.level;
+        g.abs = self.abs.level;
+        g._completed_laps = self.completed_laps;
+        g.i_current_time = lap_ms(self.current_lap_time);
+        g._i_last_time = lap_ms(self.last_lap_time);
+        g._i_best_time = lap_ms(self.best_lap_time);
+        g.current_sector_index = self.current_sector;
+        g.is_valid_lap = !self.lap_invalid as i32;
+        g.normalized_car_position = self.lap_fraction;
+        (p, g, s)
+    }
+}
+
+fn lap_ms(seconds: Option<f64>) -> i32 {
+    seconds.map_or(NO_TIME, |s| (s * 1000.0).round() as i32)
+}
+
+fn lap_time(ms: i32) -> Option<f64> {
+    (ms > 0 && ms != NO_TIME).then(|| ms as f64 / 1000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(fuel: Option<f32>, fuel_percent: Option<f32>) -> TelemetryFrame {
+        let frame = TelemetryFrame {
+            fuel,
+            fuel_percent,
+            ..TelemetryFrame::default()
+        };
+        let (p, g, s) = frame.to_acc(1);
+        TelemetryFrame::from_acc(&p, &g, &s, 0.0)
+    }
+
+    #[test]
+    fn fuel_keeps_its_unit_through_the_acc_layout() {
+        let litres = round_trip(Some(20.0), Some(50.0));
+        assert_eq!(litres.fuel, Some(20.0));
+        assert_eq!(litres.fuel_percent, Some(50.0));
+
+        let percent = round_trip(None, Some(40.0));
+        assert_eq!(percent.fuel, None);
+        assert_eq!(percent.fuel_percent, Some(40.0));
+
+        let no_tank = round_trip(Some(30.0), None);
+        assert_eq!(no_tank.fuel, Some(30.0));
+        assert_eq!(no_tank.fuel_percent, None);
+    }
+}
@@ -0,0 +1,825 @@
+//! ACC's broadcasting protocol: UDP messages between the game and a
+//! registered client, set up in `Documents/Assetto Corsa Competizione/Config/
+//! broadcasting.json`.
+//!
+//! Messages start with their type byte; numbers are little endian and
+//! strings are a `u16` byte length followed by UTF-8. Both directions are
+//! modelled, so a fake server can be built from the same types.
+
+use super::car_data::CAR_MODELS;
+use super::enums::SessionType;
+use super::standings::{CarStanding, Standings};
+use std::collections::{BTreeMap, VecDeque};
+
+pub const DEFAULT_PORT: u16 = 9000;
+pub const PROTOCOL_VERSION: u8 = 4;
+/// What ACC sends for laps and splits without a time.
+const NO_TIME: i32 = i32::MAX;
+/// Events kept in [`BroadcastSession::events`].
+const MAX_EVENTS: usize = 20;
+
+// Client to game
+const REGISTER_COMMAND_APPLICATION: u8 = 1;
+const UNREGISTER_COMMAND_APPLICATION: u8 = 9;
+const REQUEST_ENTRY_LIST: u8 = 10;
+const REQUEST_TRACK_DATA: u8 = 11;
+
+// Game to client
+const REGISTRATION_RESULT: u8 = 1;
+const REALTIME_UPDATE: u8 = 2;
+const REALTIME_CAR_UPDATE: u8 = 3;
+const ENTRY_LIST: u8 = 4;
+const TRACK_DATA: u8 = 5;
+const ENTRY_LIST_CAR: u8 = 6;
+const BROADCASTING_EVENT: u8 = 7;
+
+/// Messages a client sends to the game.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Request {
+    Register {
+        display_name: String,
+        /// `connectionPassword` of broadcasting.json.
+        connection_password: String,
+        update_interval_ms: i32,
+        /// `commandPassword`; empty for a read-only client.
+        command_password: String,
+    },
+    Unregister {
+        connection_id: i32,
+    },
+    EntryList {
+        connection_id: i32,
+    },
+    TrackData {
+        connection_id: i32,
+    },
+}
+
+impl Request {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut w = Writer::default();
+        match self {
+            Request::Register {
+                display_name,
+                connection_password,
+                update_interval_ms,
+                command_password,
+            } => {
+                w.u8(REGISTER_COMMAND_APPLICATION);
+                w.u8(PROTOCOL_VERSION);
+                w.string(display_name);
+                w.string(connection_password);
+                w.i32(*update_interval_ms);
+                w.string(command_password);
+            }
+            Request::Unregister { connection_id } => {
+                w.u8(UNREGISTER_COMMAND_APPLICATION);
+                w.i32(*connection_id);
+            }
+            Request::EntryList { connection_id } => {
+                w.u8(REQUEST_ENTRY_LIST);
+                w.i32(*connection_id);
+            }
+            Request::TrackData { connection_id } => {
+                w.u8(REQUEST_TRACK_DATA);
+                w.i32(*connection_id);
+            }
+        }
+        w.0
+    }
+
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        let mut r = Reader(bytes);
+        Some(match r.u8()? {
+            REGISTER_COMMAND_APPLICATION => {
+                if r.u8()? != PROTOCOL_VERSION {
+                    return None;
+                }
+                Request::Register {
+                    display_name: r.string()?,
+                    connection_password: r.string()?,
+                    update_interval_ms: r.i32()?,
+                    command_password: r.string()?,
+                }
+            }
+            UNREGISTER_COMMAND_APPLICATION => Request::Unregister {
+                connection_id: r.i32()?,
+            },
+            REQUEST_ENTRY_LIST => Request::EntryList {
+                connection_id: r.i32()?,
+            },
+            REQUEST_TRACK_DATA => Request::TrackData {
+                connection_id: r.i32()?,
+            },
+            _ => return None,
+        })
+    }
+}
+
+/// Messages the game sends to a client.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Message {
+    RegistrationResult(RegistrationResult),
+    RealtimeUpdate(RealtimeUpdate),
+    RealtimeCarUpdate(RealtimeCarUpdate),
+    /// The car indices of the session; their details follow as
+    /// [`Message::EntryListCar`].
+    EntryList {
+        connection_id: i32,
+        car_indices: Vec<u16>,
+    },
+    EntryListCar(CarEntry),
+    TrackData(TrackData),
+    BroadcastingEvent(BroadcastingEvent),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RegistrationResult {
+    pub connection_id: i32,
+    pub success: bool,
+    /// Registered without a valid command password.
+    pub read_only: bool,
+    pub error: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RealtimeUpdate {
+    pub event_index: u16,
+    pub session_index: u16,
+    /// 0 practice, 4 qualifying, 9 superpole, 10 race, 11 hotlap,
+    /// 12 hotstint, 13 hotlap superpole, 14 replay.
+    pub session_type: u8,
+    /// 0 none, 1 starting, 2 pre formation, 3 formation lap, 4 pre session,
+    /// 5 session, 6 session over, 7 post session, 8 result UI.
+    pub phase: u8,
+    /// ms
+    pub session_time: f32,
+    pub session_end_time: f32,
+    pub focused_car_index: i32,
+    pub active_camera_set: String,
+    pub active_camera: String,
+    pub current_hud_page: String,
+    /// (replay session time, remaining time) in ms while a replay plays.
+    pub replay: Option<(f32, f32)>,
+    /// ms since midnight
+    pub time_of_day: f32,
+    /// °C
+    pub ambient_temp: u8,
+    pub track_temp: u8,
+    /// 0 to 10, in tenths.
+    pub clouds: u8,
+    pub rain_level: u8,
+    pub wetness: u8,
+    pub best_session_lap: LapInfo,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RealtimeCarUpdate {
+    pub car_index: u16,
+    pub driver_index: u16,
+    pub driver_count: u8,
+    /// -1 reverse, 0 neutral, 1 first.
+    pub gear: i8,
+    /// m
+    pub world_pos: [f32; 2],
+    pub yaw: f32,
+    /// 0 none, 1 track, 2 pit lane, 3 pit entry, 4 pit exit.
+    pub car_location: u8,
+    pub kmh: u16,
+    pub position: u16,
+    pub cup_position: u16,
+    pub track_position: u16,
+    /// Position along the lap, 0 to 1.
+    pub spline_position: f32,
+    pub laps: u16,
+    /// ms to the car's best lap, live.
+    pub delta: i32,
+    pub best_session_lap: LapInfo,
+    pub last_lap: LapInfo,
+    pub current_lap: LapInfo,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LapInfo {
+    /// ms, `None` without a time.
+    pub lap_time_ms: Option<i32>,
+    pub car_index: u16,
+    pub driver_index: u16,
+    /// ms per sector, `None` for sectors without a time.
+    pub splits: Vec<Option<i32>>,
+    pub is_invalid: bool,
+    pub is_valid_for_best: bool,
+    pub is_out_lap: bool,
+    pub is_in_lap: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CarEntry {
+    pub car_index: u16,
+    /// ACC's car model id, as in the car table.
+    pub car_model_type: u8,
+    pub team_name: String,
+    pub race_number: i32,
+    /// 0 pro (overall), 1 pro-am, 2 am, 3 silver, 4 national.
+    pub cup_category: u8,
+    pub current_driver_index: u8,
+    pub nationality: u16,
+    pub drivers: Vec<DriverInfo>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DriverInfo {
+    pub first_name: String,
+    pub last_name: String,
+    pub short_name: String,
+    /// 0 bronze, 1 silver, 2 gold, 3 platinum.
+    pub category: u8,
+    pub nationality: u16,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TrackData {
+    pub connection_id: i32,
+    pub name: String,
+    pub id: i32,
+    /// m
+    pub length: i32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BroadcastingEvent {
+    /// 1 green flag, 2 session over, 3 penalty message, 4 accident,
+    /// 5 lap completed, 6 best session lap, 7 best personal lap.
+    pub kind: u8,
+    pub message: String,
+    pub time_ms: i32,
+    pub car_index: i32,
+}
+
+impl Message {
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        let mut r = Reader(bytes);
+        Some(match r.u8()? {
+            REGISTRATION_RESULT => Message::RegistrationResult(RegistrationResult {
+                connection_id: r.i32()?,
+                success: r.u8()? > 0,
+                read_only: r.u8()? == 0,
+                error: r.string()?,
+            }),
+            REALTIME_UPDATE => {
+                let mut u = RealtimeUpdate {
+                    event_index: r.u16()?,
+                    session_index: r.u16()?,
+                    session_type: r.u8()?,
+                    phase: r.u8()?,
+                    session_time: r.f32()?,
+                    session_end_time: r.f32()?,
+                    focused_car_index: r.i32()?,
+                    active_camera_set: r.string()?,
+                    active_camera: r.string()?,
+                    current_hud_page: r.string()?,
+                    ..RealtimeUpdate::default()
+                };
+                if r.u8()? > 0 {
+                    u.replay = Some((r.f32()?, r.f32()?));
+                }
+                u.time_of_day = r.f32()?;
+                u.ambient_temp = r.u8()?;
+                u.track_temp = r.u8()?;
+                u.clouds = r.u8()?;
+                u.rain_level = r.u8()?;
+                u.wetness = r.u8()?;
+                u.best_session_lap = r.lap()?;
+                Message::RealtimeUpdate(u)
+            }
+            REALTIME_CAR_UPDATE => Message::RealtimeCarUpdate(RealtimeCarUpdate {
+                car_index: r.u16()?,
+                driver_index: r.u16()?,
+                driver_count: r.u8()?,
+                // Sent as 1 = R, 2 = N, 3 = 1st
+                gear: (r.u8()? as i8).wrapping_sub(2),
+                world_pos: [r.f32()?, r.f32()?],
+                yaw: r.f32()?,
+                car_location: r.u8()?,
+                kmh: r.u16()?,
+                position: r.u16()?,
+                cup_position: r.u16()?,
+                track_position: r.u16()?,
+                spline_position: r.f32()?,
+                laps: r.u16()?,
+                delta: r.i32()?,
+                best_session_lap: r.lap()?,
+                last_lap: r.lap()?,
+                current_lap: r.lap()?,
+            }),
+            ENTRY_LIST => {
+                let connection_id = r.i32()?;
+                let count = r.u16()?;
+                let car_indices = (0..count).map(|_| r.u16()).collect::<Option<_>>()?;
+                Message::EntryList {
+                    connection_id,
+                    car_indices,
+                }
+            }
+            ENTRY_LIST_CAR => {
+                let mut car = CarEntry {
+                    car_index: r.u16()?,
+                    car_model_type: r.u8()?,
+                    team_name: r.string()?,
+                    race_number: r.i32()?,
+                    cup_category: r.u8()?,
+                    current_driver_index: r.u8()?,
+                    nationality: r.u16()?,
+                    drivers: Vec::new(),
+                };
+                for _ in 0..r.u8()? {
+                    car.drivers.push(DriverInfo {
+                        first_name: r.string()?,
+                        last_name: r.string()?,
+                        short_name: r.string()?,
+                        category: r.u8()?,
+                        nationality: r.u16()?,
+                    });
+                }
+                Message::EntryListCar(car)
+            }
+            // Camera sets and HUD pages follow; the overlay has no use for them
+            TRACK_DATA => Message::TrackData(TrackData {
+                connection_id: r.i32()?,
+                name: r.string()?,
+                id: r.i32()?,
+                length: r.i32()?,
+            }),
+            BROADCASTING_EVENT => Message::BroadcastingEvent(BroadcastingEvent {
+                kind: r.u8()?,
+                message: r.string()?,
+                time_ms: r.i32()?,
+                car_index: r.i32()?,
+            }),
+            _ => return None,
+        })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut w = Writer::default();
+        match self {
+            Message::RegistrationResult(m) => {
+                w.u8(REGISTRATION_RESULT);
+                w.i32(m.connection_id);
+                w.u8(m.success as u8);
+                w.u8(!m.read_only as u8);
+                w.string(&m.error);
+            }
+            Message::RealtimeUpdate(u) => {
+                w.u8(REALTIME_UPDATE);
+                w.u16(u.event_index);
+                w.u16(u.session_index);
+                w.u8(u.session_type);
+                w.u8(u.phase);
+                w.f32(u.session_time);
+                w.f32(u.session_end_time);
+                w.i32(u.focused_car_index);
+                w.string(&u.active_camera_set);
+                w.string(&u.active_camera);
+                w.string(&u.current_hud_page);
+                w.u8(u.replay.is_some() as u8);
+                if let Some((time, remaining)) = u.replay {
+                    w.f32(time);
+                    w.f32(remaining);
+                }
+                w.f32(u.time_of_day);
+                w.u8(u.ambient_temp);
+                w.u8(u.track_temp);
+                w.u8(u.clouds);
+                w.u8(u.rain_level);
+                w.u8(u.wetness);
+                w.lap(&u.best_session_lap);
+            }
+            Message::RealtimeCarUpdate(c) => {
+                w.u8(REALTIME_CAR_UPDATE);
+                w.u16(c.car_index);
+                w.u16(c.driver_index);
+                w.u8(c.driver_count);
+                w.u8(c.gear.wrapping_add(2) as u8);
+                w.f32(c.world_pos[0]);
+                w.f32(c.world_pos[1]);
+                w.f32(c.yaw);
+                w.u8(c.car_location);
+                w.u16(c.kmh);
+                w.u16(c.position);
+                w.u16(c.cup_position);
+                w.u16(c.track_position);
+                w.f32(c.spline_position);
+                w.u16(c.laps);
+                w.i32(c.delta);
+                w.lap(&c.best_session_lap);
+                w.lap(&c.last_lap);
+                w.lap(&c.current_lap);
+            }
+            Message::EntryList {
+                connection_id,
+                car_indices,
+            } => {
+                w.u8(ENTRY_LIST);
+                w.i32(*connection_id);
+                w.u16(car_indices.len() as u16);
+                for &index in car_indices {
+                    w.u16(index);
+                }
+            }
+            Message::EntryListCar(car) => {
+                w.u8(ENTRY_LIST_CAR);
+                w.u16(car.car_index);
+                w.u8(car.car_model_type);
+                w.string(&car.team_name);
+                w.i32(car.race_number);
+                w.u8(car.cup_category);
+                w.u8(car.current_driver_index);
+                w.u16(car.nationality);
+                w.u8(car.drivers.len() as u8);
+                for d in &car.drivers {
+                    w.string(&d.first_name);
+                    w.string(&d.last_name);
+                    w.string(&d.short_name);
+                    w.u8(d.category);
+                    w.u16(d.nationality);
+                }
+            }
+            Message::TrackData(t) => {
+                w.u8(TRACK_DATA);
+                w.i32(t.connection_id);
+                w.string(&t.name);
+                w.i32(t.id);
+                w.i32(t.length);
+                // No camera sets, no HUD pages
+                w.u8(0);
+                w.u8(0);
+            }
+            Message::BroadcastingEvent(e) => {
+                w.u8(BROADCASTING_EVENT);
+                w.u8(e.kind);
+                w.string(&e.message);
+                w.i32(e.time_ms);
+                w.i32(e.car_index);
+            }
+        }
+        w.0
+    }
+}
+
+impl RealtimeUpdate {
+    pub fn session_type(&self) -> SessionType {
+        match self.session_type {
+            0 => SessionType::Practice,
+            4 | 9 => SessionType::Qualify,
+            10 => SessionType::Race,
+            11 => SessionType::Hotlap,
+            12 => SessionType::Hotstint,
+            13 => SessionType::HotlapSuperpole,
+            _ => SessionType::Unknown,
+        }
+    }
+}
+
+impl CarEntry {
+    pub fn driver_name(&self, index: usize) -> String {
+        match self.drivers.get(index) {
+            Some(d) => format!("{} {}", d.first_name, d.last_name),
+            None => String::new(),
+        }
+    }
+}
+
+/// The session as last reported by the game, built up from its messages.
+#[derive(Clone, Debug, Default)]
+pub struct BroadcastSession {
+    /// Set once the game accepted the registration.
+    pub connection_id: Option<i32>,
+    pub read_only: bool,
+    pub track: Option<TrackData>,
+    pub realtime: Option<RealtimeUpdate>,
+    /// By car index.
+    pub entries: BTreeMap<u16, CarEntry>,
+    pub cars: BTreeMap<u16, RealtimeCarUpdate>,
+    /// Latest events, oldest first.
+    pub events: VecDeque<BroadcastingEvent>,
+}
+
+impl BroadcastSession {
+    pub fn apply(&mut self, message: Message) {
+        match message {
+            Message::RegistrationResult(r) => {
+                *self = Self::default();
+                if r.success {
+                    self.connection_id = Some(r.connection_id);
+                    self.read_only = r.read_only;
+                }
+            }
+            Message::RealtimeUpdate(u) => self.realtime = Some(u),
+            Message::RealtimeCarUpdate(c) => {
+                self.cars.insert(c.car_index, c);
+            }
+            Message::EntryList { car_indices, .. } => {
+                // Cars that left the session are dropped; the details of the
+                // others follow
+                self.entries.retain(|index, _| car_indices.contains(index));
+                self.cars.retain(|index, _| car_indices.contains(index));
+            }
+            Message::EntryListCar(car) => {
+                self.entries.insert(car.car_index, car);
+            }
+            Message::TrackData(t) => self.track = Some(t),
+            Message::BroadcastingEvent(e) => {
+                if self.events.len() == MAX_EVENTS {
+                    self.events.pop_front();
+                }
+                self.events.push_back(e);
+            }
+        }
+    }
+
+    /// Whether a car reports updates without being in the entry list: it
+    /// joined after the list was sent, which then needs requesting again.
+    pub fn missing_entries(&self) -> bool {
+        self.cars
+            .keys()
+            .any(|index| !self.entries.contains_key(index))
+    }
+
+    /// Every car with both an entry and an update, the focused car as the
+    /// player's. Cars are named from the built-in table; see
+    /// [`CarDatabase::resolve_standings`](super::CarDatabase::resolve_standings)
+    /// for the user's profiles.
+    pub fn standings(&self) -> Standings {
+        let focused = self.realtime.as_ref().map(|u| u.focused_car_index);
+        let cars = self
+            .cars
+            .values()
+            .filter_map(|c| {
+                let entry = self.entries.get(&c.car_index)?;
+                let model = CAR_MODELS
+                    .iter()
+                    .find(|m| m.car_id == entry.car_model_type as i32);
+                Some(CarStanding {
+                    id: c.car_index as i32,
+                    position: c.position as i32,
+                    driver: entry.driver_name(c.driver_index as usize),
+                    car_model: match model {
+                        Some(m) => m.car_model.to_string(),
+                        None => format!("acc_{}", entry.car_model_type),
+                    },
+                    car_id: Some(entry.car_model_type as i32),
+                    class: model.map_or(String::new(), |m| m.class.name().to_string()),
+                    completed_laps: c.laps as i32,
+                    lap_fraction: c.spline_position,
+                    last_lap_time: lap_time(&c.last_lap),
+                    best_lap_time: lap_time(&c.best_session_lap),
+                    best_sectors: std::array::from_fn(|i| {
+                        let ms = c.best_session_lap.splits.get(i).copied().flatten()?;
+                        Some(ms as f64 / 1000.0)
+                    }),
+                    in_pit: c.car_location >= 2,
+                    is_player: focused == Some(c.car_index as i32),
+                })
+            })
+            .collect();
+        Standings {
+            track_length: self.track.as_ref().map_or(0.0, |t| t.length as f32),
+            cars,
+        }
+    }
+}
+
+fn lap_time(lap: &LapInfo) -> Option<f64> {
+    lap.lap_time_ms.map(|ms| ms as f64 / 1000.0)
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let (head, rest) = self.0.split_first_chunk::<N>()?;
+        self.0 = rest;
+        Some(*head)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take::<1>().map(|b| b[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.take().map(u16::from_le_bytes)
+    }
+
+    fn i32(&mut self) -> Option<i32> {
+        self.take().map(i32::from_le_bytes)
+    }
+
+    fn f32(&mut self) -> Option<f32> {
+        self.take().map(f32::from_le_bytes)
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let len = self.u16()? as usize;
+        let bytes = self.0.get(..len)?;
+        self.0 = &self.0[len..];
+        Some(String::from_utf8_lossy(bytes).into_owned())
+    }
+
+    fn time(&mut self) -> Option<Option<i32>> {
+        self.i32().map(|ms| (ms != NO_TIME).then_some(ms))
+    }
+
+    fn lap(&mut self) -> Option<LapInfo> {
+        let mut lap = LapInfo {
+            lap_time_ms: self.time()?,
+            car_index: self.u16()?,
+            driver_index: self.u16()?,
+            ..LapInfo::default()
+        };
+        for _ in 0..self.u8()? {
+            lap.splits.push(self.time()?);
+        }
+        lap.is_invalid = self.u8()? > 0;
+        lap.is_valid_for_best = self.u8()? > 0;
+        lap.is_out_lap = self.u8()? > 0;
+        lap.is_in_lap = self.u8()? > 0;
+        Some(lap)
+    }
+}
+
+#[derive(Default)]
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn u8(&mut self, v: u8) {
+        self.0.push(v);
+    }
+
+    fn u16(&mut self, v: u16) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn i32(&mut self, v: i32) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn f32(&mut self, v: f32) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn string(&mut self, s: &str) {
+        self.u16(s.len() as u16);
+        self.0.extend_from_slice(s.as_bytes());
+    }
+
+    fn lap(&mut self, lap: &LapInfo) {
+        self.i32(lap.lap_time_ms.unwrap_or(NO_TIME));
+        self.u16(lap.car_index);
+        self.u16(lap.driver_index);
+        self.u8(lap.splits.len() as u8);
+        for split in &lap.splits {
+            self.i32(split.unwrap_or(NO_TIME));
+        }
+        self.u8(lap.is_invalid as u8);
+        self.u8(lap.is_valid_for_best as u8);
+        self.u8(lap.is_out_lap as u8);
+        self.u8(lap.is_in_lap as u8);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lap(car_index: u16, lap_time_ms: Option<i32>) -> LapInfo {
+        LapInfo {
+            lap_time_ms,
+            car_index,
+            driver_index: 1,
+            splits: vec![Some(31_250), None, Some(40_125)],
+            is_invalid: true,
+            is_valid_for_best: false,
+            is_out_lap: true,
+            is_in_lap: false,
+        }
+    }
+
+    #[test]
+    fn requests_round_trip() {
+        let requests = [
+            Request::Register {
+                display_name: "SimTem".to_string(),
+                connection_password: "asd".to_string(),
+                update_interval_ms: 250,
+                command_password: String::new(),
+            },
+            Request::Unregister { connection_id: 7 },
+            Request::EntryList { connection_id: 7 },
+            Request::TrackData { connection_id: 7 },
+        ];
+        for request in requests {
+            assert_eq!(Request::parse(&request.encode()), Some(request));
+        }
+    }
+
+    #[test]
+    fn messages_round_trip() {
+        let messages = [
+            Message::RegistrationResult(RegistrationResult {
+                connection_id: 7,
+                success: true,
+                read_only: true,
+                error: "no command password".to_string(),
+            }),
+            Message::RealtimeUpdate(RealtimeUpdate {
+                event_index: 1,
+                session_index: 2,
+                session_type: 10,
+                phase: 5,
+                session_time: 61_000.0,
+                session_end_time: 1_200_000.0,
+                focused_car_index: 3,
+                active_camera_set: "Onboard".to_string(),
+                active_camera: "Onboard0".to_string(),
+                current_hud_page: "Basic HUD".to_string(),
+                replay: Some((30_000.0, 5_000.0)),
+                time_of_day: 52_000_000.0,
+                ambient_temp: 22,
+                track_temp: 31,
+                clouds: 3,
+                rain_level: 1,
+                wetness: 2,
+                best_session_lap: lap(3, Some(104_321)),
+            }),
+            Message::RealtimeCarUpdate(RealtimeCarUpdate {
+                car_index: 3,
+                driver_index: 1,
+                driver_count: 2,
+                gear: -1,
+                world_pos: [-120.5, 310.25],
+                yaw: 1.5,
+                car_location: 2,
+                kmh: 212,
+                position: 4,
+                cup_position: 2,
+                track_position: 5,
+                spline_position: 0.75,
+                laps: 12,
+                delta: -250,
+                best_session_lap: lap(3, Some(104_321)),
+                last_lap: lap(3, Some(105_002)),
+                current_lap: lap(3, None),
+            }),
+            Message::EntryList {
+                connection_id: 7,
+                car_indices: vec![3, 1, 1001],
+            },
+            Message::EntryListCar(CarEntry {
+                car_index: 3,
+                car_model_type: 32,
+                team_name: "Team Ünicode".to_string(),
+                race_number: 296,
+                cup_category: 1,
+                current_driver_index: 1,
+                nationality: 5,
+                drivers: vec![
+                    DriverInfo {
+                        first_name: "Ann".to_string(),
+                        last_name: "Other".to_string(),
+                        short_name: "OTH".to_string(),
+                        category: 2,
+                        nationality: 5,
+                    },
+                    DriverInfo::default(),
+                ],
+            }),
+            Message::TrackData(TrackData {
+                connection_id: 7,
+                name: "Spa".to_string(),
+                id: 11,
+                length: 7004,
+            }),
+            Message::BroadcastingEvent(BroadcastingEvent {
+                kind: 6,
+                message: "Best session lap".to_string(),
+                time_ms: 104_321,
+                car_index: 3,
+            }),
+        ];
+        for message in messages {
+            assert_eq!(Message::parse(&message.encode()), Some(message));
+        }
+    }
+
+    #[test]
+    fn every_gear_byte_parses() {
+        for gear in i8::MIN..=i8::MAX {
+            let update = Message::RealtimeCarUpdate(RealtimeCarUpdate {
+                gear,
+                ..RealtimeCarUpdate::default()
+            });
+            assert_eq!(Message::parse(&update.encode()), Some(update));
+        }
+    }
+}
@@ -1,19 +1,81 @@
-#[allow(dead_code)]
+use serde::Deserialize;
+
+/// Racing class of a car, as grouped in ACC's car selection.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize)]
+pub enum CarClass {
+    #[serde(rename = "GT3")]
+    Gt3,
+    #[serde(rename = "GT4")]
+    Gt4,
+    #[serde(rename = "GT2")]
+    Gt2,
+    #[serde(rename = "TCX")]
+    Tcx,
+    Cup,
+    #[serde(rename = "ST")]
+    SuperTrofeo,
+    #[serde(rename = "CHL")]
+    Challenge,
+}
+
+impl CarClass {
+    pub fn name(self) -> &'static str {
+        match self {
+            CarClass::Gt3 => "GT3",
+            CarClass::Gt4 => "GT4",
+            CarClass::Gt2 => "GT2",
+            CarClass::Tcx => "TCX",
+            CarClass::Cup => "CUP",
+            CarClass::SuperTrofeo => "ST",
+            CarClass::Challenge => "CHL",
+        }
+    }
+}
+
 pub struct CarModelData {
+    /// ACC's car model id, as used by the broadcasting API.
     pub car_id: i32,
     pub shift_rpm: i32,
     pub bb_offset: f32,
     pub brake_pressure_co: [f32; 2],
     pub max_steering_angle: i32,
     pub car_model: &'static str,
+    pub class: CarClass,
 }
 
-pub fn _get_car(id: i32) -> Option<&'static CarModelData> {
-    CAR_MODELS.iter().find(|c| c.car_id == id)
-}
+// Every id and model name may appear only once.
+const _: () = {
+    let mut i = 0;
+    while i < CAR_MODELS.len() {
+        let mut j = i + 1;
+        while j < CAR_MODELS.len() {
+            assert!(
+                CAR_MODELS[i].car_id != CAR_MODELS[j].car_id,
+                "duplicate car_id"
+            );
+            assert!(
+                !str_eq(CAR_MODELS[i].car_model, CAR_MODELS[j].car_model),
+                "duplicate car_model"
+            );
+            j += 1;
+        }
+        i += 1;
+    }
+};
 
-pub fn get_car_by_name(name: &str) -> Option<&'static CarModelData> {
-    CAR_MODELS.iter().find(|c| c.car_model == name)
+const fn str_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
 }
 
 pub fn parse_static_string(chars: &[u16]) -> String {
@@ -21,7 +83,37 @@ pub fn parse_static_string(chars: &[u16]) -> String {
     String::from_utf16_lossy(&chars[0..len])
 }
 
-pub const CAR_MODELS: [CarModelData; 41] = [
+/// Like [`parse_static_string`], for sims that export 8-bit strings.
+pub fn parse_byte_string(chars: &[u8]) -> String {
+    let len = chars.iter().position(|&c| c == 0).unwrap_or(chars.len());
+    String::from_utf8_lossy(&chars[0..len]).into_owned()
+}
+
+/// Profile key for sims that only report a display name: lower case, with
+/// runs of anything else than letters and digits turned into `_`
+/// ("Porsche 963 #6" becomes `porsche_963_6`).
+pub fn car_model_from_name(name: &str) -> String {
+    let mut model = String::new();
+    for c in name.trim().chars() {
+        if c.is_ascii_alphanumeric() {
+            model.push(c.to_ascii_lowercase());
+        } else if !model.is_empty() && !model.ends_with('_') {
+            model.push('_');
+        }
+    }
+    model.trim_end_matches('_').to_string()
+}
+
+/// Inverse of [`parse_static_string`]: UTF-16, zero padded, truncated to fit.
+pub fn write_static_string(dst: &mut [u16], s: &str) {
+    dst.fill(0);
+    let max = dst.len().saturating_sub(1);
+    for (d, c) in dst[..max].iter_mut().zip(s.encode_utf16()) {
+        *d = c;
+    }
+}
+
+pub const CAR_MODELS: [CarModelData; 54] = [
     // GT3-2018
     CarModelData {
         car_id: 12,
@@ -30,6 +122,7 @@ pub const CAR_MODELS: [CarModelData; 41] = [
         brake_pressure_co: [7.9585, 7.9585],
         max_steering_angle: 320,
         car_model: "amr_v12_vantage_gt3",
+        class: CarClass::Gt3,
     },
     CarModelData {
         car_id: 3,
@@ -38,6 +131,7 @@ pub const CAR_MODELS: [CarModelData; 41] = [
         brake_pressure_co: [7.5980, 7.4855],
         max_steering_angle: 360,
         car_model: "audi_r8_lms",
+        class: CarClass::Gt3,
     },
     CarModelData {
         car_id: 11,
@@ -46,6 +140,7 @@ pub const CAR_MODELS: [CarModelData; 41] = [
         brake_pressure_co: [7.9585, 7.9585],
         max_steering_angle: 320,
         car_model: "bentley_continental_gt3_2016",
+        class: CarClass::Gt3,
     },
     CarModelData {
         car_id: 8,
@@ -54,6 +149,7 @@ pub const CAR_MODELS: [CarModelData; 41] = [
         brake_pressure_co: [7.9585, 7.9585],
         max_steering_angle: 320,
         car_model: "bentley_continental_gt3_2018",
+        class: CarClass::Gt3,
     },
     CarModelData {
         car_id: 7,
@@ -62,6 +158,7 @@ pub const CAR_MODELS: [CarModelData; 41] = [
         brake_pressure_co: [7.9585, 7.9585],
         max_steering_angle: 283,
         car_model: "bmw_m6_gt3",
+        class: CarClass::Gt3,
     },
     CarModelData {
         car_id: 14,
@@ -70,6 +167,7 @@ pub const CAR_MODELS: [CarModelData; 41] = [
         brake_pressure_co: [7.9585, 7.9585],
         max_steering_angle: 360,
         car_model: "jaguar_g3",
+        class: CarClass::Gt3,
     },
     CarModelData {
         car_id: 2,
@@ -78,6 +176,7 @@ pub const CAR_MODELS: [CarModelData; 41] = [
         brake_pressure_co: [7.5980, 7.4855],
         max_steering_angle: 240,
         car_model: "ferrari_488_gt3",
+        class: CarClass::Gt3,
     },
     CarModelData {
         car_id: 17,
@@ -86,6 +185,7 @@ pub const CAR_MODELS: [CarModelData; 41] = [
         brake_pressure_co: [7.5980, 7.4855],
         max_steering_angle: 310,
         car_model: "honda_nsx_gt3",
+        class: CarClass::Gt3,
     },
     CarModelData {
         car_id: 13,
@@ -94,6 +194,7 @@ pub const CAR_MODELS: [CarModelData; 41] = [
         brake_pressure_co: [7.5980, 7.4855],
         max_steering_angle: 360,
         car_model: "lamborghini_gallardo_rex",
+        class: CarClass::Gt3,
     },
     CarModelData {
         car_id: 4,
@@ -102,6 +203,7 @@ pub const CAR_MODELS: [CarModelData; 41] = [
         brake_pressure_co: [7.5980, 7.4855],
         max_steering_angle: 310,
         car_model: "lamborghini_huracan_gt3",
+        class: CarClass::Gt3,
     },
     CarModelData {
         car_id: 18,
@@ -110,6 +212,7 @@ pub const CAR_MODELS: [CarModelData; 41] = [
         brake_pressure_co: [7.5980, 7.4855],
         max_steering_angle: 310,
         car_model: "lamborghini_huracan_st",
+        class: CarClass::SuperTrofeo,
     },
     CarModelData {
         car_id: 15,
@@ -118,6 +221,7 @@ pub const CAR_MODELS: [CarModelData; 41] = [
         brake_pressure_co: [7.9585, 7.9585],
         max_steering_angle: 320,
         car_model: "lexus_rc_f_gt3",
+        class: CarClass::Gt3,
     },
     CarModelData {
         car_id: 5,
@@ -126,6 +230,7 @@ pub const CAR_MODELS: [CarModelData; 41] = [
         brake_pressure_co: [7.5980, 7.4855],
         max_steering_angle: 240,
         car_model: "mclaren_650s_gt3",
+        class: CarClass::Gt3,
     },
     CarModelData {
         car_id: 1,
@@ -134,6 +239,7 @@ pub const CAR_MODELS: [CarModelData; 41] = [
         brake_pressure_co: [7.9585, 7.9585],
         max_steering_angle: 320,
         car_model: "mercedes_amg_gt3",
+        class: CarClass::Gt3,
     },
     CarModelData {
         car_id: 10,
@@ -142,6 +248,7 @@ pub const CAR_MODELS: [CarModelData; 41] = [
         brake_pressure_co: [7.9585, 7.9585],
         max_steering_angle: 320,
         car_model: "nissan_gt_r_gt3_2017",
+        class: CarClass::Gt3,
     },
     CarModelData {
         car_id: 6,
@@ -150,6 +257,7 @@ pub const CAR_MODELS: [CarModelData; 41] = [
         brake_pressure_co: [7.9585, 7.9585],
         max_steering_angle: 320,
         car_model: "nissan_gt_r_gt3_2018",
+        class: CarClass::Gt3,
     },
     CarModelData {
         car_id: 0,
@@ -158,6 +266,7 @@ pub const CAR_MODELS: [CarModelData; 41] = [
         brake_pressure_co: [7.1497, 6.7715],
         max_steering_angle: 400,
         car_model: "porsche_991_gt3_r",
+        class: CarClass::Gt3,
     },
     CarModelData {
         car_id: 9,
@@ -166,6 +275,7 @@ pub const CAR_MODELS: [CarModelData; 41] = [
         brake_pressure_co: [7.1497, 6.7715],
         max_steering_angle: 400,
         car_model: "porsche_991ii_gt3_cup",
+        class: CarClass::Cup,
     },
     // GT3-2019
     CarModelData {
@@ -175,6 +285,7 @@ pub const CAR_MODELS: [CarModelData; 41] = [
         brake_pressure_co: [7.9585, 7.9585],
         max_steering_angle: 320,
         car_model: "amr_v8_vantage_gt3",
+        class: CarClass::Gt3,
     },
     CarModelData {
         car_id: 19,
@@ -183,6 +294,7 @@ pub const CAR_MODELS: [CarModelData; 41] = [
         brake_pressure_co: [7.5980, 7.4855],
         max_steering_angle: 360,
         car_model: "audi_r8_lms_evo",
+        class: CarClass::Gt3,
     },
     CarModelData {
         car_id: 21,
@@ -191,6 +303,7 @@ pub const CAR_MODELS: [CarModelData; 41] = [
         brake_pressure_co: [7.5980, 7.4855],
         max_steering_angle: 310,
         car_model: "honda_nsx_gt3_evo",
+        class: CarClass::Gt3,
     },
     CarModelData {
         car_id: 16,
@@ -199,6 +312,7 @@ pub const CAR_MODELS: [CarModelData; 41] = [
         brake_pressure_co: [7.5980, 7.4855],
         max_steering_angle: 310,
         car_model: "lamborghini_huracan_gt3_evo",
+        class: CarClass::Gt3,
     },
     CarModelData {
         car_id: 22,
@@ -207,6 +321,7 @@ pub const CAR_MODELS: [CarModelData; 41] = [
         brake_pressure_co: [7.5980, 7.4855],
         max_steering_angle: 240,
         car_model: "mclaren_720s_gt3",
+        class: CarClass::Gt3,
     },
     CarModelData {
         car_id: 23,
@@ -215,15 +330,53 @@ pub const CAR_MODELS: [CarModelData; 41] = [
         brake_pressure_co: [7.1497, 6.7715],
         max_steering_angle: 400,
         car_model: "porsche_991ii_gt3_r",
+        class: CarClass::Gt3,
     },
-    // GT3-2023
+    // GT3-2023/24
     CarModelData {
-        car_id: 24,
+        car_id: 34,
         shift_rpm: 9000,
         bb_offset: -21.0,
         brake_pressure_co: [7.1497, 6.7715],
         max_steering_angle: 400,
         car_model: "porsche_992_gt3_r",
+        class: CarClass::Gt3,
+    },
+    CarModelData {
+        car_id: 32,
+        shift_rpm: 0,
+        bb_offset: -5.0,
+        brake_pressure_co: [7.5980, 7.4855],
+        max_steering_angle: 400,
+        car_model: "ferrari_296_gt3",
+        class: CarClass::Gt3,
+    },
+    CarModelData {
+        car_id: 33,
+        shift_rpm: 0,
+        bb_offset: -14.0,
+        brake_pressure_co: [7.5980, 7.4855],
+        max_steering_angle: 310,
+        car_model: "lamborghini_huracan_gt3_evo2",
+        class: CarClass::Gt3,
+    },
+    CarModelData {
+        car_id: 35,
+        shift_rpm: 0,
+        bb_offset: -17.0,
+        brake_pressure_co: [7.5980, 7.4855],
+        max_steering_angle: 240,
+        car_model: "mclaren_720s_gt3_evo",
+        class: CarClass::Gt3,
+    },
+    CarModelData {
+        car_id: 36,
+        shift_rpm: 0,
+        bb_offset: -5.0,
+        brake_pressure_co: [7.9585, 7.9585],
+        max_steering_angle: 330,
+        car_model: "ford_mustang_gt3",
+        class: CarClass::Gt3,
     },
     // GT4
     CarModelData {
@@ -233,6 +386,7 @@ pub const CAR_MODELS: [CarModelData; 41] = [
         brake_pressure_co: [10.0, 10.0],
         max_steering_angle: 360,
         car_model: "alpine_a110_gt4",
+        class: CarClass::Gt4,
     },
     CarModelData {
         car_id: 51,
@@ -241,6 +395,7 @@ pub const CAR_MODELS: [CarModelData; 41] = [
         brake_pressure_co: [10.0, 10.0],
         max_steering_angle: 320,
         car_model: "amr_v8_vantage_gt4",
+        class: CarClass::Gt4,
     },
     CarModelData {
         car_id: 52,
@@ -249,6 +404,7 @@ pub const CAR_MODELS: [CarModelData; 41] = [
         brake_pressure_co: [10.0, 10.0],
         max_steering_angle: 360,
         car_model: "audi_r8_gt4",
+        class: CarClass::Gt4,
     },
     CarModelData {
         car_id: 53,
@@ -257,6 +413,7 @@ pub const CAR_MODELS: [CarModelData; 41] = [
         brake_pressure_co: [7.2886, 10.0],
         max_steering_angle: 246,
         car_model: "bmw_m4_gt4",
+        class: CarClass::Gt4,
     },
     CarModelData {
         car_id: 55,
@@ -265,6 +422,7 @@ pub const CAR_MODELS: [CarModelData; 41] = [
         brake_pressure_co: [10.0, 10.0],
         max_steering_angle: 360,
         car_model: "chevrolet_camaro_gt4r",
+        class: CarClass::Gt4,
     },
     CarModelData {
         car_id: 56,
@@ -273,6 +431,7 @@ pub const CAR_MODELS: [CarModelData; 41] = [
         brake_pressure_co: [10.0, 10.0],
         max_steering_angle: 360,
         car_model: "ginetta_g55_gt4",
+        class: CarClass::Gt4,
     },
     CarModelData {
         car_id: 57,
@@ -281,6 +440,7 @@ pub const CAR_MODELS: [CarModelData; 41] = [
         brake_pressure_co: [10.0, 10.0],
         max_steering_angle: 290,
         car_model: "ktm_xbow_gt4",
+        class: CarClass::Gt4,
     },
     CarModelData {
         car_id: 58,
@@ -289,6 +449,7 @@ pub const CAR_MODELS: [CarModelData; 41] = [
         brake_pressure_co: [7.7768, 7.6142],
         max_steering_angle: 450,
         car_model: "maserati_mc_gt4",
+        class: CarClass::Gt4,
     },
     CarModelData {
         car_id: 59,
@@ -297,6 +458,7 @@ pub const CAR_MODELS: [CarModelData; 41] = [
         brake_pressure_co: [10.0, 10.0],
         max_steering_angle: 240,
         car_model: "mclaren_570s_gt4",
+        class: CarClass::Gt4,
     },
     CarModelData {
         car_id: 60,
@@ -305,6 +467,7 @@ pub const CAR_MODELS: [CarModelData; 41] = [
         brake_pressure_co: [10.0, 10.0],
         max_steering_angle: 246,
         car_model: "mercedes_amg_gt4",
+        class: CarClass::Gt4,
     },
     CarModelData {
         car_id: 61,
@@ -313,8 +476,9 @@ pub const CAR_MODELS: [CarModelData; 41] = [
         brake_pressure_co: [10.0, 10.0],
         max_steering_angle: 400,
         car_model: "porsche_718_cayman_gt4_mr",
+        class: CarClass::Gt4,
     },
-    // GT3-2020/21 & Challengers
+    // GT3-2020/21 & TCX
     CarModelData {
         car_id: 24,
         shift_rpm: 0,
@@ -322,6 +486,7 @@ pub const CAR_MODELS: [CarModelData; 41] = [
         brake_pressure_co: [7.5980, 7.4855],
         max_steering_angle: 240,
         car_model: "ferrari_488_gt3_evo",
+        class: CarClass::Gt3,
     },
     CarModelData {
         car_id: 25,
@@ -330,6 +495,7 @@ pub const CAR_MODELS: [CarModelData; 41] = [
         brake_pressure_co: [7.9585, 7.9585],
         max_steering_angle: 320,
         car_model: "mercedes_amg_gt3_evo",
+        class: CarClass::Gt3,
     },
     CarModelData {
         car_id: 30,
@@ -338,6 +504,7 @@ pub const CAR_MODELS: [CarModelData; 41] = [
         brake_pressure_co: [7.9585, 7.9585],
         max_steering_angle: 270,
         car_model: "bmw_m4_gt3",
+        class: CarClass::Gt3,
     },
     CarModelData {
         car_id: 31,
@@ -346,6 +513,7 @@ pub const CAR_MODELS: [CarModelData; 41] = [
         brake_pressure_co: [7.5980, 7.4855],
         max_steering_angle: 360,
         car_model: "audi_r8_lms_evo_ii",
+        class: CarClass::Gt3,
     },
     CarModelData {
         car_id: 27,
@@ -354,5 +522,115 @@ pub const CAR_MODELS: [CarModelData; 41] = [
         brake_pressure_co: [7.2886, 10.0],
         max_steering_angle: 180,
         car_model: "bmw_m2_cs_racing",
+        class: CarClass::Tcx,
+    },
+    // Cup, Challenge & Super Trofeo
+    CarModelData {
+        car_id: 26,
+        shift_rpm: 0,
+        bb_offset: -13.0,
+        brake_pressure_co: [7.5980, 7.4855],
+        max_steering_angle: 240,
+        car_model: "ferrari_488_challenge_evo",
+        class: CarClass::Challenge,
+    },
+    CarModelData {
+        car_id: 28,
+        shift_rpm: 0,
+        bb_offset: -5.0,
+        brake_pressure_co: [7.1497, 6.7715],
+        max_steering_angle: 270,
+        car_model: "porsche_992_gt3_cup",
+        class: CarClass::Cup,
+    },
+    CarModelData {
+        car_id: 29,
+        shift_rpm: 0,
+        bb_offset: -14.0,
+        brake_pressure_co: [7.5980, 7.4855],
+        max_steering_angle: 310,
+        car_model: "lamborghini_huracan_st_evo2",
+        class: CarClass::SuperTrofeo,
+    },
+    // GT2
+    CarModelData {
+        car_id: 80,
+        shift_rpm: 0,
+        bb_offset: -20.0,
+        brake_pressure_co: [7.5980, 7.4855],
+        max_steering_angle: 360,
+        car_model: "audi_r8_gt2",
+        class: CarClass::Gt2,
+    },
+    CarModelData {
+        car_id: 82,
+        shift_rpm: 0,
+        bb_offset: -20.0,
+        brake_pressure_co: [7.9585, 7.9585],
+        max_steering_angle: 300,
+        car_model: "ktm_xbow_gt2",
+        class: CarClass::Gt2,
+    },
+    CarModelData {
+        car_id: 83,
+        shift_rpm: 0,
+        bb_offset: -5.0,
+        brake_pressure_co: [7.5980, 7.4855],
+        max_steering_angle: 400,
+        car_model: "maserati_mc20_gt2",
+        class: CarClass::Gt2,
+    },
+    CarModelData {
+        car_id: 84,
+        shift_rpm: 0,
+        bb_offset: -20.0,
+        brake_pressure_co: [7.9585, 7.9585],
+        max_steering_angle: 320,
+        car_model: "mercedes_amg_gt2",
+        class: CarClass::Gt2,
+    },
+    CarModelData {
+        car_id: 85,
+        shift_rpm: 0,
+        bb_offset: -5.0,
+        brake_pressure_co: [7.1497, 6.7715],
+        max_steering_angle: 400,
+        car_model: "porsche_911_gt2_rs_cs_evo",
+        class: CarClass::Gt2,
+    },
+    CarModelData {
+        car_id: 86,
+        shift_rpm: 0,
+        bb_offset: -5.0,
+        brake_pressure_co: [7.1497, 6.7715],
+        max_steering_angle: 400,
+        car_model: "porsche_935",
+        class: CarClass::Gt2,
     },
 ];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::CarProfile;
+    use std::collections::HashSet;
+
+    #[test]
+    fn catalogue_is_unique_and_well_formed() {
+        let mut ids = HashSet::new();
+        let mut models = HashSet::new();
+        for car in &CAR_MODELS {
+            assert!(ids.insert(car.car_id), "duplicate car_id {}", car.car_id);
+            assert!(
+                models.insert(car.car_model),
+                "duplicate car_model {}",
+                car.car_model
+            );
+            assert!(car.car_id >= 0, "{} has no car_id", car.car_model);
+            assert!(car.bb_offset != 0.0, "{} has no bb_offset", car.car_model);
+            if let Err(e) = CarProfile::from(car).validate() {
+                panic!("{}: {}", car.car_model, e);
+            }
+        }
+    }
+}
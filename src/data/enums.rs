@@ -0,0 +1,206 @@
+//! Typed versions of the enum-like `i32` fields in the ACC pages, with the
+//! values from the shared memory documentation.
+
+use std::fmt;
+
+/// A raw page value that is not one of the documented variants.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct InvalidValue {
+    pub field: &'static str,
+    pub value: i32,
+}
+
+impl fmt::Display for InvalidValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid {} value {}", self.field, self.value)
+    }
+}
+
+impl std::error::Error for InvalidValue {}
+
+/// Declares a `#[repr(i32)]` enum with a fallible conversion from the raw value.
+macro_rules! acc_enum {
+    ($(#[$meta:meta])* $name:ident { $($(#[$vmeta:meta])* $variant:ident = $value:expr,)* }) => {
+        $(#[$meta])*
+        #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+        #[repr(i32)]
+        pub enum $name {
+            $($(#[$vmeta])* $variant = $value,)*
+        }
+
+        impl TryFrom<i32> for $name {
+            type Error = InvalidValue;
+
+            fn try_from(value: i32) -> Result<Self, Self::Error> {
+                match value {
+                    $($value => Ok($name::$variant),)*
+                    _ => Err(InvalidValue { field: stringify!($name), value }),
+                }
+            }
+        }
+    };
+}
+
+acc_enum! {
+    /// `AC_STATUS`: what the sim is doing.
+    AcStatus {
+        Off = 0,
+        Replay = 1,
+        Live = 2,
+        Pause = 3,
+    }
+}
+
+acc_enum! {
+    /// `AC_SESSION_TYPE`
+    SessionType {
+        Unknown = -1,
+        Practice = 0,
+        Qualify = 1,
+        Race = 2,
+        Hotlap = 3,
+        TimeAttack = 4,
+        Drift = 5,
+        Drag = 6,
+        Hotstint = 7,
+        HotlapSuperpole = 8,
+    }
+}
+
+acc_enum! {
+    /// `AC_FLAG_TYPE`: the flag shown to the player.
+    FlagType {
+        None = 0,
+        Blue = 1,
+        Yellow = 2,
+        Black = 3,
+        White = 4,
+        Checkered = 5,
+        Penalty = 6,
+        Green = 7,
+        Orange = 8,
+    }
+}
+
+acc_enum! {
+    /// `PenaltyShortcut`: the pending penalty and what it was given for.
+    PenaltyType {
+        None = 0,
+        DriveThroughCutting = 1,
+        StopAndGo10Cutting = 2,
+        StopAndGo20Cutting = 3,
+        StopAndGo30Cutting = 4,
+        DisqualifiedCutting = 5,
+        RemoveBestLaptimeCutting = 6,
+        DriveThroughPitSpeeding = 7,
+        StopAndGo10PitSpeeding = 8,
+        StopAndGo20PitSpeeding = 9,
+        StopAndGo30PitSpeeding = 10,
+        DisqualifiedPitSpeeding = 11,
+        RemoveBestLaptimePitSpeeding = 12,
+        DisqualifiedIgnoredMandatoryPit = 13,
+        PostRaceTime = 14,
+        DisqualifiedTrolling = 15,
+        DisqualifiedPitEntry = 16,
+        DisqualifiedPitExit = 17,
+        DisqualifiedWrongWay = 18,
+        DriveThroughIgnoredDriverStint = 19,
+        DisqualifiedIgnoredDriverStint = 20,
+        DisqualifiedExceededDriverStintLimit = 21,
+    }
+}
+
+acc_enum! {
+    /// `ACC_TRACK_GRIP_STATUS`
+    TrackGripStatus {
+        Green = 0,
+        Fast = 1,
+        Optimum = 2,
+        Greasy = 3,
+        Damp = 4,
+        Wet = 5,
+        Flooded = 6,
+    }
+}
+
+acc_enum! {
+    /// `ACC_RAIN_INTENSITY`
+    RainIntensity {
+        NoRain = 0,
+        Drizzle = 1,
+        LightRain = 2,
+        MediumRain = 3,
+        HeavyRain = 4,
+        Thunderstorm = 5,
+    }
+}
+
+impl RainIntensity {
+    /// Buckets a rain amount from 0 (dry) to 1 (heaviest), for sims that
+    /// report rain as a fraction.
+    pub fn from_fraction(rain: f32) -> Self {
+        match rain {
+            r if r <= 0.0 => RainIntensity::NoRain,
+            r if r < 0.1 => RainIntensity::Drizzle,
+            r if r < 0.3 => RainIntensity::LightRain,
+            r if r < 0.6 => RainIntensity::MediumRain,
+            r if r < 0.8 => RainIntensity::HeavyRain,
+            _ => RainIntensity::Thunderstorm,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every value in `documented` converts to the variant with that value,
+    /// the values just outside do not.
+    fn check<T>(documented: std::ops::RangeInclusive<i32>, raw: fn(T) -> i32, name: &'static str)
+    where
+        T: TryFrom<i32, Error = InvalidValue>,
+    {
+        for value in documented.clone() {
+            let variant = T::try_from(value).unwrap_or_else(|e| panic!("{}", e));
+            assert_eq!(raw(variant), value);
+        }
+        for value in [documented.start() - 1, documented.end() + 1, i32::MAX] {
+            let e = T::try_from(value)
+                .err()
+                .expect("undocumented value accepted");
+            assert_eq!(e, InvalidValue { field: name, value });
+        }
+    }
+
+    #[test]
+    fn raw_values_convert_to_the_documented_variants() {
+        check(0..=3, |v: AcStatus| v as i32, "AcStatus");
+        check(-1..=8, |v: SessionType| v as i32, "SessionType");
+        check(0..=8, |v: FlagType| v as i32, "FlagType");
+        check(0..=21, |v: PenaltyType| v as i32, "PenaltyType");
+        check(0..=6, |v: TrackGripStatus| v as i32, "TrackGripStatus");
+        check(0..=5, |v: RainIntensity| v as i32, "RainIntensity");
+    }
+
+    #[test]
+    fn invalid_values_name_the_field() {
+        let e = AcStatus::try_from(7).unwrap_err();
+        assert_eq!(e.to_string(), "invalid AcStatus value 7");
+    }
+
+    #[test]
+    fn rain_fraction_buckets() {
+        let buckets = [0.0, 0.05, 0.2, 0.5, 0.7, 1.0].map(RainIntensity::from_fraction);
+        assert_eq!(
+            buckets,
+            [
+                RainIntensity::NoRain,
+                RainIntensity::Drizzle,
+                RainIntensity::LightRain,
+                RainIntensity::MediumRain,
+                RainIntensity::HeavyRain,
+                RainIntensity::Thunderstorm,
+            ]
+        );
+    }
+}
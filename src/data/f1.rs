@@ -0,0 +1,700 @@
+//! UDP telemetry of the Codemasters / EA F1 games (F1 23 and F1 24 formats).
+//!
+//! Every packet starts with a [`Header`]; the structs below follow it and are
+//! packed little endian, as sent. Only the packets the overlay uses are
+//! modelled, and only the parts of them it reads.
+
+use super::ShmPage;
+use super::enums::{AcStatus, FlagType, RainIntensity, SessionType};
+use super::standings::{CarStanding, Standings};
+use super::telemetry::{DriverAid, TelemetryFrame};
+use std::mem::size_of;
+
+pub const DEFAULT_PORT: u16 = 20777;
+pub const MAX_CARS: usize = 22;
+const G: f32 = 9.81;
+
+pub const PACKET_MOTION: u8 = 0;
+pub const PACKET_SESSION: u8 = 1;
+pub const PACKET_LAP_DATA: u8 = 2;
+pub const PACKET_PARTICIPANTS: u8 = 4;
+pub const PACKET_CAR_TELEMETRY: u8 = 6;
+pub const PACKET_CAR_STATUS: u8 = 7;
+
+#[repr(C, packed)]
+#[derive(Copy, Clone)]
+pub struct Header {
+    /// 2023 or 2024.
+    pub packet_format: u16,
+    pub _game_year: u8,
+    pub _game_major_version: u8,
+    pub _game_minor_version: u8,
+    pub _packet_version: u8,
+    pub packet_id: u8,
+    pub session_uid: u64,
+    pub _session_time: f32,
+    pub frame_identifier: u32,
+    pub _overall_frame_identifier: u32,
+    pub player_car_index: u8,
+    pub _secondary_player_car_index: u8,
+}
+
+#[repr(C, packed)]
+#[derive(Copy, Clone)]
+pub struct CarMotionData {
+    pub _world_position: [f32; 3],
+    pub _world_velocity: [f32; 3],
+    pub _world_forward_dir: [i16; 3],
+    pub _world_right_dir: [i16; 3],
+    pub g_force_lateral: f32,
+    pub g_force_longitudinal: f32,
+    pub g_force_vertical: f32,
+    pub _yaw: f32,
+    pub _pitch: f32,
+    pub _roll: f32,
+}
+
+/// The start of the session packet.
+#[repr(C, packed)]
+#[derive(Copy, Clone)]
+pub struct SessionData {
+    /// 0 clear, 1 light cloud, 2 overcast, 3 light rain, 4 heavy rain, 5 storm.
+    pub weather: u8,
+    pub _track_temperature: i8,
+    pub _air_temperature: i8,
+    pub _total_laps: u8,
+    /// m
+    pub track_length: u16,
+    pub session_type: u8,
+    pub track_id: i8,
+}
+
+/// F1 24 lap data; F1 23 lacks the minute parts of the deltas and the speed
+/// trap fields, see [`LapData23`].
+#[repr(C, packed)]
+#[derive(Copy, Clone)]
+pub struct LapData {
+    pub last_lap_time_ms: u32,
+    pub current_lap_time_ms: u32,
+    pub _sector_1_time_ms: u16,
+    pub _sector_1_time_minutes: u8,
+    pub _sector_2_time_ms: u16,
+    pub _sector_2_time_minutes: u8,
+    pub _delta_to_car_in_front_ms: u16,
+    pub _delta_to_car_in_front_minutes: u8,
+    pub _delta_to_race_leader_ms: u16,
+    pub _delta_to_race_leader_minutes: u8,
+    /// m from the line, negative before crossing it for the first time.
+    pub lap_distance: f32,
+    pub _total_distance: f32,
+    pub _safety_car_delta: f32,
+    pub car_position: u8,
+    pub current_lap_num: u8,
+    /// 0 none, 1 pitting, 2 in the pit area.
+    pub pit_status: u8,
+    pub _num_pit_stops: u8,
+    /// 0 to 2.
+    pub sector: u8,
+    pub current_lap_invalid: u8,
+    pub _penalties: u8,
+    pub _total_warnings: u8,
+    pub _corner_cutting_warnings: u8,
+    pub _num_unserved_drive_through_pens: u8,
+    pub _num_unserved_stop_go_pens: u8,
+    pub _grid_position: u8,
+    /// 0 in garage, 1 flying lap, 2 in lap, 3 out lap, 4 on track.
+    pub driver_status: u8,
+    /// 2 active, 3 finished; the others mean the car is out.
+    pub result_status: u8,
+    pub _pit_lane_timer_active: u8,
+    pub _pit_lane_time_in_lane_ms: u16,
+    pub _pit_stop_timer_ms: u16,
+    pub _pit_stop_should_serve_pen: u8,
+    pub _speed_trap_fastest_speed: f32,
+    pub _speed_trap_fastest_lap: u8,
+}
+
+#[repr(C, packed)]
+#[derive(Copy, Clone)]
+pub struct LapData23 {
+    pub last_lap_time_ms: u32,
+    pub current_lap_time_ms: u32,
+    pub _sector_1_time_ms: u16,
+    pub _sector_1_time_minutes: u8,
+    pub _sector_2_time_ms: u16,
+    pub _sector_2_time_minutes: u8,
+    pub _delta_to_car_in_front_ms: u16,
+    pub _delta_to_race_leader_ms: u16,
+    pub lap_distance: f32,
+    pub _total_distance: f32,
+    pub _safety_car_delta: f32,
+    pub car_position: u8,
+    pub current_lap_num: u8,
+    pub pit_status: u8,
+    pub _num_pit_stops: u8,
+    pub sector: u8,
+    pub current_lap_invalid: u8,
+    pub _penalties: u8,
+    pub _total_warnings: u8,
+    pub _corner_cutting_warnings: u8,
+    pub _num_unserved_drive_through_pens: u8,
+    pub _num_unserved_stop_go_pens: u8,
+    pub _grid_position: u8,
+    pub driver_status: u8,
+    pub result_status: u8,
+    pub _pit_lane_timer_active: u8,
+    pub _pit_lane_time_in_lane_ms: u16,
+    pub _pit_stop_timer_ms: u16,
+    pub _pit_stop_should_serve_pen: u8,
+}
+
+/// F1 24 participant; F1 23 has no tech level, see [`ParticipantData23`].
+#[repr(C, packed)]
+#[derive(Copy, Clone)]
+pub struct ParticipantData {
+    pub _ai_controlled: u8,
+    pub _driver_id: u8,
+    pub _network_id: u8,
+    pub team_id: u8,
+    pub _my_team: u8,
+    pub _race_number: u8,
+    pub _nationality: u8,
+    pub name: [u8; 48],
+    pub _your_telemetry: u8,
+    pub _show_online_names: u8,
+    pub _tech_level: u16,
+    pub _platform: u8,
+}
+
+#[repr(C, packed)]
+#[derive(Copy, Clone)]
+pub struct ParticipantData23 {
+    pub _ai_controlled: u8,
+    pub _driver_id: u8,
+    pub _network_id: u8,
+    pub team_id: u8,
+    pub _my_team: u8,
+    pub _race_number: u8,
+    pub _nationality: u8,
+    pub name: [u8; 48],
+    pub _your_telemetry: u8,
+    pub _show_online_names: u8,
+    pub _platform: u8,
+}
+
+#[repr(C, packed)]
+#[derive(Copy, Clone)]
+pub struct CarTelemetryData {
+    /// km/h
+    pub speed: u16,
+    pub throttle: f32,
+    pub steer: f32,
+    pub brake: f32,
+    /// 0 to 100.
+    pub clutch: u8,
+    /// -1 reverse, 0 neutral, 1 first.
+    pub gear: i8,
+    pub engine_rpm: u16,
+    pub _drs: u8,
+    pub _rev_lights_percent: u8,
+    pub _rev_lights_bit_value: u16,
+    pub _brakes_temperature: [u16; 4],
+    pub _tyres_surface_temperature: [u8; 4],
+    pub _tyres_inner_temperature: [u8; 4],
+    pub _engine_temperature: u16,
+    pub _tyres_pressure: [f32; 4],
+    pub _surface_type: [u8; 4],
+}
+
+#[repr(C, packed)]
+#[derive(Copy, Clone)]
+pub struct CarStatusData {
+    /// 0 off, 1 medium, 2 full.
+    pub traction_control: u8,
+    pub anti_lock_brakes: u8,
+    pub _fuel_mix: u8,
+    /// Percent front.
+    pub front_brake_bias: u8,
+    pub pit_limiter_status: u8,
+    /// kg; the games count fuel by mass.
+    pub fuel_in_tank: f32,
+    pub fuel_capacity: f32,
+    pub _fuel_remaining_laps: f32,
+    pub max_rpm: u16,
+    pub _idle_rpm: u16,
+    pub _max_gears: u8,
+    pub _drs_allowed: u8,
+    pub _drs_activation_distance: u16,
+    pub _actual_tyre_compound: u8,
+    pub _visual_tyre_compound: u8,
+    pub _tyres_age_laps: u8,
+    /// -1 invalid, 0 none, 1 green, 2 blue, 3 yellow.
+    pub vehicle_fia_flags: i8,
+    pub _engine_power_ice: f32,
+    pub _engine_power_mguk: f32,
+    pub _ers_store_energy: f32,
+    pub _ers_deploy_mode: u8,
+    pub _ers_harvested_this_lap_mguk: f32,
+    pub _ers_harvested_this_lap_mguh: f32,
+    pub _ers_deployed_this_lap: f32,
+    pub _network_paused: u8,
+}
+
+// Sizes from the F1 23 / F1 24 UDP specifications
+const _: () = assert!(size_of::<Header>() == 29);
+const _: () = assert!(size_of::<CarMotionData>() == 60);
+const _: () = assert!(size_of::<LapData>() == 57);
+const _: () = assert!(size_of::<LapData23>() == 50);
+const _: () = assert!(size_of::<ParticipantData>() == 60);
+const _: () = assert!(size_of::<ParticipantData23>() == 58);
+const _: () = assert!(size_of::<CarTelemetryData>() == 60);
+const _: () = assert!(size_of::<CarStatusData>() == 55);
+
+unsafe impl ShmPage for Header {}
+unsafe impl ShmPage for CarMotionData {}
+unsafe impl ShmPage for SessionData {}
+unsafe impl ShmPage for LapData {}
+unsafe impl ShmPage for LapData23 {}
+unsafe impl ShmPage for ParticipantData {}
+unsafe impl ShmPage for ParticipantData23 {}
+unsafe impl ShmPage for CarTelemetryData {}
+unsafe impl ShmPage for CarStatusData {}
+
+/// What the overlay keeps of one car's lap data, the same for both formats.
+#[derive(Copy, Clone, Default)]
+pub struct CarLap {
+    pub last_lap_time_ms: u32,
+    pub current_lap_time_ms: u32,
+    pub lap_distance: f32,
+    pub car_position: u8,
+    pub current_lap_num: u8,
+    pub pit_status: u8,
+    pub sector: u8,
+    pub current_lap_invalid: u8,
+    pub driver_status: u8,
+    pub result_status: u8,
+}
+
+impl From<LapData> for CarLap {
+    fn from(l: LapData) -> Self {
+        Self {
+            last_lap_time_ms: l.last_lap_time_ms,
+            current_lap_time_ms: l.current_lap_time_ms,
+            lap_distance: l.lap_distance,
+            car_position: l.car_position,
+            current_lap_num: l.current_lap_num,
+            pit_status: l.pit_status,
+            sector: l.sector,
+            current_lap_invalid: l.current_lap_invalid,
+            driver_status: l.driver_status,
+            result_status: l.result_status,
+        }
+    }
+}
+
+impl From<LapData23> for CarLap {
+    fn from(l: LapData23) -> Self {
+        Self {
+            last_lap_time_ms: l.last_lap_time_ms,
+            current_lap_time_ms: l.current_lap_time_ms,
+            lap_distance: l.lap_distance,
+            car_position: l.car_position,
+            current_lap_num: l.current_lap_num,
+            pit_status: l.pit_status,
+            sector: l.sector,
+            current_lap_invalid: l.current_lap_invalid,
+            driver_status: l.driver_status,
+            result_status: l.result_status,
+        }
+    }
+}
+
+/// The state built up from the packets of one session. Packets arrive at
+/// different rates (telemetry at the configured rate, participants every few
+/// seconds), so each one only updates its part.
+#[derive(Clone, Default)]
+pub struct Session {
+    pub packet_format: u16,
+    pub session_uid: u64,
+    pub player: usize,
+    pub session: Option<SessionData>,
+    pub motion: Option<CarMotionData>,
+    pub laps: Vec<CarLap>,
+    /// (name, team id) of every car.
+    pub participants: Vec<(String, u8)>,
+    pub telemetry: Option<CarTelemetryData>,
+    pub status: Option<CarStatusData>,
+    /// Best lap of every car, tracked from their last laps.
+    pub best_laps: Vec<u32>,
+}
+
+impl Session {
+    /// Applies one packet. Returns the header if the packet was understood.
+    pub fn apply(&mut self, packet: &[u8]) -> Option<Header> {
+        let header = Header::from_bytes(packet)?;
+        let format = header.packet_format;
+        if format != 2023 && format != 2024 {
+            return None;
+        }
+        let session_uid = header.session_uid;
+        if session_uid != self.session_uid || format != self.packet_format {
+            *self = Self {
+                packet_format: format,
+                session_uid,
+                ..Self::default()
+            };
+        }
+        let player = header.player_car_index as usize;
+        if player >= MAX_CARS {
+            return None;
+        }
+        self.player = player;
+
+        let body = &packet[size_of::<Header>()..];
+        match header.packet_id {
+            PACKET_MOTION => self.motion = Some(car::<CarMotionData>(body, player)?),
+            PACKET_SESSION => self.session = Some(SessionData::from_bytes(body)?),
+            PACKET_LAP_DATA => {
+                let laps: Vec<CarLap> = if format == 2023 {
+                    cars::<LapData23>(body).map(CarLap::from).collect()
+                } else {
+                    cars::<LapData>(body).map(CarLap::from).collect()
+                };
+                if laps.len() < MAX_CARS {
+                    return None;
+                }
+                self.best_laps.resize(MAX_CARS, 0);
+                for (best, lap) in self.best_laps.iter_mut().zip(&laps) {
+                    if lap.last_lap_time_ms > 0 && (*best == 0 || lap.last_lap_time_ms < *best) {
+                        *best = lap.last_lap_time_ms;
+                    }
+                }
+                self.laps = laps;
+            }
+            PACKET_PARTICIPANTS => {
+                // Starts with the number of active cars
+                let body = body.get(1..)?;
+                self.participants = if format == 2023 {
+                    cars::<ParticipantData23>(body)
+                        .map(|p| (super::parse_byte_string(&p.name), p.team_id))
+                        .collect()
+                } else {
+                    cars::<ParticipantData>(body)
+                        .map(|p| (super::parse_byte_string(&p.name), p.team_id))
+                        .collect()
+                };
+            }
+            PACKET_CAR_TELEMETRY => self.telemetry = Some(car::<CarTelemetryData>(body, player)?),
+            PACKET_CAR_STATUS => self.status = Some(car::<CarStatusData>(body, player)?),
+            _ => return None,
+        }
+        Some(header)
+    }
+
+    /// The player's car; `None` until its telemetry packet arrived.
+    pub fn telemetry(&self) -> Option<TelemetryFrame> {
+        let t = self.telemetry?;
+        let mut frame = TelemetryFrame {
+            status: Some(AcStatus::Live),
+            throttle: t.throttle,
+            brake: t.brake,
+            clutch: t.clutch as f32 / 100.0,
+            steering: t.steer,
+            gear: t.gear as i32,
+            speed: t.speed as f32 / 3.6,
+            rpm: t.engine_rpm as f32,
+            ignition_on: true,
+            engine_running: t.engine_rpm > 0,
+            ..TelemetryFrame::default()
+        };
+        if let Some(m) = self.motion {
+            // The games report g with positive longitudinal when accelerating
+            frame.acceleration = [
+                m.g_force_lateral * G,
+                m.g_force_vertical * G,
+                m.g_force_longitudinal * G,
+            ];
+        }
+        if let Some(s) = self.status {
+            frame.max_rpm = s.max_rpm as f32;
+            frame.fuel = Some(s.fuel_in_tank);
+            frame.fuel_percent =
+                (s.fuel_capacity > 0.0).then(|| s.fuel_in_tank / s.fuel_capacity * 100.0);
+            frame.brake_bias = (s.front_brake_bias > 0).then_some(s.front_brake_bias as f32);
+            frame.tc = DriverAid {
+                level: s.traction_control as i32,
+                active: false,
+            };
+            frame.abs = DriverAid {
+                level: s.anti_lock_brakes as i32,
+                active: false,
+            };
+            frame.pit_limiter_on = s.pit_limiter_status != 0;
+            frame.flag = Some(match s.vehicle_fia_flags {
+                1 => FlagType::Green,
+                2 => FlagType::Blue,
+                3 => FlagType::Yellow,
+                _ => FlagType::None,
+            });
+        }
+        if let Some(s) = self.session {
+            frame.session_type = Some(self.session_type(s.session_type));
+            frame.rain = Some(match s.weather {
+                0..=2 => RainIntensity::NoRain,
+                3 => RainIntensity::LightRain,
+                4 => RainIntensity::HeavyRain,
+                _ => RainIntensity::Thunderstorm,
+            });
+        }
+        if let Some(lap) = self.laps.get(self.player) {
+            frame.in_pit = lap.pit_status == 2 || lap.driver_status == 0;
+            frame.in_pit_lane = lap.pit_status != 0;
+            frame.completed_laps = lap.current_lap_num.saturating_sub(1) as i32;
+            frame.current_lap_time = lap_time(lap.current_lap_time_ms);
+            frame.last_lap_time = lap_time(lap.last_lap_time_ms);
+            frame.best_lap_time = lap_time(self.best_laps[self.player]);
+            frame.current_sector = lap.sector as i32;
+            frame.lap_invalid = lap.current_lap_invalid != 0;
+            frame.lap_fraction = self.lap_fraction(lap);
+        }
+        Some(frame)
+    }
+
+    pub fn standings(&self) -> Option<Standings> {
+        if self.laps.is_empty() || self.participants.is_empty() {
+            return None;
+        }
+        let cars = self
+            .laps
+            .iter()
+            .zip(&self.participants)
+            .enumerate()
+            // Position 0: slot not used in this session
+            .filter(|(_, (lap, _))| lap.car_position > 0 && lap.result_status >= 2)
+            .map(|(i, (lap, (name, team)))| CarStanding {
+                id: i as i32,
+                position: lap.car_position as i32,
+                driver: name.clone(),
+                car_model: self.car_model(),
+                car_id: None,
+                class: format!("team {}", team),
+                completed_laps: lap.current_lap_num.saturating_sub(1) as i32,
+                lap_fraction: self.lap_fraction(lap),
+                last_lap_time: lap_time(lap.last_lap_time_ms),
+                best_lap_time: lap_time(self.best_laps[i]),
+                // Only the sectors of the current lap are sent
+                best_sectors: Default::default(),
+                in_pit: lap.pit_status != 0,
+                is_player: i == self.player,
+            })
+            .collect();
+        Some(Standings {
+            track_length: self.session.map_or(0.0, |s| s.track_length as f32),
+            cars,
+        })
+    }
+
+    /// All cars of a season share one profile, e.g. `f1_24`.
+    pub fn car_model(&self) -> String {
+        format!("f1_{}", self.packet_format % 100)
+    }
+
+    pub fn track(&self) -> String {
+        match self.session {
+            Some(s) => format!("f1_track_{}", s.track_id),
+            None => String::new(),
+        }
+    }
+
+    fn session_type(&self, raw: u8) -> SessionType {
+        // F1 24 inserted the sprint shootout sessions before the race
+        let race = if self.packet_format == 2023 { 10 } else { 15 };
+        match raw {
+            1..=4 => SessionType::Practice,
+            r if (5..race).contains(&r) => SessionType::Qualify,
+            r if (race..race + 3).contains(&r) => SessionType::Race,
+            r if r == race + 3 => SessionType::Hotlap,
+            _ => SessionType::Unknown,
+        }
+    }
+
+    fn lap_fraction(&self, lap: &CarLap) -> f32 {
+        match self.session {
+            Some(s) if s.track_length > 0 && lap.lap_distance > 0.0 => {
+                (lap.lap_distance / s.track_length as f32).clamp(0.0, 1.0)
+            }
+            _ => 0.0,
+        }
+    }
+}
+
+fn lap_time(ms: u32) -> Option<f64> {
+    (ms > 0).then(|| ms as f64 / 1000.0)
+}
+
+/// The per-car array at the start of a packet body.
+fn cars<T: ShmPage>(body: &[u8]) -> impl Iterator<Item = T> + '_ {
+    body.chunks_exact(size_of::<T>())
+        .take(MAX_CARS)
+        .filter_map(T::from_bytes)
+}
+
+fn car<T: ShmPage>(body: &[u8], index: usize) -> Option<T> {
+    T::from_bytes(body.get(index * size_of::<T>()..)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAYER: usize = 3;
+
+    fn packet(format: u16, packet_id: u8, body: &[u8]) -> Vec<u8> {
+        let mut header = Header::zeroed();
+        header.packet_format = format;
+        header.packet_id = packet_id;
+        header.session_uid = 7;
+        header.player_car_index = PLAYER as u8;
+        let mut bytes = header.as_bytes().to_vec();
+        bytes.extend_from_slice(body);
+        bytes
+    }
+
+    /// A per car body with `car` in the player's slot and `other` in the rest.
+    fn cars_body<T: ShmPage>(car: &T, other: &T) -> Vec<u8> {
+        (0..MAX_CARS)
+            .flat_map(|i| if i == PLAYER { car } else { other }.as_bytes().to_vec())
+            .collect()
+    }
+
+    fn session_packet(format: u16, session_type: u8) -> Vec<u8> {
+        let mut session = SessionData::zeroed();
+        session.track_length = 5000;
+        session.session_type = session_type;
+        session.weather = 3;
+        packet(format, PACKET_SESSION, session.as_bytes())
+    }
+
+    fn car_packets(format: u16) -> Vec<Vec<u8>> {
+        let mut telemetry = CarTelemetryData::zeroed();
+        telemetry.speed = 252;
+        telemetry.throttle = 1.0;
+        telemetry.gear = 6;
+        telemetry.engine_rpm = 11200;
+        let mut status = CarStatusData::zeroed();
+        status.max_rpm = 13000;
+        status.fuel_in_tank = 40.0;
+        status.fuel_capacity = 100.0;
+        status.front_brake_bias = 56;
+        let zero_telemetry = CarTelemetryData::zeroed();
+        let zero_status = CarStatusData::zeroed();
+        vec![
+            packet(
+                format,
+                PACKET_CAR_TELEMETRY,
+                &cars_body(&telemetry, &zero_telemetry),
+            ),
+            packet(format, PACKET_CAR_STATUS, &cars_body(&status, &zero_status)),
+        ]
+    }
+
+    fn decode(packets: &[Vec<u8>]) -> Session {
+        let mut session = Session::default();
+        for bytes in packets {
+            assert!(session.apply(bytes).is_some());
+        }
+        session
+    }
+
+    fn assert_player_car(frame: &TelemetryFrame) {
+        assert_eq!(frame.gear, 6);
+        assert_eq!(frame.rpm, 11200.0);
+        assert_eq!(frame.max_rpm, 13000.0);
+        assert!((frame.speed_kmh() - 252.0).abs() < 1e-3);
+        assert_eq!(frame.fuel, Some(40.0));
+        assert_eq!(frame.fuel_percent, Some(40.0));
+        assert_eq!(frame.brake_bias, Some(56.0));
+        assert_eq!(frame.rain, Some(RainIntensity::LightRain));
+        assert_eq!(frame.session_type, Some(SessionType::Race));
+    }
+
+    #[test]
+    fn decodes_f1_24() {
+        let mut lap = LapData::zeroed();
+        lap.last_lap_time_ms = 91_234;
+        lap.current_lap_time_ms = 45_000;
+        lap.current_lap_num = 5;
+        lap.sector = 1;
+        lap.lap_distance = 2500.0;
+        lap.car_position = 2;
+        lap.driver_status = 4;
+        lap.result_status = 2;
+        let mut packets = car_packets(2024);
+        packets.push(session_packet(2024, 15));
+        packets.push(packet(
+            2024,
+            PACKET_LAP_DATA,
+            &cars_body(&lap, &LapData::zeroed()),
+        ));
+        let session = decode(&packets);
+
+        let frame = session.telemetry().unwrap();
+        assert_player_car(&frame);
+        assert_eq!(frame.completed_laps, 4);
+        assert_eq!(frame.current_sector, 1);
+        assert_eq!(frame.current_lap_time, Some(45.0));
+        assert_eq!(frame.last_lap_time, Some(91.234));
+        assert_eq!(frame.best_lap_time, Some(91.234));
+        assert_eq!(frame.lap_fraction, 0.5);
+        assert!(!frame.in_pit);
+        assert_eq!(session.car_model(), "f1_24");
+    }
+
+    #[test]
+    fn decodes_f1_23() {
+        let mut lap = LapData23::zeroed();
+        lap.last_lap_time_ms = 88_000;
+        lap.current_lap_num = 2;
+        lap.sector = 2;
+        lap.lap_distance = 4000.0;
+        lap.car_position = 1;
+        lap.driver_status = 4;
+        lap.result_status = 2;
+        // The race is session 10 in F1 23
+        let mut packets = car_packets(2023);
+        packets.push(session_packet(2023, 10));
+        packets.push(packet(
+            2023,
+            PACKET_LAP_DATA,
+            &cars_body(&lap, &LapData23::zeroed()),
+        ));
+        let session = decode(&packets);
+
+        let frame = session.telemetry().unwrap();
+        assert_player_car(&frame);
+        assert_eq!(frame.completed_laps, 1);
+        assert_eq!(frame.current_sector, 2);
+        assert_eq!(frame.last_lap_time, Some(88.0));
+        assert_eq!(frame.lap_fraction, 0.8);
+        assert_eq!(session.car_model(), "f1_23");
+    }
+
+    #[test]
+    fn rejects_short_packets_and_starts_over_on_a_new_session() {
+        let mut session = decode(&car_packets(2024));
+        assert!(session.telemetry().is_some());
+        // Lap data for fewer than all cars
+        let short = packet(2024, PACKET_LAP_DATA, LapData::zeroed().as_bytes());
+        assert!(session.apply(&short).is_none());
+        assert!(session.apply(&[0; 10]).is_none());
+
+        let mut header = Header::zeroed();
+        header.packet_format = 2024;
+        header.packet_id = PACKET_SESSION;
+        header.session_uid = 8;
+        let mut bytes = header.as_bytes().to_vec();
+        bytes.extend_from_slice(SessionData::zeroed().as_bytes());
+        assert!(session.apply(&bytes).is_some());
+        assert!(session.telemetry().is_none());
+    }
+}
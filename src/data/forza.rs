@@ -0,0 +1,279 @@
+//! Forza "Data Out" UDP telemetry.
+//!
+//! The format is told apart by the packet length: "sled" (Motorsport 7, physics
+//! only), "dash" (Motorsport 7, sled plus dashboard data), Horizon 4/5 (dash
+//! with 12 undocumented bytes in between) and Motorsport (2023), which appends
+//! tyre wear and the track to dash.
+
+use super::ShmPage;
+use super::enums::AcStatus;
+use super::telemetry::TelemetryFrame;
+use std::mem::size_of;
+
+pub const DEFAULT_PORT: u16 = 5300;
+
+/// Packet layouts, by length.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Format {
+    Sled,
+    Dash,
+    Horizon,
+    Motorsport2023,
+}
+
+impl Format {
+    pub fn from_len(len: usize) -> Option<Self> {
+        match len {
+            232 => Some(Format::Sled),
+            311 => Some(Format::Dash),
+            324 => Some(Format::Horizon),
+            331 => Some(Format::Motorsport2023),
+            _ => None,
+        }
+    }
+
+    /// Where the dash part starts, if the format has one.
+    fn dash_offset(self) -> Option<usize> {
+        match self {
+            Format::Sled => None,
+            Format::Dash | Format::Motorsport2023 => Some(size_of::<Sled>()),
+            Format::Horizon => Some(size_of::<Sled>() + 12),
+        }
+    }
+
+    pub fn packet_len(self) -> usize {
+        match self {
+            Format::Sled => 232,
+            Format::Dash => 311,
+            Format::Horizon => 324,
+            Format::Motorsport2023 => 331,
+        }
+    }
+}
+
+/// The physics part every format starts with. Local axes: +x right, +y up,
+/// +z forward.
+#[repr(C, packed)]
+#[derive(Copy, Clone)]
+pub struct Sled {
+    /// 0 in menus and while paused.
+    pub is_race_on: i32,
+    pub timestamp_ms: u32,
+    pub engine_max_rpm: f32,
+    pub _engine_idle_rpm: f32,
+    pub current_engine_rpm: f32,
+    /// m/s²
+    pub acceleration: [f32; 3],
+    pub velocity: [f32; 3],
+    pub _angular_velocity: [f32; 3],
+    pub _yaw: f32,
+    pub _pitch: f32,
+    pub _roll: f32,
+    pub _normalized_suspension_travel: [f32; 4],
+    pub _tire_slip_ratio: [f32; 4],
+    pub _wheel_rotation_speed: [f32; 4],
+    pub _wheel_on_rumble_strip: [i32; 4],
+    pub _wheel_in_puddle_depth: [f32; 4],
+    pub _surface_rumble: [f32; 4],
+    pub _tire_slip_angle: [f32; 4],
+    pub _tire_combined_slip: [f32; 4],
+    pub _suspension_travel_meters: [f32; 4],
+    pub car_ordinal: i32,
+    pub _car_class: i32,
+    pub _car_performance_index: i32,
+    pub _drivetrain_type: i32,
+    pub _num_cylinders: i32,
+}
+
+#[repr(C, packed)]
+#[derive(Copy, Clone)]
+pub struct Dash {
+    pub _position: [f32; 3],
+    /// m/s
+    pub speed: f32,
+    pub _power: f32,
+    pub _torque: f32,
+    pub _tire_temp: [f32; 4],
+    pub _boost: f32,
+    /// Fraction of a full tank.
+    pub fuel: f32,
+    pub _distance_traveled: f32,
+    /// Seconds.
+    pub best_lap: f32,
+    pub last_lap: f32,
+    pub current_lap: f32,
+    pub _current_race_time: f32,
+    pub lap_number: u16,
+    pub _race_position: u8,
+    /// 0 to 255, like the other inputs.
+    pub accel: u8,
+    pub brake: u8,
+    pub clutch: u8,
+    pub _hand_brake: u8,
+    /// 0 reverse, 1 first; 11 neutral in Motorsport (2023).
+    pub gear: u8,
+    /// -127 to 127.
+    pub steer: i8,
+    pub _normalized_driving_line: i8,
+    pub _normalized_ai_brake_difference: i8,
+}
+
+// Forza Motorsport 7 "Data Out" documentation
+const _: () = assert!(size_of::<Sled>() == 232);
+const _: () = assert!(size_of::<Sled>() + size_of::<Dash>() == 311);
+
+unsafe impl ShmPage for Sled {}
+unsafe impl ShmPage for Dash {}
+
+/// One decoded packet.
+#[derive(Copy, Clone)]
+pub struct Packet {
+    pub format: Format,
+    pub sled: Sled,
+    pub dash: Option<Dash>,
+}
+
+impl Packet {
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        let format = Format::from_len(bytes.len())?;
+        let sled = Sled::from_bytes(bytes)?;
+        let dash = match format.dash_offset() {
+            Some(offset) => Some(Dash::from_bytes(&bytes[offset..])?),
+            None => None,
+        };
+        Some(Self { format, sled, dash })
+    }
+
+    /// The packet in `format`, for replaying; the bytes a format has beyond
+    /// sled and dash are left zero.
+    pub fn encode(&self, format: Format) -> Vec<u8> {
+        let mut bytes = vec![0; format.packet_len()];
+        bytes[..size_of::<Sled>()].copy_from_slice(self.sled.as_bytes());
+        if let (Some(offset), Some(dash)) = (format.dash_offset(), &self.dash) {
+            bytes[offset..offset + size_of::<Dash>()].copy_from_slice(dash.as_bytes());
+        }
+        bytes
+    }
+
+    /// Profile key of the car: Forza only reports its ordinal.
+    pub fn car_model(&self) -> String {
+        format!("forza_{}", { self.sled.car_ordinal })
+    }
+}
+
+impl TelemetryFrame {
+    pub fn from_forza(p: &Packet) -> Self {
+        let s = &p.sled;
+        let [ax, ay, az] = s.acceleration;
+        let [vx, vy, vz] = s.velocity;
+        let mut frame = Self {
+            status: Some(if s.is_race_on != 0 {
+                AcStatus::Live
+            } else {
+                AcStatus::Pause
+            }),
+            rpm: s.current_engine_rpm,
+            max_rpm: s.engine_max_rpm,
+            speed: (vx * vx + vy * vy + vz * vz).sqrt(),
+            acceleration: [ax, ay, az],
+            ignition_on: true,
+            engine_running: s.current_engine_rpm > 0.0,
+            ..Self::default()
+        };
+        let Some(d) = p.dash else {
+            return frame;
+        };
+        frame.speed = d.speed;
+        frame.throttle = d.accel as f32 / 255.0;
+        frame.brake = d.brake as f32 / 255.0;
+        frame.clutch = d.clutch as f32 / 255.0;
+        frame.steering = d.steer as f32 / 127.0;
+        frame.gear = match d.gear {
+            0 => -1,
+            g if g > 10 => 0,
+            g => g as i32,
+        };
+        // Only the fraction of a full tank is known
+        frame.fuel_percent = Some(d.fuel * 100.0);
+        frame.completed_laps = d.lap_number as i32;
+        frame.current_lap_time = lap_time(d.current_lap);
+        frame.last_lap_time = lap_time(d.last_lap);
+        frame.best_lap_time = lap_time(d.best_lap);
+        frame
+    }
+}
+
+fn lap_time(seconds: f32) -> Option<f64> {
+    (seconds > 0.0).then_some(seconds as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packet(gear: u8) -> Packet {
+        let mut sled = Sled::zeroed();
+        sled.is_race_on = 1;
+        sled.engine_max_rpm = 8000.0;
+        sled.current_engine_rpm = 6500.0;
+        sled.velocity = [3.0, 0.0, 4.0];
+        sled.car_ordinal = 2352;
+        let mut dash = Dash::zeroed();
+        dash.speed = 40.0;
+        dash.accel = 255;
+        dash.gear = gear;
+        dash.fuel = 0.25;
+        dash.lap_number = 3;
+        dash.last_lap = 92.5;
+        Packet {
+            format: Format::Dash,
+            sled,
+            dash: Some(dash),
+        }
+    }
+
+    #[test]
+    fn decodes_every_format() {
+        for format in [Format::Dash, Format::Horizon, Format::Motorsport2023] {
+            let bytes = packet(4).encode(format);
+            let parsed = Packet::parse(&bytes).unwrap();
+            assert_eq!(parsed.format, format);
+            assert_eq!(parsed.car_model(), "forza_2352");
+
+            let frame = TelemetryFrame::from_forza(&parsed);
+            assert_eq!(frame.status, Some(AcStatus::Live));
+            assert_eq!(frame.gear, 4);
+            assert_eq!(frame.rpm, 6500.0);
+            assert_eq!(frame.max_rpm, 8000.0);
+            assert_eq!(frame.speed, 40.0);
+            assert_eq!(frame.throttle, 1.0);
+            assert_eq!(frame.fuel, None);
+            assert_eq!(frame.fuel_percent, Some(25.0));
+            assert_eq!(frame.completed_laps, 3);
+            assert_eq!(frame.last_lap_time, Some(92.5));
+            assert_eq!(frame.current_lap_time, None);
+        }
+        assert!(Packet::parse(&[0; 300]).is_none());
+    }
+
+    #[test]
+    fn maps_reverse_and_neutral() {
+        let gear = |raw| TelemetryFrame::from_forza(&packet(raw)).gear;
+        assert_eq!(gear(0), -1);
+        assert_eq!(gear(1), 1);
+        assert_eq!(gear(11), 0);
+    }
+
+    #[test]
+    fn sled_has_no_dash_fields() {
+        let bytes = packet(4).encode(Format::Sled);
+        let parsed = Packet::parse(&bytes).unwrap();
+        assert!(parsed.dash.is_none());
+        let frame = TelemetryFrame::from_forza(&parsed);
+        // Speed from the velocity instead
+        assert_eq!(frame.speed, 5.0);
+        assert_eq!(frame.rpm, 6500.0);
+        assert_eq!(frame.gear, 0);
+        assert_eq!(frame.fuel_percent, None);
+    }
+}
@@ -10,18 +10,19 @@ pub struct Graphics {
     pub _split: [u16; 15],
     pub _completed_laps: i32,
     pub _position: i32,
-    pub _i_current_time: i32,
+    pub i_current_time: i32,
     pub _i_last_time: i32,
     pub _i_best_time: i32,
     pub _session_time_left: f32,
     pub _distance_traveled: f32,
     pub _is_in_pit: i32,
-    pub _current_sector_index: i32,
+    pub current_sector_index: i32,
     pub _last_sector_time: i32,
     pub _number_of_laps: i32,
     pub _tyre_compound: [u16; 33],
+    pub _padding_1: u16,
     pub _replay_time_multiplier: f32,
-    pub _normalized_car_position: f32,
+    pub normalized_car_position: f32,
     pub _active_cars: i32,
     pub _car_coordinates: [[f32; 3]; 60],
     pub _car_id: [i32; 60],
@@ -54,14 +55,17 @@ pub struct Graphics {
     pub _session_index: i32,
     pub _used_fuel: f32,
     pub _delta_lap_time: [u16; 15],
+    pub _padding_2: u16,
     pub _i_delta_lap_time: i32,
     pub _estimated_lap_time: [u16; 15],
+    pub _padding_3: u16,
     pub _i_estimated_lap_time: i32,
     pub _is_delta_positive: i32,
     pub _i_split: i32,
-    pub _is_valid_lap: i32,
+    pub is_valid_lap: i32,
     pub _fuel_estimated_laps: f32,
     pub _track_status: [u16; 33],
+    pub _padding_4: u16,
     pub _missing_mandatory_pits: i32,
     pub _clock: f32,
     pub _direction_light_left: i32,
@@ -86,3 +90,10 @@ pub struct Graphics {
     pub _gap_ahead: i32,
     pub _gap_behind: i32,
 }
+
+// SPageFileGraphic from the ACC shared memory documentation (SM 1.8/1.9)
+const _: () = assert!(std::mem::size_of::<Graphics>() == 1588);
+const _: () = assert!(std::mem::offset_of!(Graphics, _car_id) == 976);
+const _: () = assert!(std::mem::offset_of!(Graphics, tc) == 1268);
+const _: () = assert!(std::mem::offset_of!(Graphics, abs) == 1280);
+const _: () = assert!(std::mem::offset_of!(Graphics, _gap_behind) == 1584);
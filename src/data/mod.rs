@@ -4,6 +4,57 @@ mod graphics;
 pub use graphics::Graphics;
 mod statics;
 pub use statics::Statics;
+pub mod ac1;
+pub mod broadcast;
 mod car_data;
-pub use car_data::{get_car_by_name, parse_static_string};
+pub mod f1;
+pub mod forza;
+pub mod outgauge;
+pub mod pcars2;
+pub mod rf2;
+pub use car_data::{
+    CarClass, car_model_from_name, parse_byte_string, parse_static_string, write_static_string,
+};
+mod profiles;
+pub use profiles::{CarDatabase, CarProfile};
+mod enums;
+pub use enums::{
+    AcStatus, FlagType, InvalidValue, PenaltyType, RainIntensity, SessionType, TrackGripStatus,
+};
+mod snapshot;
+pub use snapshot::Snapshot;
+mod telemetry;
+pub use telemetry::{DriverAid, SECTORS, TelemetryFrame, sector_times};
+mod standings;
+pub use standings::{CarGap, CarStanding, Standings};
+mod acc;
 
+/// A `#[repr(C)]` shared memory page made only of plain numbers, with all
+/// padding spelled out as fields, so it can be copied to and from raw bytes.
+///
+/// # Safety
+/// Implementors must have no implicit padding and accept any bit pattern.
+pub unsafe trait ShmPage: Copy + 'static {
+    fn zeroed() -> Self {
+        unsafe { std::mem::zeroed() }
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self as *const Self as *const u8, size_of::<Self>()) }
+    }
+
+    fn as_bytes_mut(&mut self) -> &mut [u8] {
+        unsafe { std::slice::from_raw_parts_mut(self as *mut Self as *mut u8, size_of::<Self>()) }
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < size_of::<Self>() {
+            return None;
+        }
+        Some(unsafe { std::ptr::read_unaligned(bytes.as_ptr() as *const Self) })
+    }
+}
+
+unsafe impl ShmPage for Physics {}
+unsafe impl ShmPage for Graphics {}
+unsafe impl ShmPage for Statics {}
@@ -0,0 +1,199 @@
+//! OutGauge and OutSim, the UDP dashboard protocols of Live for Speed, also
+//! sent by BeamNG.drive.
+//!
+//! OutGauge carries what a dashboard shows, OutSim the motion of the car.
+//! Both may end with an optional `i32` id set in the game's config, so they
+//! are told apart by length.
+
+use super::ShmPage;
+use super::enums::AcStatus;
+use super::telemetry::{DriverAid, TelemetryFrame};
+use std::mem::size_of;
+
+pub const DEFAULT_OUTGAUGE_PORT: u16 = 4444;
+pub const DEFAULT_OUTSIM_PORT: u16 = 4123;
+
+/// Bits of [`OutGauge::dash_lights`] and [`OutGauge::show_lights`].
+pub const DL_PITSPEED: u32 = 1 << 3;
+pub const DL_TC: u32 = 1 << 4;
+pub const DL_ABS: u32 = 1 << 10;
+
+#[repr(C, packed)]
+#[derive(Copy, Clone)]
+pub struct OutGauge {
+    /// ms
+    pub time: u32,
+    /// Short car name, e.g. `XRT` in LFS and `beam` in BeamNG.
+    pub car: [u8; 4],
+    pub _flags: u16,
+    /// 0 reverse, 1 neutral, 2 first.
+    pub gear: u8,
+    pub _player_id: u8,
+    /// m/s
+    pub speed: f32,
+    pub rpm: f32,
+    pub _turbo: f32,
+    pub _eng_temp: f32,
+    /// 0 to 1.
+    pub fuel: f32,
+    pub _oil_pressure: f32,
+    pub _oil_temp: f32,
+    /// Lights that are on.
+    pub dash_lights: u32,
+    /// Lights the car has.
+    pub show_lights: u32,
+    pub throttle: f32,
+    pub brake: f32,
+    pub clutch: f32,
+    pub _display1: [u8; 16],
+    pub _display2: [u8; 16],
+}
+
+/// World axes: x east, y north, z up; angles anticlockwise, heading 0
+/// facing north.
+#[repr(C, packed)]
+#[derive(Copy, Clone)]
+pub struct OutSim {
+    /// ms
+    pub time: u32,
+    pub _angular_velocity: [f32; 3],
+    pub heading: f32,
+    pub pitch: f32,
+    pub roll: f32,
+    /// m/s², world axes.
+    pub acceleration: [f32; 3],
+    pub _velocity: [f32; 3],
+    /// 1/65536 m
+    pub _position: [i32; 3],
+}
+
+// InSim documentation (InSim.txt, OutGaugePack and OutSimPack)
+const _: () = assert!(size_of::<OutGauge>() == 92);
+const _: () = assert!(size_of::<OutSim>() == 64);
+
+unsafe impl ShmPage for OutGauge {}
+unsafe impl ShmPage for OutSim {}
+
+/// Either packet, with or without the trailing id.
+pub enum Packet {
+    Gauge(OutGauge),
+    Sim(OutSim),
+}
+
+impl Packet {
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        const GAUGE: usize = size_of::<OutGauge>();
+        const SIM: usize = size_of::<OutSim>();
+        match bytes.len() {
+            GAUGE | 96 => OutGauge::from_bytes(bytes).map(Packet::Gauge),
+            SIM | 68 => OutSim::from_bytes(bytes).map(Packet::Sim),
+            _ => None,
+        }
+    }
+}
+
+impl OutGauge {
+    /// Profile key of the car, the short name in lower case.
+    pub fn car_model(&self) -> String {
+        super::car_model_from_name(&super::parse_byte_string(&self.car))
+    }
+}
+
+impl OutSim {
+    /// The acceleration in car coordinates, undoing heading, pitch and roll.
+    pub fn local_acceleration(&self) -> [f32; 3] {
+        let [x, y, z] = self.acceleration;
+        let (sh, ch) = self.heading.sin_cos();
+        let (x, y) = (ch * x + sh * y, -sh * x + ch * y);
+        let (sp, cp) = self.pitch.sin_cos();
+        let (y, z) = (cp * y + sp * z, -sp * y + cp * z);
+        let (sr, cr) = self.roll.sin_cos();
+        let (x, z) = (cr * x - sr * z, sr * x + cr * z);
+        // Car axes: x right, y forward, z up
+        [x, z, y]
+    }
+}
+
+impl TelemetryFrame {
+    /// `max_rpm` stays 0, as OutGauge does not send the rev limit.
+    pub fn from_outgauge(og: &OutGauge, os: Option<&OutSim>) -> Self {
+        let lit = |light: u32| og.dash_lights & light != 0;
+        // Aids the car does not have stay at level 0
+        let aid = |light: u32| DriverAid {
+            level: (og.show_lights & light != 0) as i32,
+            active: lit(light),
+        };
+        let mut frame = Self {
+            status: Some(AcStatus::Live),
+            throttle: og.throttle,
+            brake: og.brake,
+            clutch: og.clutch,
+            gear: og.gear as i32 - 1,
+            speed: og.speed,
+            rpm: og.rpm,
+            // Only the fraction of a full tank is known
+            fuel_percent: Some(og.fuel * 100.0),
+            tc: aid(DL_TC),
+            abs: aid(DL_ABS),
+            ignition_on: true,
+            engine_running: og.rpm > 0.0,
+            pit_limiter_on: lit(DL_PITSPEED),
+            ..Self::default()
+        };
+        if let Some(os) = os {
+            frame.acceleration = os.local_acceleration();
+            frame.orientation = [os.heading, os.pitch, os.roll];
+        }
+        frame
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::{FRAC_PI_2, FRAC_PI_6};
+
+    fn local(heading: f32, pitch: f32, roll: f32, acceleration: [f32; 3]) -> [f32; 3] {
+        let mut os = OutSim::zeroed();
+        os.heading = heading;
+        os.pitch = pitch;
+        os.roll = roll;
+        os.acceleration = acceleration;
+        os.local_acceleration()
+    }
+
+    fn assert_near(actual: [f32; 3], expected: [f32; 3]) {
+        let close = actual
+            .iter()
+            .zip(&expected)
+            .all(|(a, e)| (a - e).abs() < 1e-4);
+        assert!(close, "{:?} != {:?}", actual, expected);
+    }
+
+    #[test]
+    fn acceleration_in_car_axes() {
+        // Facing north, braking: [lateral, vertical, longitudinal]
+        assert_near(local(0.0, 0.0, 0.0, [0.0, -5.0, 9.81]), [0.0, 9.81, -5.0]);
+        // Facing west, accelerating and turning right (north)
+        assert_near(
+            local(FRAC_PI_2, 0.0, 0.0, [-5.0, 2.0, 0.0]),
+            [2.0, 0.0, 5.0],
+        );
+        // Nose up: some of the vertical acceleration is along the car
+        let (s, c) = FRAC_PI_6.sin_cos();
+        assert_near(
+            local(0.0, FRAC_PI_6, 0.0, [0.0, 0.0, 9.81]),
+            [0.0, 9.81 * c, 9.81 * s],
+        );
+        // Rolled: some of it is across the car
+        assert_near(
+            local(0.0, 0.0, FRAC_PI_6, [0.0, 0.0, 9.81]),
+            [-9.81 * s, 9.81 * c, 0.0],
+        );
+        // Heading and pitch together, facing east up a hill
+        assert_near(
+            local(-FRAC_PI_2, FRAC_PI_6, 0.0, [3.0, 0.0, 9.81]),
+            [0.0, 9.81 * c - 3.0 * s, 3.0 * c + 9.81 * s],
+        );
+    }
+}
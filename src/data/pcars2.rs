@@ -0,0 +1,541 @@
+//! Project CARS 2 shared memory (`$pcars2$`), which Automobilista 2 exports
+//! too, with a few fields appended.
+//!
+//! The writer bumps `mSequenceNumber` before and after each update, so it is
+//! odd while the block is being written.
+
+use super::ShmPage;
+use super::car_data::{car_model_from_name, parse_byte_string};
+use super::enums::{AcStatus, FlagType, RainIntensity, SessionType};
+use super::standings::{CarStanding, Standings};
+use super::telemetry::{DriverAid, TelemetryFrame};
+use std::mem::{offset_of, size_of};
+
+pub const FILE_NAME: &str = "$pcars2$";
+pub const PARTICIPANTS_MAX: usize = 64;
+const STRING_LENGTH_MAX: usize = 64;
+/// `mVersion` of the first layout with `mSequenceNumber`.
+pub const MIN_VERSION: u32 = 9;
+
+// mGameState
+const GAME_INGAME_PLAYING: u32 = 2;
+const GAME_INGAME_PAUSED: u32 = 3;
+const GAME_INGAME_INMENU_TIME_TICKING: u32 = 4;
+const GAME_INGAME_REPLAY: u32 = 6;
+const GAME_FRONT_END_REPLAY: u32 = 7;
+// mPitMode
+const PIT_MODE_IN_PIT: u32 = 2;
+const PIT_MODE_IN_GARAGE: u32 = 4;
+// mCarFlags
+const CAR_ENGINE_ACTIVE: u32 = 1 << 1;
+const CAR_SPEED_LIMITER: u32 = 1 << 3;
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct ParticipantInfo {
+    pub is_active: u8,
+    pub name: [u8; STRING_LENGTH_MAX],
+    pub _padding_1: [u8; 3],
+    pub _world_position: [f32; 3],
+    /// m from the line.
+    pub current_lap_distance: f32,
+    pub race_position: u32,
+    pub laps_completed: u32,
+    pub _current_lap: u32,
+    /// From 0.
+    pub current_sector: i32,
+}
+
+/// The PCARS2 block, up to `mSnowDensity`. The player's car is
+/// `participant_info[viewed_participant_index]`.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SharedMemory {
+    pub version: u32,
+    pub _build_version_number: u32,
+    pub game_state: u32,
+    /// 1 practice, 2 test, 3 qualifying, 4 formation lap, 5 race, 6 time trial.
+    pub session_state: u32,
+    pub _race_state: u32,
+    pub viewed_participant_index: i32,
+    pub num_participants: i32,
+    pub participant_info: [ParticipantInfo; PARTICIPANTS_MAX],
+
+    pub unfiltered_throttle: f32,
+    pub unfiltered_brake: f32,
+    pub unfiltered_steering: f32,
+    pub unfiltered_clutch: f32,
+
+    pub car_name: [u8; STRING_LENGTH_MAX],
+    pub _car_class_name: [u8; STRING_LENGTH_MAX],
+
+    pub _laps_in_event: u32,
+    pub track_location: [u8; STRING_LENGTH_MAX],
+    pub track_variation: [u8; STRING_LENGTH_MAX],
+    pub track_length: f32,
+
+    pub _num_sectors: i32,
+    pub lap_invalidated: u8,
+    pub _padding_1: [u8; 3],
+    /// Seconds, -1 when unset, like the other times.
+    pub best_lap_time: f32,
+    pub last_lap_time: f32,
+    pub current_time: f32,
+    pub _split_time_ahead: f32,
+    pub _split_time_behind: f32,
+    pub _split_time: f32,
+    pub _event_time_remaining: f32,
+    pub _personal_fastest_lap_time: f32,
+    pub _world_fastest_lap_time: f32,
+    pub _current_sector_times: [f32; 3],
+    pub _fastest_sector_times: [f32; 3],
+    pub _personal_fastest_sector_times: [f32; 3],
+    pub _world_fastest_sector_times: [f32; 3],
+
+    /// 1 green, 2 blue, 3/4 white, 5 red, 6/7 yellow, 8 black and white,
+    /// 9 black with orange circle, 10 black, 11 chequered.
+    pub highest_flag_colour: u32,
+    pub _highest_flag_reason: u32,
+
+    pub pit_mode: u32,
+    pub _pit_schedule: u32,
+
+    pub car_flags: u32,
+    pub _oil_temp_celsius: f32,
+    pub _oil_pressure_kpa: f32,
+    pub _water_temp_celsius: f32,
+    pub _water_pressure_kpa: f32,
+    pub _fuel_pressure_kpa: f32,
+    /// Fraction of `fuel_capacity`.
+    pub fuel_level: f32,
+    /// Litres.
+    pub fuel_capacity: f32,
+    /// m/s
+    pub speed: f32,
+    pub rpm: f32,
+    pub max_rpm: f32,
+    pub _brake: f32,
+    pub _throttle: f32,
+    pub _clutch: f32,
+    pub _steering: f32,
+    /// -1 reverse, 0 neutral, 1 first.
+    pub gear: i32,
+    pub _num_gears: i32,
+    pub _odometer_km: f32,
+    pub anti_lock_active: u8,
+    pub _padding_2: [u8; 3],
+    pub _last_opponent_collision_index: i32,
+    pub _last_opponent_collision_magnitude: f32,
+    pub _boost_active: u8,
+    pub _padding_3: [u8; 3],
+    pub _boost_amount: f32,
+
+    pub _orientation: [f32; 3],
+    pub _local_velocity: [f32; 3],
+    pub _world_velocity: [f32; 3],
+    pub _angular_velocity: [f32; 3],
+    /// m/s², +x left, +y up, +z rearwards.
+    pub local_acceleration: [f32; 3],
+    pub _world_acceleration: [f32; 3],
+    pub _extents_centre: [f32; 3],
+
+    pub _tyre_flags: [u32; 4],
+    pub _terrain: [u32; 4],
+    pub _tyre_y: [f32; 4],
+    pub _tyre_rps: [f32; 4],
+    pub _tyre_slip_speed: [f32; 4],
+    pub _tyre_temp: [f32; 4],
+    pub _tyre_grip: [f32; 4],
+    pub _tyre_height_above_ground: [f32; 4],
+    pub _tyre_lateral_stiffness: [f32; 4],
+    pub _tyre_wear: [f32; 4],
+    pub _brake_damage: [f32; 4],
+    pub _suspension_damage: [f32; 4],
+    pub _brake_temp_celsius: [f32; 4],
+    pub _tyre_tread_temp: [f32; 4],
+    pub _tyre_layer_temp: [f32; 4],
+    pub _tyre_carcass_temp: [f32; 4],
+    pub _tyre_rim_temp: [f32; 4],
+    pub _tyre_internal_air_temp: [f32; 4],
+
+    pub _crash_state: u32,
+    pub _aero_damage: f32,
+    pub _engine_damage: f32,
+
+    pub _ambient_temperature: f32,
+    pub _track_temperature: f32,
+    /// 0 to 1.
+    pub rain_density: f32,
+    pub _wind_speed: f32,
+    pub _wind_direction_x: f32,
+    pub _wind_direction_y: f32,
+    pub _cloud_brightness: f32,
+
+    pub sequence_number: u32,
+
+    pub _wheel_local_position_y: [f32; 4],
+    pub _suspension_travel: [f32; 4],
+    pub _suspension_velocity: [f32; 4],
+    pub _air_pressure: [f32; 4],
+    pub _engine_speed: f32,
+    pub _engine_torque: f32,
+    pub _wings: [f32; 2],
+    pub _hand_brake: f32,
+
+    pub _current_sector_1_times: [f32; PARTICIPANTS_MAX],
+    pub _current_sector_2_times: [f32; PARTICIPANTS_MAX],
+    pub _current_sector_3_times: [f32; PARTICIPANTS_MAX],
+    pub fastest_sector_1_times: [f32; PARTICIPANTS_MAX],
+    pub fastest_sector_2_times: [f32; PARTICIPANTS_MAX],
+    pub fastest_sector_3_times: [f32; PARTICIPANTS_MAX],
+    pub fastest_lap_times: [f32; PARTICIPANTS_MAX],
+    pub last_lap_times: [f32; PARTICIPANTS_MAX],
+    pub _laps_invalidated: [u8; PARTICIPANTS_MAX],
+    pub _race_states: [u32; PARTICIPANTS_MAX],
+    pub pit_modes: [u32; PARTICIPANTS_MAX],
+    pub _orientations: [[f32; 3]; PARTICIPANTS_MAX],
+    pub _speeds: [f32; PARTICIPANTS_MAX],
+    pub car_names: [[u8; STRING_LENGTH_MAX]; PARTICIPANTS_MAX],
+    pub car_class_names: [[u8; STRING_LENGTH_MAX]; PARTICIPANTS_MAX],
+
+    pub _enforced_pit_stop_lap: i32,
+    pub _translated_track_location: [u8; STRING_LENGTH_MAX],
+    pub _translated_track_variation: [u8; STRING_LENGTH_MAX],
+    /// Fraction of the brake force on the rear axle, -1 when unset.
+    pub brake_bias: f32,
+    pub _turbo_boost_pressure: f32,
+    pub _tyre_compound: [[u8; 40]; 4],
+    pub _pit_schedules: [u32; PARTICIPANTS_MAX],
+    pub _highest_flag_colours: [u32; PARTICIPANTS_MAX],
+    pub _highest_flag_reasons: [u32; PARTICIPANTS_MAX],
+    pub _nationalities: [u32; PARTICIPANTS_MAX],
+    pub _snow_density: f32,
+}
+
+/// Fields Automobilista 2 appends to [`SharedMemory`].
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct Ams2Extension {
+    pub _session_duration: f32,
+    pub _session_additional_laps: i32,
+    pub _tyre_temp_left: [f32; 4],
+    pub _tyre_temp_center: [f32; 4],
+    pub _tyre_temp_right: [f32; 4],
+    pub _drs_state: u32,
+    pub _ride_height: [f32; 4],
+    pub _joy_pad_0: u32,
+    pub _d_pad: u32,
+    pub anti_lock_setting: i32,
+    pub traction_control_setting: i32,
+    pub _ers_deployment_mode: i32,
+    pub _ers_auto_mode_enabled: u8,
+    pub _padding_1: [u8; 3],
+    pub _clutch_temp: f32,
+    pub _clutch_wear: f32,
+    pub _clutch_overheated: u8,
+    pub _clutch_slipping: u8,
+    pub _padding_2: [u8; 2],
+    pub _yellow_flag_state: i32,
+    pub _session_is_private: u8,
+    pub _padding_3: [u8; 3],
+    pub _launch_stage: i32,
+}
+
+/// The whole block as exported by AMS2.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct Ams2SharedMemory {
+    pub base: SharedMemory,
+    pub ext: Ams2Extension,
+}
+
+// SharedMemory.h of PCARS2 (version 9) and AMS2
+const _: () = assert!(size_of::<ParticipantInfo>() == 100);
+const _: () = assert!(offset_of!(SharedMemory, unfiltered_throttle) == 6428);
+const _: () = assert!(offset_of!(SharedMemory, best_lap_time) == 6716);
+const _: () = assert!(offset_of!(SharedMemory, highest_flag_colour) == 6800);
+const _: () = assert!(offset_of!(SharedMemory, gear) == 6876);
+const _: () = assert!(offset_of!(SharedMemory, sequence_number) == 7320);
+const _: () = assert!(offset_of!(SharedMemory, car_names) == 11056);
+const _: () = assert!(offset_of!(SharedMemory, brake_bias) == 19380);
+const _: () = assert!(size_of::<SharedMemory>() == 20576);
+const _: () = assert!(size_of::<Ams2SharedMemory>() == 20700);
+
+unsafe impl ShmPage for SharedMemory {}
+unsafe impl ShmPage for Ams2SharedMemory {}
+
+impl TelemetryFrame {
+    /// Telemetry of the viewed car; `ext` is only there for AMS2.
+    pub fn from_pcars2(sm: &SharedMemory, ext: Option<&Ams2Extension>) -> Self {
+        let player = sm.player();
+        let mut frame = Self {
+            status: Some(match sm.game_state {
+                GAME_INGAME_PLAYING | GAME_INGAME_INMENU_TIME_TICKING => AcStatus::Live,
+                GAME_INGAME_PAUSED => AcStatus::Pause,
+                GAME_INGAME_REPLAY | GAME_FRONT_END_REPLAY => AcStatus::Replay,
+                _ => AcStatus::Off,
+            }),
+            session_type: Some(match sm.session_state {
+                1 | 2 => SessionType::Practice,
+                3 => SessionType::Qualify,
+                4 | 5 => SessionType::Race,
+                6 => SessionType::TimeAttack,
+                _ => SessionType::Unknown,
+            }),
+            flag: Some(match sm.highest_flag_colour {
+                1 => FlagType::Green,
+                2 => FlagType::Blue,
+                3 | 4 => FlagType::White,
+                6 | 7 => FlagType::Yellow,
+                8 => FlagType::Penalty,
+                9 => FlagType::Orange,
+                10 => FlagType::Black,
+                11 => FlagType::Checkered,
+                _ => FlagType::None,
+            }),
+            rain: Some(RainIntensity::from_fraction(sm.rain_density)),
+            in_pit: matches!(sm.pit_mode, PIT_MODE_IN_PIT | PIT_MODE_IN_GARAGE),
+            in_pit_lane: sm.pit_mode != 0,
+
+            throttle: sm.unfiltered_throttle,
+            brake: sm.unfiltered_brake,
+            clutch: sm.unfiltered_clutch,
+            steering: sm.unfiltered_steering,
+            gear: sm.gear,
+            speed: sm.speed,
+            rpm: sm.rpm,
+            max_rpm: sm.max_rpm,
+            fuel: Some(sm.fuel_level * sm.fuel_capacity),
+            fuel_percent: (sm.fuel_capacity > 0.0).then_some(sm.fuel_level * 100.0),
+            brake_bias: (sm.brake_bias > 0.0).then_some((1.0 - sm.brake_bias) * 100.0),
+            // +z points rearwards
+            acceleration: {
+                let [x, y, z] = sm.local_acceleration;
+                [x, y, -z]
+            },
+            abs: DriverAid {
+                level: ext.map_or(0, |e| e.anti_lock_setting.max(0)),
+                active: sm.anti_lock_active != 0,
+            },
+            tc: DriverAid {
+                level: ext.map_or(0, |e| e.traction_control_setting.max(0)),
+                active: false,
+            },
+            ignition_on: sm.car_flags & CAR_ENGINE_ACTIVE != 0,
+            engine_running: sm.car_flags & CAR_ENGINE_ACTIVE != 0 && sm.rpm > 0.0,
+            pit_limiter_on: sm.car_flags & CAR_SPEED_LIMITER != 0,
+
+            current_lap_time: lap_time(sm.current_time),
+            last_lap_time: lap_time(sm.last_lap_time),
+            best_lap_time: lap_time(sm.best_lap_time),
+            lap_invalid: sm.lap_invalidated != 0,
+            ..Self::default()
+        };
+        if let Some(p) = player {
+            frame.completed_laps = p.laps_completed as i32;
+            frame.current_sector = p.current_sector.max(0);
+            frame.lap_fraction = lap_fraction(p.current_lap_distance, sm.track_length);
+        }
+        frame
+    }
+}
+
+impl SharedMemory {
+    /// Entries of `participant_info` the game fills in.
+    fn participant_count(&self) -> usize {
+        (self.num_participants.max(0) as usize).min(PARTICIPANTS_MAX)
+    }
+
+    pub fn player(&self) -> Option<&ParticipantInfo> {
+        let index = usize::try_from(self.viewed_participant_index).ok()?;
+        (index < self.participant_count()).then(|| &self.participant_info[index])
+    }
+
+    pub fn standings(&self) -> Standings {
+        let player = usize::try_from(self.viewed_participant_index).ok();
+        let cars = self.participant_info[..self.participant_count()]
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| p.is_active != 0)
+            .map(|(i, p)| CarStanding {
+                id: i as i32,
+                position: p.race_position as i32,
+                driver: parse_byte_string(&p.name),
+                car_model: car_model_from_name(&parse_byte_string(&self.car_names[i])),
+                car_id: None,
+                class: parse_byte_string(&self.car_class_names[i]),
+                completed_laps: p.laps_completed as i32,
+                lap_fraction: lap_fraction(p.current_lap_distance, self.track_length),
+                last_lap_time: lap_time(self.last_lap_times[i]),
+                best_lap_time: lap_time(self.fastest_lap_times[i]),
+                best_sectors: [
+                    lap_time(self.fastest_sector_1_times[i]),
+                    lap_time(self.fastest_sector_2_times[i]),
+                    lap_time(self.fastest_sector_3_times[i]),
+                ],
+                in_pit: self.pit_modes[i] != 0,
+                is_player: player == Some(i),
+            })
+            .collect();
+        Standings {
+            track_length: self.track_length,
+            cars,
+        }
+    }
+
+    /// Track and layout, e.g. "Interlagos GP".
+    pub fn track_name(&self) -> String {
+        let location = parse_byte_string(&self.track_location);
+        let variation = parse_byte_string(&self.track_variation);
+        if variation.is_empty() {
+            location
+        } else {
+            format!("{} {}", location, variation)
+        }
+    }
+}
+
+fn lap_time(seconds: f32) -> Option<f64> {
+    (seconds > 0.0).then_some(seconds as f64)
+}
+
+fn lap_fraction(distance: f32, track_length: f32) -> f32 {
+    if track_length > 0.0 {
+        (distance / track_length).clamp(0.0, 1.0)
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block() -> SharedMemory {
+        let mut sm = SharedMemory::zeroed();
+        sm.version = MIN_VERSION;
+        sm.game_state = GAME_INGAME_PLAYING;
+        sm.track_length = 4000.0;
+        sm.num_participants = 3;
+        sm.viewed_participant_index = 1;
+        for (i, name) in ["Ana", "Ben", "Cy"].iter().enumerate() {
+            let p = &mut sm.participant_info[i];
+            p.is_active = 1;
+            p.name[..name.len()].copy_from_slice(name.as_bytes());
+            p.race_position = i as u32 + 1;
+            p.laps_completed = 4;
+            p.current_lap_distance = 1000.0 * i as f32;
+            p.current_sector = i as i32;
+            sm.car_names[i][..9].copy_from_slice(b"Ginetta G");
+            sm.car_class_names[i][..3].copy_from_slice(b"GT5");
+            sm.fastest_lap_times[i] = 90.0 + i as f32;
+        }
+        sm
+    }
+
+    #[test]
+    fn maps_the_session_state() {
+        let mut sm = block();
+        let mut frame = |f: &dyn Fn(&mut SharedMemory)| {
+            f(&mut sm);
+            TelemetryFrame::from_pcars2(&sm, None)
+        };
+        assert_eq!(frame(&|_| {}).status, Some(AcStatus::Live));
+        let paused = frame(&|sm| sm.game_state = GAME_INGAME_PAUSED);
+        assert_eq!(paused.status, Some(AcStatus::Pause));
+        let replay = frame(&|sm| sm.game_state = GAME_FRONT_END_REPLAY);
+        assert_eq!(replay.status, Some(AcStatus::Replay));
+        let menu = frame(&|sm| sm.game_state = 1);
+        assert_eq!(menu.status, Some(AcStatus::Off));
+
+        let sessions = [0, 1, 2, 3, 4, 5, 6]
+            .map(|state| frame(&|sm| sm.session_state = state).session_type.unwrap());
+        assert_eq!(
+            sessions,
+            [
+                SessionType::Unknown,
+                SessionType::Practice,
+                SessionType::Practice,
+                SessionType::Qualify,
+                SessionType::Race,
+                SessionType::Race,
+                SessionType::TimeAttack,
+            ]
+        );
+
+        let flags = [0, 2, 4, 5, 7, 8, 9, 10, 11]
+            .map(|colour| frame(&|sm| sm.highest_flag_colour = colour).flag.unwrap());
+        assert_eq!(
+            flags,
+            [
+                FlagType::None,
+                FlagType::Blue,
+                FlagType::White,
+                FlagType::None,
+                FlagType::Yellow,
+                FlagType::Penalty,
+                FlagType::Orange,
+                FlagType::Black,
+                FlagType::Checkered,
+            ]
+        );
+
+        let pit = frame(&|sm| sm.pit_mode = PIT_MODE_IN_GARAGE);
+        assert!(pit.in_pit && pit.in_pit_lane);
+        let limiter = frame(&|sm| sm.car_flags = CAR_ENGINE_ACTIVE | CAR_SPEED_LIMITER);
+        assert!(limiter.ignition_on && limiter.pit_limiter_on);
+    }
+
+    #[test]
+    fn reads_the_viewed_car() {
+        let mut sm = block();
+        sm.fuel_level = 0.5;
+        sm.fuel_capacity = 100.0;
+        sm.brake_bias = 0.45;
+        sm.local_acceleration = [1.0, 2.0, 3.0];
+        let ext = Ams2Extension {
+            traction_control_setting: 3,
+            anti_lock_setting: -1,
+            ..unsafe { std::mem::zeroed() }
+        };
+        let frame = TelemetryFrame::from_pcars2(&sm, Some(&ext));
+        assert_eq!(frame.completed_laps, 4);
+        assert_eq!(frame.current_sector, 1);
+        assert_eq!(frame.lap_fraction, 0.25);
+        assert_eq!(frame.fuel, Some(50.0));
+        assert_eq!(frame.fuel_percent, Some(50.0));
+        assert_eq!(frame.brake_bias, Some(55.0));
+        assert_eq!(frame.acceleration, [1.0, 2.0, -3.0]);
+        assert_eq!((frame.tc.level, frame.abs.level), (3, 0));
+
+        // No viewed car, e.g. in the menus
+        sm.viewed_participant_index = -1;
+        assert_eq!(TelemetryFrame::from_pcars2(&sm, None).completed_laps, 0);
+    }
+
+    #[test]
+    fn standings_of_the_active_participants() {
+        let mut sm = block();
+        sm.participant_info[2].is_active = 0;
+        // Entries past num_participants are left out
+        sm.participant_info[3] = sm.participant_info[0];
+
+        let standings = sm.standings();
+        assert_eq!(standings.track_length, 4000.0);
+        let cars: Vec<_> = standings
+            .cars
+            .iter()
+            .map(|c| (c.driver.as_str(), c.position, c.is_player, c.best_lap_time))
+            .collect();
+        assert_eq!(
+            cars,
+            [("Ana", 1, false, Some(90.0)), ("Ben", 2, true, Some(91.0))]
+        );
+        assert_eq!(standings.cars[1].lap_fraction, 0.25);
+        assert_eq!(standings.cars[1].class, "GT5");
+        assert_eq!(
+            standings.cars[1].car_model,
+            car_model_from_name("Ginetta G")
+        );
+    }
+}
@@ -87,3 +87,12 @@ pub struct Physics {
     pub _g_vibrations: f32,
     pub _abs_vibrations: f32,
 }
+
+// SPageFilePhysics from the ACC shared memory documentation (SM 1.8/1.9)
+const _: () = assert!(std::mem::size_of::<Physics>() == 800);
+const _: () = assert!(std::mem::offset_of!(Physics, gear) == 16);
+const _: () = assert!(std::mem::offset_of!(Physics, tc) == 204);
+const _: () = assert!(std::mem::offset_of!(Physics, abs) == 252);
+const _: () = assert!(std::mem::offset_of!(Physics, brake_bias) == 564);
+const _: () = assert!(std::mem::offset_of!(Physics, current_max_rpm) == 588);
+const _: () = assert!(std::mem::offset_of!(Physics, ignition_on) == 772);
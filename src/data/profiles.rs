@@ -0,0 +1,695 @@
+//! Car profiles: the built-in `CAR_MODELS` table merged with user files
+//! from `~/.config/simtem/cars/`, looked up by ACC model name.
+//!
+//! Each `*.toml` or `*.json` file holds a list of `car` entries. An entry
+//! only needs `car_model`; every other field overrides the built-in value
+//! for that car. Files are applied in name order, so a later file wins.
+//!
+//! ```toml
+//! [[car]]
+//! car_model = "bmw_m4_gt4"
+//! class = "GT4"
+//! shift_rpm = 6900
+//! # per gear from 1st, 0 falls back to shift_rpm
+//! upshift_rpm = [6600, 6800, 6900, 6950, 7000]
+//! # per gear from 1st: highest rpm at which a downshift won't over-rev
+//! downshift_rpm = [0, 4800, 5200, 5500, 5700]
+//! ```
+
+use super::car_data::{CAR_MODELS, CarClass, CarModelData};
+use super::standings::Standings;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+#[derive(Clone, Debug)]
+pub struct CarProfile {
+    /// ACC's car model id, -1 when unknown. Unique across profiles.
+    pub car_id: i32,
+    pub car_model: String,
+    pub class: Option<CarClass>,
+    pub shift_rpm: i32,
+    pub bb_offset: f32,
+    pub brake_pressure_co: [f32; 2],
+    pub max_steering_angle: i32,
+    /// Upshift point per forward gear, index 0 = 1st. Zero or missing
+    /// entries fall back to `shift_rpm`.
+    pub upshift_rpm: Vec<i32>,
+    /// Highest rpm per forward gear at which downshifting is safe, index 0 = 1st.
+    pub downshift_rpm: Vec<i32>,
+}
+
+const MAX_GEARS: usize = 10;
+
+impl CarProfile {
+    /// Upshift point for forward gear `gear` (1 = first), if known.
+    pub fn upshift_rpm(&self, gear: i32) -> Option<i32> {
+        per_gear(&self.upshift_rpm, gear).or((self.shift_rpm > 0).then_some(self.shift_rpm))
+    }
+
+    /// Safe downshift rpm for forward gear `gear` (1 = first), if known.
+    pub fn downshift_rpm(&self, gear: i32) -> Option<i32> {
+        per_gear(&self.downshift_rpm, gear)
+    }
+
+    /// Neutral values for a car with no built-in entry.
+    fn generic(car_model: &str) -> Self {
+        Self {
+            car_id: -1,
+            car_model: car_model.to_string(),
+            class: None,
+            shift_rpm: 0,
+            bb_offset: 0.0,
+            brake_pressure_co: [10.0, 10.0],
+            max_steering_angle: 360,
+            upshift_rpm: Vec::new(),
+            downshift_rpm: Vec::new(),
+        }
+    }
+
+    pub(super) fn validate(&self) -> Result<(), String> {
+        let model_ok = !self.car_model.is_empty()
+            && self
+                .car_model
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+        if !model_ok {
+            return Err(format!("invalid car_model '{}'", self.car_model));
+        }
+        if self.shift_rpm != 0 && !(1000..=20000).contains(&self.shift_rpm) {
+            return Err(format!("shift_rpm {} out of range", self.shift_rpm));
+        }
+        for (name, table) in [
+            ("upshift_rpm", &self.upshift_rpm),
+            ("downshift_rpm", &self.downshift_rpm),
+        ] {
+            if table.len() > MAX_GEARS {
+                return Err(format!("{} has more than {} gears", name, MAX_GEARS));
+            }
+            if let Some(rpm) = table
+                .iter()
+                .find(|r| **r != 0 && !(1000..=20000).contains(*r))
+            {
+                return Err(format!("{} entry {} out of range", name, rpm));
+            }
+        }
+        if !(-50.0..=50.0).contains(&self.bb_offset) {
+            return Err(format!("bb_offset {} out of range", self.bb_offset));
+        }
+        if self
+            .brake_pressure_co
+            .iter()
+            .any(|co| !(*co > 0.0 && *co <= 100.0))
+        {
+            return Err(format!(
+                "brake_pressure_co {:?} out of range",
+                self.brake_pressure_co
+            ));
+        }
+        if !(90..=1080).contains(&self.max_steering_angle) {
+            return Err(format!(
+                "max_steering_angle {} out of range",
+                self.max_steering_angle
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl From<&CarModelData> for CarProfile {
+    fn from(car: &CarModelData) -> Self {
+        Self {
+            car_id: car.car_id,
+            car_model: car.car_model.to_string(),
+            class: Some(car.class),
+            shift_rpm: car.shift_rpm,
+            bb_offset: car.bb_offset,
+            brake_pressure_co: car.brake_pressure_co,
+            max_steering_angle: car.max_steering_angle,
+            upshift_rpm: Vec::new(),
+            downshift_rpm: Vec::new(),
+        }
+    }
+}
+
+fn per_gear(table: &[i32], gear: i32) -> Option<i32> {
+    let index = usize::try_from(gear - 1).ok()?;
+    table.get(index).copied().filter(|rpm| *rpm > 0)
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProfileFile {
+    #[serde(default)]
+    car: Vec<ProfileOverride>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProfileOverride {
+    car_model: String,
+    car_id: Option<i32>,
+    class: Option<CarClass>,
+    shift_rpm: Option<i32>,
+    bb_offset: Option<f32>,
+    brake_pressure_co: Option<[f32; 2]>,
+    max_steering_angle: Option<i32>,
+    upshift_rpm: Option<Vec<i32>>,
+    downshift_rpm: Option<Vec<i32>>,
+}
+
+impl ProfileOverride {
+    fn apply(&self, profile: &mut CarProfile) {
+        if let Some(v) = self.car_id {
+            profile.car_id = v;
+        }
+        if let Some(v) = self.class {
+            profile.class = Some(v);
+        }
+        if let Some(v) = self.shift_rpm {
+            profile.shift_rpm = v;
+        }
+        if let Some(v) = self.bb_offset {
+            profile.bb_offset = v;
+        }
+        if let Some(v) = self.brake_pressure_co {
+            profile.brake_pressure_co = v;
+        }
+        if let Some(v) = self.max_steering_angle {
+            profile.max_steering_angle = v;
+        }
+        if let Some(v) = &self.upshift_rpm {
+            profile.upshift_rpm = v.clone();
+        }
+        if let Some(v) = &self.downshift_rpm {
+            profile.downshift_rpm = v.clone();
+        }
+    }
+}
+
+pub struct CarDatabase {
+    dir: PathBuf,
+    profiles: Vec<CarProfile>,
+    /// Problems found in user files on the last load, one line each.
+    pub errors: Vec<String>,
+    signature: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+impl CarDatabase {
+    pub fn load(dir: impl Into<PathBuf>) -> Self {
+        let mut db = Self {
+            dir: dir.into(),
+            profiles: Vec::new(),
+            errors: Vec::new(),
+            signature: Vec::new(),
+        };
+        db.reload();
+        db
+    }
+
+    pub fn get_by_name(&self, name: &str) -> Option<&CarProfile> {
+        self.profiles.iter().find(|c| c.car_model == name)
+    }
+
+    pub fn get_by_id(&self, car_id: i32) -> Option<&CarProfile> {
+        self.profiles
+            .iter()
+            .find(|c| c.car_id >= 0 && c.car_id == car_id)
+    }
+
+    /// Names the cars of `standings` that carry an ACC car id after their
+    /// profile, so user files can add or reclassify cars.
+    pub fn resolve_standings(&self, standings: &mut Standings) {
+        for car in &mut standings.cars {
+            let Some(profile) = car.car_id.and_then(|id| self.get_by_id(id)) else {
+                continue;
+            };
+            car.car_model = profile.car_model.clone();
+            if let Some(class) = profile.class {
+                car.class = class.name().to_string();
+            }
+        }
+    }
+
+    pub fn profiles(&self) -> &[CarProfile] {
+        &self.profiles
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Rebuilds the database from the built-in table and the user files.
+    pub fn reload(&mut self) {
+        self.profiles = CAR_MODELS.iter().map(CarProfile::from).collect();
+        self.errors.clear();
+        self.signature = profile_files(&self.dir);
+
+        for (path, _) in self.signature.clone() {
+            match read_file(&path) {
+                Ok(file) => {
+                    for entry in file.car {
+                        self.merge(&path, entry);
+                    }
+                }
+                Err(e) => self.errors.push(format!("{}: {}", path.display(), e)),
+            }
+        }
+        for e in &self.errors {
+            println!("Car profiles: {}", e);
+        }
+    }
+
+    /// Sets the upshift rpm of the given `(gear, rpm)` pairs in the user's
+    /// profile for `car_model` (`<dir>/<car_model>.toml`), keeping the other
+    /// gears and everything else in that file, and reloads the database.
+    pub fn save_upshift_rpm(
+        &mut self,
+        car_model: &str,
+        shifts: &[(i32, i32)],
+    ) -> std::io::Result<PathBuf> {
+        let mut table = self
+            .get_by_name(car_model)
+            .map(|p| p.upshift_rpm.clone())
+            .unwrap_or_default();
+        for &(gear, rpm) in shifts.iter().filter(|(gear, _)| *gear >= 1) {
+            let index = (gear - 1) as usize;
+            if table.len() <= index {
+                table.resize(index + 1, 0);
+            }
+            table[index] = rpm;
+        }
+        // Also keeps `car_model` from naming a file outside the directory
+        let profile = CarProfile {
+            upshift_rpm: table,
+            ..CarProfile::generic(car_model)
+        };
+        profile.validate().map_err(|e| invalid(&e))?;
+        let table = profile.upshift_rpm;
+
+        let path = self.dir.join(format!("{}.toml", car_model));
+        let mut doc: toml::Table = match std::fs::read_to_string(&path) {
+            Ok(text) => toml::from_str(&text).map_err(|e| invalid(&e.to_string()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => toml::Table::new(),
+            Err(e) => return Err(e),
+        };
+        let toml::Value::Array(cars) = doc
+            .entry("car")
+            .or_insert_with(|| toml::Value::Array(Vec::new()))
+        else {
+            return Err(invalid("`car` is not a list"));
+        };
+
+        let rpms = toml::Value::Array(table.iter().map(|r| toml::Value::from(*r)).collect());
+        let existing = cars.iter_mut().find_map(|car| {
+            car.as_table_mut()
+                .filter(|t| t.get("car_model").and_then(toml::Value::as_str) == Some(car_model))
+        });
+        match existing {
+            Some(entry) => {
+                entry.insert("upshift_rpm".to_string(), rpms);
+            }
+            None => {
+                let mut entry = toml::Table::new();
+                entry.insert("car_model".to_string(), car_model.into());
+                entry.insert("upshift_rpm".to_string(), rpms);
+                cars.push(toml::Value::Table(entry));
+            }
+        }
+
+        let text = toml::to_string(&doc).map_err(|e| invalid(&e.to_string()))?;
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::write(&path, text)?;
+        self.reload();
+        Ok(path)
+    }
+
+    /// Writes `<dir>/<car_model>.toml` with every field spelled out at its
+    /// neutral default, ready for editing, and reloads the database.
+    pub fn create_profile(&mut self, car_model: &str) -> std::io::Result<PathBuf> {
+        let path = self.dir.join(format!("{}.toml", car_model));
+        let p = CarProfile::generic(car_model);
+        p.validate().map_err(|e| invalid(&e))?;
+        let text = format!(
+            "# Created by SimTem for a car without a built-in profile.\n\
+             # class is one of GT3, GT4, GT2, TCX, Cup, ST, CHL.\n\
+             [[car]]\n\
+             car_model = \"{}\"\n\
+             shift_rpm = {}\n\
+             bb_offset = {:.1}\n\
+             brake_pressure_co = [{:.1}, {:.1}]\n\
+             max_steering_angle = {}\n",
+            p.car_model,
+            p.shift_rpm,
+            p.bb_offset,
+            p.brake_pressure_co[0],
+            p.brake_pressure_co[1],
+            p.max_steering_angle,
+        );
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .and_then(|mut f| std::io::Write::write_all(&mut f, text.as_bytes()))?;
+        self.reload();
+        Ok(path)
+    }
+
+    /// Reloads if a profile file was added, removed or modified.
+    pub fn reload_if_changed(&mut self) -> bool {
+        if profile_files(&self.dir) == self.signature {
+            return false;
+        }
+        println!("Car profiles changed, reloading {}", self.dir.display());
+        self.reload();
+        true
+    }
+
+    fn merge(&mut self, path: &Path, entry: ProfileOverride) {
+        let existing = self
+            .profiles
+            .iter()
+            .position(|c| c.car_model == entry.car_model);
+        let mut profile = match existing {
+            Some(i) => self.profiles[i].clone(),
+            None => CarProfile::generic(&entry.car_model),
+        };
+        entry.apply(&mut profile);
+
+        let duplicate = self
+            .get_by_id(profile.car_id)
+            .filter(|other| other.car_model != profile.car_model);
+        let result = match duplicate {
+            Some(other) => Err(format!(
+                "car_id {} is already used by {}",
+                profile.car_id, other.car_model
+            )),
+            None => profile.validate(),
+        };
+        if let Err(e) = result {
+            self.errors
+                .push(format!("{}: {}: {}", path.display(), entry.car_model, e));
+            return;
+        }
+        match existing {
+            Some(i) => self.profiles[i] = profile,
+            None => self.profiles.push(profile),
+        }
+    }
+}
+
+fn invalid(msg: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, msg)
+}
+
+fn read_file(path: &Path) -> Result<ProfileFile, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    if path.extension().is_some_and(|ext| ext == "json") {
+        serde_json::from_str(&text).map_err(|e| e.to_string())
+    } else {
+        toml::from_str(&text).map_err(|e| e.to_string())
+    }
+}
+
+/// Profile files in name order with their modification times.
+fn profile_files(dir: &Path) -> Vec<(PathBuf, Option<SystemTime>)> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<_> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| {
+            p.extension()
+                .is_some_and(|ext| ext == "toml" || ext == "json")
+        })
+        .map(|p| {
+            let mtime = std::fs::metadata(&p).and_then(|m| m.modified()).ok();
+            (p, mtime)
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::CarStanding;
+
+    /// An empty directory for the user files of one test.
+    fn user_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("simtem-test-cars-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn standings_are_named_after_the_user_profiles() {
+        let dir = user_dir("standings");
+        std::fs::write(
+            dir.join("cars.toml"),
+            "[[car]]\ncar_model = \"new_gt3\"\ncar_id = 99\nclass = \"GT3\"\n\n\
+             [[car]]\ncar_model = \"bmw_m4_gt4\"\nclass = \"ST\"\n",
+        )
+        .unwrap();
+        let cars = CarDatabase::load(&dir);
+        assert!(cars.errors.is_empty(), "{:?}", cars.errors);
+        assert_eq!(cars.get_by_id(99).unwrap().car_model, "new_gt3");
+        assert!(cars.get_by_id(-1).is_none());
+
+        let car = |car_id| CarStanding {
+            car_model: "acc_unknown".to_string(),
+            car_id,
+            ..CarStanding::default()
+        };
+        let mut standings = Standings {
+            cars: vec![car(Some(99)), car(Some(53)), car(None)],
+            ..Standings::default()
+        };
+        cars.resolve_standings(&mut standings);
+        let names: Vec<_> = standings
+            .cars
+            .iter()
+            .map(|c| (c.car_model.as_str(), c.class.as_str()))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                ("new_gt3", "GT3"),
+                ("bmw_m4_gt4", "ST"),
+                ("acc_unknown", "")
+            ]
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn builtin(car_model: &str) -> CarProfile {
+        CAR_MODELS
+            .iter()
+            .find(|c| c.car_model == car_model)
+            .map(CarProfile::from)
+            .unwrap()
+    }
+
+    #[test]
+    fn per_gear_shift_points_fall_back_to_shift_rpm() {
+        let car = CarProfile {
+            shift_rpm: 7000,
+            upshift_rpm: vec![6600, 0, 6900],
+            downshift_rpm: vec![0, 4800, 5200],
+            ..CarProfile::generic("test_car")
+        };
+        let up: Vec<_> = (0..=5).map(|gear| car.upshift_rpm(gear)).collect();
+        assert_eq!(
+            up,
+            [
+                Some(7000),
+                Some(6600),
+                Some(7000),
+                Some(6900),
+                Some(7000),
+                Some(7000)
+            ]
+        );
+        let down: Vec<_> = (0..=4).map(|gear| car.downshift_rpm(gear)).collect();
+        assert_eq!(down, [None, None, Some(4800), Some(5200), None]);
+        assert_eq!(car.upshift_rpm(-1), Some(7000));
+
+        let unknown = CarProfile {
+            upshift_rpm: vec![6600],
+            ..CarProfile::generic("test_car")
+        };
+        assert_eq!(unknown.upshift_rpm(1), Some(6600));
+        assert_eq!(unknown.upshift_rpm(2), None);
+        assert_eq!(unknown.downshift_rpm(1), None);
+    }
+
+    #[test]
+    fn user_files_override_builtin_fields_in_name_order() {
+        let dir = user_dir("merge");
+        std::fs::write(
+            dir.join("a.toml"),
+            "[[car]]\ncar_model = \"bmw_m4_gt4\"\nshift_rpm = 6900\nupshift_rpm = [6600, 0, 6800]\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("b.json"),
+            r#"{"car": [{"car_model": "bmw_m4_gt4", "max_steering_angle": 480},
+                        {"car_model": "my_kart", "shift_rpm": 12000}]}"#,
+        )
+        .unwrap();
+        std::fs::write(dir.join("notes.txt"), "not a profile").unwrap();
+        let cars = CarDatabase::load(&dir);
+        assert!(cars.errors.is_empty(), "{:?}", cars.errors);
+
+        let base = builtin("bmw_m4_gt4");
+        let bmw = cars.get_by_name("bmw_m4_gt4").unwrap();
+        assert_eq!(bmw.car_id, base.car_id);
+        assert_eq!(bmw.class, base.class);
+        assert_eq!(bmw.bb_offset, base.bb_offset);
+        assert_eq!(bmw.shift_rpm, 6900);
+        assert_eq!(bmw.upshift_rpm, [6600, 0, 6800]);
+        assert_eq!(bmw.max_steering_angle, 480);
+
+        let kart = cars.get_by_name("my_kart").unwrap();
+        assert_eq!(kart.car_id, -1);
+        assert_eq!(kart.class, None);
+        assert_eq!(kart.shift_rpm, 12000);
+        assert_eq!(cars.profiles().len(), CAR_MODELS.len() + 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn duplicate_car_ids_are_rejected() {
+        let dir = user_dir("duplicate");
+        let bmw_id = builtin("bmw_m4_gt4").car_id;
+        std::fs::write(
+            dir.join("cars.toml"),
+            format!(
+                "[[car]]\ncar_model = \"new_gt4\"\ncar_id = {}\n\n\
+                 [[car]]\ncar_model = \"bmw_m4_gt4\"\ncar_id = {}\n",
+                bmw_id, bmw_id
+            ),
+        )
+        .unwrap();
+        let cars = CarDatabase::load(&dir);
+        assert_eq!(cars.errors.len(), 1, "{:?}", cars.errors);
+        assert!(
+            cars.errors[0].contains("new_gt4: car_id"),
+            "{}",
+            cars.errors[0]
+        );
+        assert!(cars.errors[0].contains("already used by bmw_m4_gt4"));
+        assert!(cars.get_by_name("new_gt4").is_none());
+        assert_eq!(cars.get_by_id(bmw_id).unwrap().car_model, "bmw_m4_gt4");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn invalid_entries_and_files_are_reported_and_skipped() {
+        let dir = user_dir("invalid");
+        std::fs::write(
+            dir.join("a.toml"),
+            "[[car]]\ncar_model = \"bmw_m4_gt4\"\nshift_rpm = 50000\n\n\
+             [[car]]\ncar_model = \"Bad Name\"\n\n\
+             [[car]]\ncar_model = \"my_kart\"\nupshift_rpm = [0, 500]\n\n\
+             [[car]]\ncar_model = \"ok_car\"\nbrake_pressure_co = [10.0, 0.0]\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("b.toml"),
+            "[[car]]\ncar_model = \"x\"\nshift = 1\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("c.json"), "{ not json").unwrap();
+        let cars = CarDatabase::load(&dir);
+
+        let errors: Vec<_> = cars
+            .errors
+            .iter()
+            .map(|e| e.strip_prefix(&format!("{}", dir.display())).unwrap())
+            .collect();
+        assert_eq!(errors.len(), 6, "{:?}", errors);
+        assert!(errors[0].ends_with("a.toml: bmw_m4_gt4: shift_rpm 50000 out of range"));
+        assert!(errors[1].ends_with("a.toml: Bad Name: invalid car_model 'Bad Name'"));
+        assert!(errors[2].ends_with("a.toml: my_kart: upshift_rpm entry 500 out of range"));
+        assert!(errors[3].contains("a.toml: ok_car: brake_pressure_co"));
+        assert!(errors[4].contains("b.toml: ") && errors[4].contains("shift"));
+        assert!(errors[5].contains("c.json: "));
+
+        assert_eq!(
+            cars.get_by_name("bmw_m4_gt4").unwrap().shift_rpm,
+            builtin("bmw_m4_gt4").shift_rpm
+        );
+        assert!(cars.get_by_name("my_kart").is_none());
+        assert!(cars.get_by_name("ok_car").is_none());
+        assert_eq!(cars.profiles().len(), CAR_MODELS.len());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn saved_upshift_rpm_is_merged_into_the_user_file() {
+        let dir = user_dir("save");
+        std::fs::write(
+            dir.join("bmw_m4_gt4.toml"),
+            "# mine\n[[car]]\ncar_model = \"bmw_m4_gt4\"\nshift_rpm = 6900\nupshift_rpm = [6600]\n",
+        )
+        .unwrap();
+        let mut cars = CarDatabase::load(&dir);
+        let path = cars
+            .save_upshift_rpm("bmw_m4_gt4", &[(3, 7100), (0, 1)])
+            .unwrap();
+        assert_eq!(path, dir.join("bmw_m4_gt4.toml"));
+        assert!(cars.errors.is_empty(), "{:?}", cars.errors);
+
+        let bmw = cars.get_by_name("bmw_m4_gt4").unwrap();
+        assert_eq!(bmw.upshift_rpm, [6600, 0, 7100]);
+        assert_eq!(bmw.shift_rpm, 6900);
+        assert_eq!(bmw.upshift_rpm(2), Some(6900));
+
+        // Survives a fresh load, and a second save keeps the first
+        let mut cars = CarDatabase::load(&dir);
+        assert_eq!(
+            cars.get_by_name("bmw_m4_gt4").unwrap().upshift_rpm(3),
+            Some(7100)
+        );
+        cars.save_upshift_rpm("bmw_m4_gt4", &[(2, 6850)]).unwrap();
+        assert_eq!(
+            cars.get_by_name("bmw_m4_gt4").unwrap().upshift_rpm,
+            [6600, 6850, 7100]
+        );
+
+        // A car without a file gets a new one
+        cars.save_upshift_rpm("my_kart", &[(1, 11000)]).unwrap();
+        assert_eq!(cars.get_by_name("my_kart").unwrap().upshift_rpm, [11000]);
+        assert!(dir.join("my_kart.toml").exists());
+
+        assert!(cars.save_upshift_rpm("../escape", &[(1, 7000)]).is_err());
+        assert!(cars.save_upshift_rpm("bmw_m4_gt4", &[(1, 50)]).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn created_profiles_load_and_are_not_overwritten() {
+        let dir = user_dir("create").join("cars");
+        let mut cars = CarDatabase::load(&dir);
+        assert!(cars.get_by_name("my_kart").is_none());
+
+        let path = cars.create_profile("my_kart").unwrap();
+        assert_eq!(path, dir.join("my_kart.toml"));
+        assert!(cars.errors.is_empty(), "{:?}", cars.errors);
+        let kart = cars.get_by_name("my_kart").unwrap();
+        assert_eq!(kart.car_id, -1);
+        assert_eq!(kart.shift_rpm, 0);
+        assert_eq!(kart.upshift_rpm(1), None);
+
+        let err = cars.create_profile("my_kart").unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+        assert!(cars.create_profile("My Kart").is_err());
+        std::fs::remove_dir_all(dir.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,472 @@
+//! rFactor 2 shared memory map plugin (rF2SMMP) layout, also used by
+//! Le Mans Ultimate.
+//!
+//! The plugin packs its structs to 4 bytes, so the doubles here are not
+//! always 8-byte aligned: copy fields out by value instead of borrowing them.
+//! Each buffer starts with a [`BufferHeader`] whose version counters guard
+//! against torn reads.
+
+use super::ShmPage;
+use super::car_data::{car_model_from_name, parse_byte_string};
+use super::enums::{AcStatus, FlagType, RainIntensity, SessionType};
+use super::standings::{CarStanding, Standings};
+use super::telemetry::{TelemetryFrame, sector_times};
+use std::mem::{offset_of, size_of};
+
+pub const TELEMETRY_FILE: &str = "$rFactor2SMMP_Telemetry$";
+pub const SCORING_FILE: &str = "$rFactor2SMMP_Scoring$";
+pub const MAX_VEHICLES: usize = 128;
+
+/// `rF2Telemetry`, updated every physics tick. Too large to copy whole at
+/// the sampling rate: read the header and the player's car from the mapping.
+#[repr(C, packed(4))]
+#[derive(Copy, Clone)]
+pub struct Telemetry {
+    pub header: BufferHeader,
+    pub num_vehicles: i32,
+    pub vehicles: [VehicleTelemetry; MAX_VEHICLES],
+}
+
+/// `rF2Scoring`, updated about five times a second.
+#[repr(C, packed(4))]
+#[derive(Copy, Clone)]
+pub struct Scoring {
+    pub header: BufferHeader,
+    pub info: ScoringInfo,
+    pub vehicles: [VehicleScoring; MAX_VEHICLES],
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct BufferHeader {
+    /// Incremented before the plugin writes the buffer.
+    pub version_begin: u32,
+    /// Set to `version_begin` once it is done.
+    pub version_end: u32,
+    pub _bytes_updated_hint: i32,
+}
+
+#[repr(C, packed(4))]
+#[derive(Copy, Clone)]
+pub struct Vec3 {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+#[repr(C, packed(4))]
+#[derive(Copy, Clone)]
+pub struct Wheel {
+    pub _suspension_deflection: f64,
+    pub _ride_height: f64,
+    pub _susp_force: f64,
+    pub _brake_temp: f64,
+    pub _brake_pressure: f64,
+    pub _rotation: f64,
+    pub _lateral_patch_vel: f64,
+    pub _longitudinal_patch_vel: f64,
+    pub _lateral_ground_vel: f64,
+    pub _longitudinal_ground_vel: f64,
+    pub _camber: f64,
+    pub _lateral_force: f64,
+    pub _longitudinal_force: f64,
+    pub _tire_load: f64,
+    pub _grip_fract: f64,
+    pub _pressure: f64,
+    pub _temperature: [f64; 3],
+    pub _wear: f64,
+    pub _terrain_name: [u8; 16],
+    pub _surface_type: u8,
+    pub _flat: u8,
+    pub _detached: u8,
+    pub _static_undeflected_radius: u8,
+    pub _vertical_tire_deflection: f64,
+    pub _wheel_y_location: f64,
+    pub _toe: f64,
+    pub _tire_carcass_temperature: f64,
+    pub _tire_inner_layer_temperature: [f64; 3],
+    pub _expansion: [u8; 24],
+}
+
+/// `rF2VehicleTelemetry`: one car, in its local frame (+x left, +y up,
+/// +z rearwards).
+#[repr(C, packed(4))]
+#[derive(Copy, Clone)]
+pub struct VehicleTelemetry {
+    pub id: i32,
+    pub _delta_time: f64,
+    pub _elapsed_time: f64,
+    pub _lap_number: i32,
+    pub _lap_start_et: f64,
+    pub vehicle_name: [u8; 64],
+    pub track_name: [u8; 64],
+    pub _pos: Vec3,
+    pub local_vel: Vec3,
+    pub local_accel: Vec3,
+    pub _ori: [Vec3; 3],
+    pub _local_rot: Vec3,
+    pub _local_rot_accel: Vec3,
+    pub gear: i32,
+    pub engine_rpm: f64,
+    pub _engine_water_temp: f64,
+    pub _engine_oil_temp: f64,
+    pub _clutch_rpm: f64,
+    pub unfiltered_throttle: f64,
+    pub unfiltered_brake: f64,
+    pub unfiltered_steering: f64,
+    pub unfiltered_clutch: f64,
+    pub _filtered_throttle: f64,
+    pub _filtered_brake: f64,
+    pub _filtered_steering: f64,
+    pub _filtered_clutch: f64,
+    pub _steering_shaft_torque: f64,
+    pub _front_3rd_deflection: f64,
+    pub _rear_3rd_deflection: f64,
+    pub _front_wing_height: f64,
+    pub _front_ride_height: f64,
+    pub _rear_ride_height: f64,
+    pub _drag: f64,
+    pub _front_downforce: f64,
+    pub _rear_downforce: f64,
+    pub fuel: f64,
+    pub engine_max_rpm: f64,
+    pub _scheduled_stops: u8,
+    pub _overheating: u8,
+    pub _detached: u8,
+    pub _headlights: u8,
+    pub _dent_severity: [u8; 8],
+    pub _last_impact_et: f64,
+    pub _last_impact_magnitude: f64,
+    pub _last_impact_pos: Vec3,
+    pub _engine_torque: f64,
+    pub _current_sector: i32,
+    pub speed_limiter: u8,
+    pub _max_gears: u8,
+    pub _front_tire_compound_index: u8,
+    pub _rear_tire_compound_index: u8,
+    pub fuel_capacity: f64,
+    pub _front_flap_activated: u8,
+    pub _rear_flap_activated: u8,
+    pub _rear_flap_legal_status: u8,
+    /// 0 off, 1 ignition, 2 ignition and starter.
+    pub ignition_starter: u8,
+    pub _front_tire_compound_name: [u8; 18],
+    pub _rear_tire_compound_name: [u8; 18],
+    pub _speed_limiter_available: u8,
+    pub _anti_stall_activated: u8,
+    pub _unused: [u8; 2],
+    pub _visual_steering_wheel_range: f32,
+    /// Fraction of the brake force on the rear axle.
+    pub rear_brake_bias: f64,
+    pub _turbo_boost_pressure: f64,
+    pub _physics_to_graphics_offset: [f32; 3],
+    pub _physical_steering_wheel_range: f32,
+    pub _expansion: [u8; 152],
+    pub _wheels: [Wheel; 4],
+}
+
+/// `rF2ScoringInfo`: the session, shared by all cars.
+#[repr(C, packed(4))]
+#[derive(Copy, Clone)]
+pub struct ScoringInfo {
+    pub track_name: [u8; 64],
+    /// 0 test day, 1-4 practice, 5-8 qualifying, 9 warmup, 10-13 race.
+    pub session: i32,
+    pub _current_et: f64,
+    pub _end_et: f64,
+    pub _max_laps: i32,
+    /// Lap length in m.
+    pub lap_dist: f64,
+    pub _pointer_1: [u8; 8],
+    pub num_vehicles: i32,
+    /// 9 while the session is paused.
+    pub game_phase: u8,
+    pub _yellow_flag_state: i8,
+    pub _sector_flag: [i8; 3],
+    pub _start_light: u8,
+    pub _num_red_lights: u8,
+    /// False in the monitor, true while driving or spectating.
+    pub in_realtime: u8,
+    pub _player_name: [u8; 32],
+    pub _plr_file_name: [u8; 64],
+    pub _dark_cloud: f64,
+    /// Rain intensity, 0 to 1.
+    pub raining: f64,
+    pub _ambient_temp: f64,
+    pub _track_temp: f64,
+    pub _wind: Vec3,
+    pub _min_path_wetness: f64,
+    pub _max_path_wetness: f64,
+    pub _game_mode: u8,
+    pub _is_password_protected: u8,
+    pub _server_port: u16,
+    pub _server_public_ip: u32,
+    pub _max_players: i32,
+    pub _server_name: [u8; 32],
+    pub _start_et: f32,
+    pub _avg_path_wetness: f64,
+    pub _expansion: [u8; 200],
+    pub _pointer_2: [u8; 8],
+}
+
+/// `rF2VehicleScoring`: standings data of one car.
+#[repr(C, packed(4))]
+#[derive(Copy, Clone)]
+pub struct VehicleScoring {
+    /// Matches [`VehicleTelemetry::id`].
+    pub id: i32,
+    pub driver_name: [u8; 32],
+    pub vehicle_name: [u8; 64],
+    pub total_laps: i16,
+    /// 0 is the last sector, 1 the first, 2 the second.
+    pub sector: i8,
+    /// 1 once the car took the chequered flag.
+    pub finish_status: i8,
+    /// Distance along the lap in m.
+    pub lap_dist: f64,
+    pub _path_lateral: f64,
+    pub _track_edge: f64,
+    pub _best_sector_1: f64,
+    pub _best_sector_2: f64,
+    pub best_lap_time: f64,
+    pub _last_sector_1: f64,
+    pub _last_sector_2: f64,
+    pub last_lap_time: f64,
+    pub _cur_sector_1: f64,
+    pub _cur_sector_2: f64,
+    pub _num_pitstops: i16,
+    pub _num_penalties: i16,
+    pub is_player: u8,
+    pub _control: i8,
+    pub in_pits: u8,
+    /// Position from 1.
+    pub place: u8,
+    pub vehicle_class: [u8; 32],
+    pub _time_behind_next: f64,
+    pub _laps_behind_next: i32,
+    pub _time_behind_leader: f64,
+    pub _laps_behind_leader: i32,
+    pub _lap_start_et: f64,
+    pub _pos: Vec3,
+    pub _local_vel: Vec3,
+    pub _local_accel: Vec3,
+    pub _ori: [Vec3; 3],
+    pub _local_rot: Vec3,
+    pub _local_rot_accel: Vec3,
+    pub _headlights: u8,
+    /// 0 none, 1 requested, 2 entering, 3 stopped, 4 exiting.
+    pub pit_state: u8,
+    pub _server_scored: u8,
+    pub _individual_phase: u8,
+    pub _qualification: i32,
+    pub time_into_lap: f64,
+    pub _estimated_lap_time: f64,
+    pub _pit_group: [u8; 24],
+    /// 0 none, 6 blue.
+    pub flag: u8,
+    pub under_yellow: u8,
+    pub _count_lap_flag: u8,
+    pub in_garage_stall: u8,
+    pub _upgrade_pack: [u8; 16],
+    pub _pit_lap_dist: f32,
+    pub best_lap_sector_1: f32,
+    pub best_lap_sector_2: f32,
+    pub _expansion: [u8; 48],
+}
+
+// rF2State.h of the plugin, #pragma pack(4)
+const _: () = assert!(size_of::<Wheel>() == 260);
+const _: () = assert!(size_of::<VehicleTelemetry>() == 1888);
+const _: () = assert!(offset_of!(VehicleTelemetry, gear) == 352);
+const _: () = assert!(offset_of!(VehicleTelemetry, fuel) == 524);
+const _: () = assert!(offset_of!(VehicleTelemetry, rear_brake_bias) == 664);
+const _: () = assert!(offset_of!(VehicleTelemetry, _wheels) == 848);
+const _: () = assert!(size_of::<ScoringInfo>() == 548);
+const _: () = assert!(offset_of!(ScoringInfo, num_vehicles) == 104);
+const _: () = assert!(offset_of!(ScoringInfo, raining) == 220);
+const _: () = assert!(size_of::<VehicleScoring>() == 584);
+const _: () = assert!(offset_of!(VehicleScoring, is_player) == 196);
+const _: () = assert!(offset_of!(VehicleScoring, time_into_lap) == 464);
+const _: () = assert!(offset_of!(VehicleScoring, flag) == 504);
+const _: () = assert!(offset_of!(Telemetry, vehicles) == 16);
+const _: () = assert!(offset_of!(Scoring, vehicles) == 560);
+
+unsafe impl ShmPage for Telemetry {}
+unsafe impl ShmPage for Scoring {}
+
+impl TelemetryFrame {
+    /// Telemetry of the player's car; scoring is `None` until the plugin
+    /// has published it.
+    pub fn from_rf2(
+        t: &VehicleTelemetry,
+        scoring: Option<(&ScoringInfo, &VehicleScoring)>,
+    ) -> Self {
+        let Vec3 { x, y, z } = t.local_vel;
+        let accel = t.local_accel;
+        let rear_bias = t.rear_brake_bias;
+        let mut frame = Self {
+            throttle: t.unfiltered_throttle as f32,
+            brake: t.unfiltered_brake as f32,
+            clutch: t.unfiltered_clutch as f32,
+            steering: t.unfiltered_steering as f32,
+            gear: t.gear,
+            speed: (x * x + y * y + z * z).sqrt() as f32,
+            rpm: t.engine_rpm as f32,
+            max_rpm: t.engine_max_rpm as f32,
+            fuel: Some(t.fuel as f32),
+            fuel_percent: (t.fuel_capacity > 0.0)
+                .then(|| (t.fuel / t.fuel_capacity * 100.0) as f32),
+            brake_bias: (rear_bias > 0.0).then_some(((1.0 - rear_bias) * 100.0) as f32),
+            // +z points rearwards
+            acceleration: [accel.x as f32, accel.y as f32, -accel.z as f32],
+            ignition_on: t.ignition_starter > 0,
+            engine_running: t.engine_rpm > 0.0,
+            pit_limiter_on: t.speed_limiter != 0,
+            ..Self::default()
+        };
+        let Some((info, v)) = scoring else {
+            return frame;
+        };
+
+        frame.status = Some(if info.game_phase == 9 {
+            AcStatus::Pause
+        } else if info.in_realtime != 0 {
+            AcStatus::Live
+        } else {
+            AcStatus::Off
+        });
+        frame.session_type = Some(match info.session {
+            0..=4 | 9 => SessionType::Practice,
+            5..=8 => SessionType::Qualify,
+            10..=13 => SessionType::Race,
+            _ => SessionType::Unknown,
+        });
+        frame.flag = Some(if v.finish_status == 1 {
+            FlagType::Checkered
+        } else if v.flag == 6 {
+            FlagType::Blue
+        } else if v.under_yellow != 0 {
+            FlagType::Yellow
+        } else {
+            FlagType::None
+        });
+        frame.rain = Some(RainIntensity::from_fraction(info.raining as f32));
+        frame.in_pit = v.pit_state == 3 || v.in_garage_stall != 0;
+        frame.in_pit_lane = v.in_pits != 0;
+
+        frame.completed_laps = v.total_laps as i32;
+        frame.current_lap_time = lap_time(v.time_into_lap);
+        frame.last_lap_time = lap_time(v.last_lap_time);
+        frame.best_lap_time = lap_time(v.best_lap_time);
+        frame.current_sector = (v.sector as i32 + 2) % 3;
+        let lap_length = info.lap_dist;
+        if lap_length > 0.0 {
+            frame.lap_fraction = (v.lap_dist / lap_length).clamp(0.0, 1.0) as f32;
+        }
+        frame
+    }
+}
+
+impl ScoringInfo {
+    /// Every car of the session, from the scoring buffer's vehicles.
+    pub fn standings(&self, vehicles: &[VehicleScoring]) -> Standings {
+        let lap_length = self.lap_dist;
+        let cars = vehicles
+            .iter()
+            .map(|v| CarStanding {
+                id: v.id,
+                position: v.place as i32,
+                driver: parse_byte_string(&v.driver_name),
+                car_model: car_model_from_name(&parse_byte_string(&v.vehicle_name)),
+                car_id: None,
+                class: parse_byte_string(&v.vehicle_class),
+                completed_laps: v.total_laps as i32,
+                lap_fraction: if lap_length > 0.0 {
+                    (v.lap_dist / lap_length).clamp(0.0, 1.0) as f32
+                } else {
+                    0.0
+                },
+                last_lap_time: lap_time(v.last_lap_time),
+                best_lap_time: lap_time(v.best_lap_time),
+                // Time into the best lap at the end of sectors 1 and 2
+                best_sectors: sector_times(
+                    [
+                        lap_time(v.best_lap_sector_1 as f64),
+                        lap_time(v.best_lap_sector_2 as f64),
+                    ],
+                    lap_time(v.best_lap_time),
+                ),
+                in_pit: v.in_pits != 0,
+                is_player: v.is_player != 0,
+            })
+            .collect();
+        Standings {
+            track_length: lap_length as f32,
+            cars,
+        }
+    }
+}
+
+/// rF2 reports laps without a time as 0 or negative.
+fn lap_time(seconds: f64) -> Option<f64> {
+    (seconds > 0.0).then_some(seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The plugin's structs are plain numbers, so all zeroes is valid.
+    fn zeroed<T: Copy>() -> T {
+        unsafe { std::mem::zeroed() }
+    }
+
+    fn scoring(sector: i8) -> (ScoringInfo, VehicleScoring) {
+        let mut info = zeroed::<ScoringInfo>();
+        info.lap_dist = 4000.0;
+        info.in_realtime = 1;
+        info.session = 10;
+        let mut v = zeroed::<VehicleScoring>();
+        v.sector = sector;
+        v.lap_dist = 1000.0;
+        v.total_laps = 2;
+        v.place = 1;
+        v.is_player = 1;
+        (info, v)
+    }
+
+    #[test]
+    fn sectors_count_from_the_first() {
+        let t = zeroed::<VehicleTelemetry>();
+        let sector = |raw| {
+            let (info, v) = scoring(raw);
+            TelemetryFrame::from_rf2(&t, Some((&info, &v))).current_sector
+        };
+        assert_eq!([sector(1), sector(2), sector(0)], [0, 1, 2]);
+
+        let (info, v) = scoring(1);
+        let frame = TelemetryFrame::from_rf2(&t, Some((&info, &v)));
+        assert_eq!(frame.status, Some(AcStatus::Live));
+        assert_eq!(frame.session_type, Some(SessionType::Race));
+        assert_eq!(frame.completed_laps, 2);
+        assert_eq!(frame.lap_fraction, 0.25);
+    }
+
+    #[test]
+    fn best_sectors_from_the_cumulative_splits() {
+        let (info, mut v) = scoring(1);
+        v.best_lap_time = 100.0;
+        v.best_lap_sector_1 = 30.0;
+        v.best_lap_sector_2 = 65.5;
+        let (_, mut no_splits) = scoring(1);
+        no_splits.best_lap_time = 101.0;
+        no_splits.is_player = 0;
+
+        let standings = info.standings(&[v, no_splits]);
+        assert_eq!(standings.track_length, 4000.0);
+        let car = &standings.cars[0];
+        assert!(car.is_player);
+        assert_eq!(car.best_sectors, [Some(30.0), Some(35.5), Some(34.5)]);
+        assert_eq!(standings.cars[1].best_sectors, [None, None, None]);
+    }
+}
@@ -0,0 +1,107 @@
+use super::enums::{AcStatus, FlagType, PenaltyType, RainIntensity, SessionType, TrackGripStatus};
+use super::{Graphics, Physics};
+
+/// Decoded view of one physics/graphics pair in ACC's own units, so nothing
+/// past the ACC adapter reads the raw page fields. Enum fields are `None`
+/// when the sim reports an undocumented value.
+#[derive(Copy, Clone, Debug)]
+pub struct Snapshot {
+    pub status: Option<AcStatus>,
+    pub session_type: Option<SessionType>,
+    pub flag: Option<FlagType>,
+    pub penalty: Option<PenaltyType>,
+    pub track_grip: Option<TrackGripStatus>,
+    pub rain: Option<RainIntensity>,
+    pub rain_in_10min: Option<RainIntensity>,
+    pub rain_in_30min: Option<RainIntensity>,
+    pub in_pit: bool,
+    pub in_pit_lane: bool,
+
+    pub gas: f32,
+    pub brake: f32,
+    /// Forward gear from 1, 0 for neutral and -1 for reverse.
+    pub gear: i32,
+    pub speed_kmh: f32,
+    pub rpm: i32,
+    pub max_rpm: i32,
+    pub fuel: f32,
+    /// Front share of the brake force, 0..1, before the car's offset.
+    pub brake_bias: f32,
+    pub tc_active: bool,
+    pub abs_active: bool,
+    pub tc_level: i32,
+    pub abs_level: i32,
+    pub ignition_on: bool,
+}
+
+impl Snapshot {
+    pub fn new(p: &Physics, g: &Graphics) -> Self {
+        Self {
+            status: g._status.try_into().ok(),
+            session_type: g._session_type.try_into().ok(),
+            flag: g._flag.try_into().ok(),
+            penalty: g._penalty.try_into().ok(),
+            track_grip: g._track_grip_status.try_into().ok(),
+            rain: g._rain_intensity.try_into().ok(),
+            rain_in_10min: g._rain_intensity_in_10min.try_into().ok(),
+            rain_in_30min: g._rain_intensity_in_30min.try_into().ok(),
+            in_pit: g._is_in_pit != 0,
+            in_pit_lane: g._is_in_pit_lane != 0,
+
+            gas: p.gas,
+            brake: p.brake,
+            // ACC counts 0 = R, 1 = N, 2 = 1st
+            gear: p.gear - 1,
+            speed_kmh: p.speed_kmh,
+            rpm: p.rpms,
+            max_rpm: p.current_max_rpm,
+            fuel: p.fuel,
+            brake_bias: p.brake_bias,
+            tc_active: p.tc == 1.0,
+            abs_active: p.abs == 1.0,
+            tc_level: g.tc,
+            abs_level: g.abs,
+            ignition_on: p.ignition_on == 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::ShmPage;
+
+    #[test]
+    fn decodes_the_raw_fields() {
+        let mut p = Physics::zeroed();
+        p.gear = 0;
+        p.rpms = 7200;
+        p.tc = 1.0;
+        p.brake_bias = 0.62;
+        let mut g = Graphics::zeroed();
+        g._status = 2;
+        g._session_type = -1;
+        g._flag = 99;
+        g._penalty = 13;
+        g._rain_intensity = 3;
+        g._is_in_pit_lane = 1;
+        g.tc = 4;
+
+        let s = Snapshot::new(&p, &g);
+        assert_eq!(s.gear, -1);
+        assert_eq!(s.rpm, 7200);
+        assert!(s.tc_active && !s.abs_active);
+        assert_eq!(s.tc_level, 4);
+        assert_eq!(s.brake_bias, 0.62);
+        assert_eq!(s.status, Some(AcStatus::Live));
+        assert_eq!(s.session_type, Some(SessionType::Unknown));
+        assert_eq!(s.flag, None);
+        assert_eq!(
+            s.penalty,
+            Some(PenaltyType::DisqualifiedIgnoredMandatoryPit)
+        );
+        assert_eq!(s.track_grip, Some(TrackGripStatus::Green));
+        assert_eq!(s.rain, Some(RainIntensity::MediumRain));
+        assert!(!s.in_pit && s.in_pit_lane);
+    }
+}
@@ -0,0 +1,252 @@
+use super::telemetry::SECTORS;
+
+/// Every car in the session, for sims that report more than the player's
+/// car. Published by the backend whenever the sim updates it, which is
+/// usually well below the telemetry rate.
+#[derive(Clone, Debug, Default)]
+pub struct Standings {
+    /// Lap length in m, 0 when unknown.
+    pub track_length: f32,
+    pub cars: Vec<CarStanding>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct CarStanding {
+    /// The sim's id for the car, stable for the session.
+    pub id: i32,
+    /// Race position from 1, 0 when unknown.
+    pub position: i32,
+    pub driver: String,
+    pub car_model: String,
+    /// ACC's car model id, for looking the car up in the car profiles.
+    pub car_id: Option<i32>,
+    pub class: String,
+    pub completed_laps: i32,
+    /// Position along the lap, 0 at the line to 1.
+    pub lap_fraction: f32,
+    /// Seconds.
+    pub last_lap_time: Option<f64>,
+    pub best_lap_time: Option<f64>,
+    /// Best time in each sector, where the sim reports it: of the best lap
+    /// or the fastest of each sector on its own, depending on the sim.
+    pub best_sectors: [Option<f64>; SECTORS],
+    pub in_pit: bool,
+    pub is_player: bool,
+}
+
+/// A car against a reference car: the player in the relative, the leader in
+/// the leaderboard.
+#[derive(Clone, Debug)]
+pub struct CarGap<'a> {
+    pub car: &'a CarStanding,
+    /// Seconds on track, positive when ahead of the reference car; `None`
+    /// without a lap time to convert the distance with.
+    pub seconds: Option<f32>,
+    /// Laps ahead of (positive) or behind (negative) the reference car in
+    /// the race.
+    pub laps: i32,
+}
+
+impl Standings {
+    pub fn player(&self) -> Option<&CarStanding> {
+        self.cars.iter().find(|car| car.is_player)
+    }
+
+    /// Fastest lap of any car.
+    pub fn best_lap_time(&self) -> Option<f64> {
+        self.cars
+            .iter()
+            .filter_map(|car| car.best_lap_time)
+            .min_by(f64::total_cmp)
+    }
+
+    /// Fastest time of any car in each sector.
+    pub fn best_sectors(&self) -> [Option<f64>; SECTORS] {
+        std::array::from_fn(|i| {
+            self.cars
+                .iter()
+                .filter_map(|car| car.best_sectors[i])
+                .min_by(f64::total_cmp)
+        })
+    }
+
+    /// Lap time used to turn distance into seconds: the player's best or
+    /// last lap, else the fastest lap in the session.
+    pub fn reference_lap_time(&self) -> Option<f64> {
+        self.player()
+            .and_then(|p| p.best_lap_time.or(p.last_lap_time))
+            .or_else(|| self.best_lap_time())
+    }
+
+    /// Up to `count` cars directly ahead of and behind the player on track,
+    /// with the player in the middle, ordered from front to back.
+    pub fn relative(&self, count: usize) -> Vec<CarGap<'_>> {
+        let Some(player) = self.player() else {
+            return Vec::new();
+        };
+        let lap_time = self.reference_lap_time();
+        let mut others: Vec<(f32, CarGap)> = self
+            .cars
+            .iter()
+            .filter(|car| !car.is_player)
+            .map(|car| {
+                // Shortest way around the lap, positive ahead
+                let mut on_track = car.lap_fraction - player.lap_fraction;
+                if on_track > 0.5 {
+                    on_track -= 1.0;
+                } else if on_track <= -0.5 {
+                    on_track += 1.0;
+                }
+                let laps = (car.distance() - player.distance() - on_track).round() as i32;
+                let gap = CarGap {
+                    car,
+                    seconds: lap_time.map(|t| on_track * t as f32),
+                    laps,
+                };
+                (on_track, gap)
+            })
+            .collect();
+        others.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        let split = others.partition_point(|(on_track, _)| *on_track > 0.0);
+        let (ahead, behind) = others.split_at(split);
+        let ahead = &ahead[ahead.len().saturating_sub(count)..];
+        let behind = &behind[..behind.len().min(count)];
+        let player = CarGap {
+            car: player,
+            seconds: Some(0.0),
+            laps: 0,
+        };
+        ahead
+            .iter()
+            .map(|(_, gap)| gap.clone())
+            .chain(std::iter::once(player))
+            .chain(behind.iter().map(|(_, gap)| gap.clone()))
+            .collect()
+    }
+
+    /// Every car by race position, against the leader. Cars a lap or more
+    /// down have no gap in seconds.
+    pub fn leaderboard(&self) -> Vec<CarGap<'_>> {
+        let mut cars: Vec<&CarStanding> = self.cars.iter().collect();
+        // Cars without a position go last
+        cars.sort_by_key(|car| (car.position <= 0, car.position));
+        let Some(leader) = cars.first().map(|car| car.distance()) else {
+            return Vec::new();
+        };
+        let lap_time = self.reference_lap_time();
+        cars.into_iter()
+            .map(|car| {
+                let behind = (leader - car.distance()).max(0.0);
+                let laps = behind.floor() as i32;
+                CarGap {
+                    car,
+                    seconds: lap_time.filter(|_| laps == 0).map(|t| -behind * t as f32),
+                    laps: -laps,
+                }
+            })
+            .collect()
+    }
+}
+
+impl CarStanding {
+    /// Laps covered in the session, with the current one as a fraction.
+    pub fn distance(&self) -> f32 {
+        self.completed_laps as f32 + self.lap_fraction
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn car(id: i32, position: i32, completed_laps: i32, lap_fraction: f32) -> CarStanding {
+        CarStanding {
+            id,
+            position,
+            completed_laps,
+            lap_fraction,
+            ..CarStanding::default()
+        }
+    }
+
+    /// The player (id 0) early in lap 6 with a 100 s best, and cars around
+    /// them on both sides of the line.
+    fn standings() -> Standings {
+        let mut player = car(0, 2, 5, 0.125);
+        player.is_player = true;
+        player.best_lap_time = Some(100.0);
+        Standings {
+            track_length: 5000.0,
+            cars: vec![
+                player,
+                // Just behind, across the line
+                car(1, 3, 4, 0.875),
+                // Behind on track but a lap ahead, no position yet
+                car(2, 0, 5, 0.9375),
+                car(3, 1, 5, 0.375),
+                // Two laps down
+                car(4, 4, 3, 0.25),
+                // Half a lap away, no position yet
+                car(5, 0, 5, 0.625),
+                // Exactly a lap and just under a lap behind the leader
+                car(6, 5, 4, 0.375),
+                car(7, 6, 4, 0.4375),
+            ],
+        }
+    }
+
+    fn ids(gaps: &[CarGap]) -> Vec<i32> {
+        gaps.iter().map(|gap| gap.car.id).collect()
+    }
+
+    #[test]
+    fn relative_wraps_around_the_line() {
+        let standings = standings();
+        let relative = standings.relative(10);
+        assert_eq!(ids(&relative), [5, 7, 3, 6, 4, 0, 2, 1]);
+        let gap = |id| relative.iter().find(|gap| gap.car.id == id).unwrap();
+
+        assert_eq!((gap(1).seconds, gap(1).laps), (Some(-25.0), 0));
+        assert_eq!((gap(2).seconds, gap(2).laps), (Some(-18.75), 1));
+        assert_eq!((gap(3).seconds, gap(3).laps), (Some(25.0), 0));
+        assert_eq!((gap(4).seconds, gap(4).laps), (Some(12.5), -2));
+        assert_eq!((gap(5).seconds, gap(5).laps), (Some(50.0), 0));
+        assert_eq!((gap(0).seconds, gap(0).laps), (Some(0.0), 0));
+    }
+
+    #[test]
+    fn relative_keeps_the_closest_cars() {
+        let standings = standings();
+        assert_eq!(ids(&standings.relative(2)), [6, 4, 0, 2, 1]);
+        assert_eq!(ids(&standings.relative(0)), [0]);
+
+        let mut no_player = standings.clone();
+        no_player.cars[0].is_player = false;
+        assert!(no_player.relative(2).is_empty());
+    }
+
+    #[test]
+    fn leaderboard_by_position() {
+        let standings = standings();
+        let leaderboard = standings.leaderboard();
+        // Cars without a position last, in the order the sim sent them
+        assert_eq!(ids(&leaderboard), [3, 0, 1, 4, 6, 7, 2, 5]);
+        let gaps: Vec<_> = leaderboard
+            .iter()
+            .map(|gap| (gap.seconds, gap.laps))
+            .collect();
+        assert_eq!(
+            gaps[..6],
+            [
+                (Some(-0.0), 0),
+                (Some(-25.0), 0),
+                (Some(-50.0), 0),
+                (None, -2),
+                (None, -1),
+                (Some(-93.75), 0),
+            ]
+        );
+        assert!(Standings::default().leaderboard().is_empty());
+    }
+}
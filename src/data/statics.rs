@@ -10,6 +10,7 @@ pub struct Statics {
     pub _player_name: [u16; 33],
     pub _player_surname: [u16; 33],
     pub _player_nick: [u16; 33],
+    pub _padding_1: u16,
     pub _sector_count: i32,
     pub _max_torque: f32,
     pub _max_power: f32,
@@ -35,11 +36,13 @@ pub struct Statics {
     pub _engine_brake_settins_count: i32,
     pub _ers_power_controller_count: i32,
     pub _track_spline_lenth: f32,
-    pub _track_configuration: u16,
+    pub _track_configuration: [u16; 33],
+    pub _padding_2: u16,
     pub _ers_max_j: f32,
     pub _is_timed_race: i32,
     pub _has_extra_lap: i32,
     pub _car_skin: [u16; 33],
+    pub _padding_3: u16,
     pub _reversed_grid_postitions: i32,
     pub _pit_window_start: i32,
     pub _pit_window_end: i32,
@@ -47,3 +50,11 @@ pub struct Statics {
     pub _dry_tyres_name: [u16; 33],
     pub _wet_tyres_name: [u16; 33],
 }
+
+// SPageFileStatic from the ACC shared memory documentation (SM 1.8/1.9)
+const _: () = assert!(std::mem::size_of::<Statics>() == 820);
+const _: () = assert!(std::mem::offset_of!(Statics, car_model) == 68);
+const _: () = assert!(std::mem::offset_of!(Statics, _track) == 134);
+const _: () = assert!(std::mem::offset_of!(Statics, _max_rpm) == 412);
+const _: () = assert!(std::mem::offset_of!(Statics, _ers_max_j) == 592);
+const _: () = assert!(std::mem::offset_of!(Statics, _wet_tyres_name) == 754);
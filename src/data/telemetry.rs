@@ -0,0 +1,124 @@
+use super::enums::{AcStatus, FlagType, PenaltyType, RainIntensity, SessionType, TrackGripStatus};
+
+/// Simulator independent view of one telemetry frame, in SI units unless
+/// noted otherwise. Each sim gets an adapter that fills it in; widgets only
+/// read this type, never the sim's own pages.
+///
+/// Session state reuses ACC's enums, which cover what the other sims report;
+/// fields a sim cannot provide are `None`.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct TelemetryFrame {
+    pub status: Option<AcStatus>,
+    pub session_type: Option<SessionType>,
+    pub flag: Option<FlagType>,
+    pub penalty: Option<PenaltyType>,
+    pub track_grip: Option<TrackGripStatus>,
+    pub rain: Option<RainIntensity>,
+    pub rain_in_10min: Option<RainIntensity>,
+    pub rain_in_30min: Option<RainIntensity>,
+    pub in_pit: bool,
+    pub in_pit_lane: bool,
+
+    /// Pedals, 0 (released) to 1 (fully pressed).
+    pub throttle: f32,
+    pub brake: f32,
+    pub clutch: f32,
+    /// Steering input, -1 (full left) to 1 (full right).
+    pub steering: f32,
+    /// Forward gear from 1, 0 for neutral and -1 for reverse.
+    pub gear: i32,
+    /// m/s
+    pub speed: f32,
+    /// Engine speed in rpm.
+    pub rpm: f32,
+    /// Rev limit in rpm, 0 when unknown.
+    pub max_rpm: f32,
+    /// Litres (kg in F1, which counts fuel by mass), `None` for sims that
+    /// only report how full the tank is.
+    pub fuel: Option<f32>,
+    /// Percent of a full tank, `None` when the tank size is unknown.
+    pub fuel_percent: Option<f32>,
+    /// Percent of the brake force on the front axle, as shown in the car.
+    pub brake_bias: Option<f32>,
+    /// m/s², in car coordinates: x lateral, y vertical, z longitudinal
+    /// (positive when accelerating).
+    pub acceleration: [f32; 3],
+    /// Heading, pitch and roll in radians, zero when unknown.
+    pub orientation: [f32; 3],
+    pub tc: DriverAid,
+    pub abs: DriverAid,
+    pub ignition_on: bool,
+    pub engine_running: bool,
+    pub pit_limiter_on: bool,
+
+    pub completed_laps: i32,
+    /// Seconds.
+    pub current_lap_time: Option<f64>,
+    pub last_lap_time: Option<f64>,
+    pub best_lap_time: Option<f64>,
+    /// Sector the car is in, from 0.
+    pub current_sector: i32,
+    /// The current lap will not count, e.g. for exceeding track limits.
+    pub lap_invalid: bool,
+    /// Position along the lap, 0 at the line to 1.
+    pub lap_fraction: f32,
+}
+
+/// Traction control or ABS: the selected setting and whether it is
+/// intervening right now.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct DriverAid {
+    /// Setting as shown in the car, 0 when off or not fitted.
+    pub level: i32,
+    pub active: bool,
+}
+
+/// Sectors of a lap in every supported sim.
+pub const SECTORS: usize = 3;
+
+/// Sector times from the time into the lap at the end of each sector but the
+/// last, plus the lap time for the last one. Sectors with a missing end are
+/// `None`.
+pub fn sector_times(
+    splits: [Option<f64>; SECTORS - 1],
+    lap_time: Option<f64>,
+) -> [Option<f64>; SECTORS] {
+    let mut ends = [None; SECTORS];
+    ends[..SECTORS - 1].copy_from_slice(&splits);
+    ends[SECTORS - 1] = lap_time;
+    std::array::from_fn(|i| {
+        let start = if i == 0 { Some(0.0) } else { ends[i - 1] };
+        Some(ends[i]? - start?)
+    })
+}
+
+impl TelemetryFrame {
+    pub fn speed_kmh(&self) -> f32 {
+        self.speed * 3.6
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sector_times_need_both_ends() {
+        assert_eq!(
+            sector_times([Some(30.0), Some(70.0)], Some(105.0)),
+            [Some(30.0), Some(40.0), Some(35.0)]
+        );
+        assert_eq!(
+            sector_times([Some(30.0), Some(70.0)], None),
+            [Some(30.0), Some(40.0), None]
+        );
+        assert_eq!(
+            sector_times([None, Some(70.0)], Some(105.0)),
+            [None, None, Some(35.0)]
+        );
+        assert_eq!(
+            sector_times([Some(30.0), None], Some(105.0)),
+            [Some(30.0), None, None]
+        );
+    }
+}
@@ -0,0 +1,7 @@
+pub mod analysis;
+pub mod data;
+pub mod paths;
+pub mod recording;
+pub mod sampler;
+pub mod source;
+pub mod widgets;
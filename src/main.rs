@@ -1,40 +1,71 @@
 use eframe::egui::{self};
-use memmap2::Mmap;
 use mimalloc::MiMalloc;
 use std::collections::VecDeque;
-use std::fs::OpenOptions;
+use std::sync::Arc;
 
 #[global_allocator]
 static GLOBAL: MiMalloc = MiMalloc;
 
-mod data;
-use data::{Graphics, Physics, Statics};
-mod widgets;
+mod analyze;
+mod cli;
+use cli::Sim;
+use simtem::analysis::{
+    DeltaReference, GearResult, LapDelta, LapTimer, LapTrace, ShiftCalibration, recorded_best,
+};
+use simtem::data::{self, CarDatabase, CarProfile, TelemetryFrame};
+use simtem::paths;
+use simtem::recording::{self, Recorder};
+use simtem::sampler::{Reader, Sample, Sampler};
+use simtem::source::{
+    AccShm, BroadcastClient, F1Udp, ForzaUdp, OutGaugeUdp, Pcars2Shm, ReplayControl, ReplaySource,
+    Rf2Shm, SyntheticSource, TelemetrySource,
+};
+use simtem::widgets::{self, ReviewAction};
 
 const COLOR_GAS: egui::Color32 = egui::Color32::from_rgb(0, 120, 0);
 const COLOR_BRAKE: egui::Color32 = egui::Color32::from_rgb(120, 0, 0);
 const SCALE_FACTOR: f32 = 1.0;
+/// Cars shown ahead of and behind the player in the relative.
+const RELATIVE_CARS: usize = 3;
+
+/// What the standings window shows.
+#[derive(Copy, Clone, PartialEq)]
+enum StandingsView {
+    Relative,
+    Leaderboard,
+}
 
 struct OverlayApp {
-    physics_mmap: Mmap,
-    graphics_mmap: Mmap,
-    statics_mmap: Mmap,
+    sampler: Sampler,
+    reader: Reader<Sample>,
+    recorder: Option<Recorder>,
+    replay: Option<Arc<ReplayControl>>,
 
     gas_history: VecDeque<(f64, f64, bool)>,
     brake_history: VecDeque<(f64, f64, bool)>,
-    start_time: std::time::Instant,
-    last_update: std::time::Instant,
     time_window: f64,
-
+    show_stats: bool,
+    standings_view: Option<StandingsView>,
+    show_lap_timing: bool,
+    lap_timer: LapTimer,
+    show_delta: bool,
+    delta_reference: DeltaReference,
+    lap_delta: LapDelta,
+    /// Fastest recorded lap of the car and track in `recorded_best_key`.
+    recorded_best: Option<LapTrace>,
+    recorded_best_key: (String, String),
+    recorded_best_loader: Option<std::thread::JoinHandle<Option<LapTrace>>>,
+
+    cars: CarDatabase,
     car_poll_timer: std::time::Instant,
     current_model_name: String,
-    cached_shift_rpm: i32,
+    cached_profile: Option<CarProfile>,
+    /// Model name of a detected car without a profile.
+    unknown_car: Option<String>,
     cached_bb_offset: f32,
 
-    // Recording / Testing Fields
-    rec_last_gear: i32,
-    rec_peak_rpm: i32,
-    is_recording: bool,
+    calibration: Option<ShiftCalibration>,
+    calibration_review: Option<(String, Vec<GearResult>)>,
 
     cache_fuel: String,
     last_fuel: f32,
@@ -51,7 +82,7 @@ struct OverlayApp {
     last_speed: i32,
     cache_rpm: String,
     last_rpm: i32,
-    last_ign: i32,
+    last_ign: bool,
     cache_gas_text: String,
     last_gas_int: i32,
     cache_brake_text: String,
@@ -60,34 +91,52 @@ struct OverlayApp {
 
 impl OverlayApp {
     fn new(
-        physics_mmap: Mmap,
-        graphics_mmap: Mmap,
-        statics_mmap: Mmap,
+        source: Box<dyn TelemetrySource>,
+        replay: Option<Arc<ReplayControl>>,
+        rate_hz: f64,
         cc: &eframe::CreationContext,
     ) -> Self {
         cc.egui_ctx.set_pixels_per_point(SCALE_FACTOR);
 
+        let time_window = 15.0;
+        let history_len = (rate_hz * time_window) as usize + 1;
+        let sampler = Sampler::spawn(source, rate_hz);
+        let reader = sampler.reader();
+        // Replays are already on disk, only live sessions get recorded
+        let recorder = replay
+            .is_none()
+            .then(|| Recorder::spawn(sampler.reader(), recording::sessions_dir()));
+
         Self {
-            physics_mmap,
-            graphics_mmap,
-            statics_mmap,
+            sampler,
+            reader,
+            recorder,
+            replay,
             // Pre-allocate to prevent resizing
-            gas_history: VecDeque::with_capacity(5000),
-            brake_history: VecDeque::with_capacity(5000),
-            start_time: std::time::Instant::now(),
-            last_update: std::time::Instant::now(),
-            time_window: 15.0,
+            gas_history: VecDeque::with_capacity(history_len),
+            brake_history: VecDeque::with_capacity(history_len),
+            time_window,
+            show_stats: false,
+            standings_view: None,
+            show_lap_timing: false,
+            lap_timer: LapTimer::new(),
+            show_delta: false,
+            delta_reference: DeltaReference::OwnBest,
+            lap_delta: LapDelta::new(),
+            recorded_best: None,
+            recorded_best_key: (String::new(), String::new()),
+            recorded_best_loader: None,
 
             // Initialize all caches
+            cars: CarDatabase::load(paths::config_dir().join("cars")),
             car_poll_timer: std::time::Instant::now(),
             current_model_name: String::new(),
-            cached_shift_rpm: 0,
+            cached_profile: None,
+            unknown_car: None,
             cached_bb_offset: 0.0,
 
-            // Init Recording defaults
-            rec_last_gear: 0,
-            rec_peak_rpm: 0,
-            is_recording: false,
+            calibration: None,
+            calibration_review: None,
 
             cache_fuel: "0.0".to_string(),
             last_fuel: -1.0,
@@ -104,139 +153,391 @@ impl OverlayApp {
             last_speed: -1,
             cache_rpm: "0".to_string(),
             last_rpm: -1,
-            last_ign: -1,
+            last_ign: false,
             cache_gas_text: "0".to_string(),
             last_gas_int: -1,
             cache_brake_text: "0".to_string(),
             last_brake_int: -1,
         }
     }
+}
+
+/// Replay transport keys. Returns true after a jump, when the graph history
+/// no longer lines up with the replayed time.
+fn replay_keys(ctx: &egui::Context, control: &ReplayControl) -> bool {
+    let speed = control.state().speed;
+    ctx.input(|i| {
+        if i.key_pressed(egui::Key::Space) {
+            control.toggle_play();
+        }
+        if i.key_pressed(egui::Key::ArrowUp) {
+            control.set_speed(speed * 2.0);
+        }
+        if i.key_pressed(egui::Key::ArrowDown) {
+            control.set_speed(speed / 2.0);
+        }
+        if i.key_pressed(egui::Key::Period) {
+            control.step(1);
+        }
+        if i.key_pressed(egui::Key::Comma) {
+            control.step(-1);
+        }
+        let jump = if i.modifiers.shift { 30.0 } else { 5.0 };
+        if i.key_pressed(egui::Key::ArrowRight) {
+            control.seek_by(jump);
+            return true;
+        }
+        if i.key_pressed(egui::Key::ArrowLeft) {
+            control.seek_by(-jump);
+            return true;
+        }
+        false
+    })
+}
+
+impl OverlayApp {
+    fn review_calibration(&mut self, ctx: &egui::Context) {
+        let Some((car_model, results)) = &self.calibration_review else {
+            return;
+        };
+        let profile = self.cars.get_by_name(car_model);
+        let current = |gear| profile.and_then(|p: &CarProfile| p.upshift_rpm(gear));
+
+        match widgets::calibration_review(ctx, car_model, results, &current) {
+            Some(ReviewAction::Save) => {
+                let shifts: Vec<_> = results
+                    .iter()
+                    .filter_map(|r| Some((r.gear, r.recommended?)))
+                    .collect();
+                match self.cars.save_upshift_rpm(car_model, &shifts) {
+                    Ok(path) => println!("CALIBRATION: saved to {}", path.display()),
+                    Err(e) => println!("CALIBRATION: saving failed: {}", e),
+                }
+                // Pick up the new table for the rev strip
+                self.current_model_name.clear();
+                self.calibration_review = None;
+            }
+            Some(ReviewAction::Discard) => self.calibration_review = None,
+            None => {}
+        }
+    }
+
+    /// The relative or the leaderboard, in a window of its own.
+    fn show_standings(&self, ctx: &egui::Context) {
+        let Some(view) = self.standings_view else {
+            return;
+        };
+        let standings = self.sampler.standings().map(|mut s| {
+            self.cars.resolve_standings(&mut s);
+            s
+        });
+        let (title, rows) = match view {
+            StandingsView::Relative => ("Relative", 2 * RELATIVE_CARS + 1),
+            StandingsView::Leaderboard => ("Leaderboard", 20),
+        };
+        let contents = |ui: &mut egui::Ui| match &standings {
+            Some(s) if !s.cars.is_empty() => match view {
+                StandingsView::Relative => widgets::relative(ui, &s.relative(RELATIVE_CARS)),
+                StandingsView::Leaderboard => {
+                    egui::ScrollArea::vertical()
+                        .show(ui, |ui| widgets::leaderboard(ui, &s.leaderboard()));
+                }
+            },
+            _ => widgets::status_message(ui, "NO STANDINGS"),
+        };
+        overlay_window(
+            ctx,
+            "standings",
+            title,
+            [320.0, rows as f32 * 20.0 + 20.0],
+            contents,
+        );
+    }
 
-    fn get_physics(&self) -> &Physics {
-        unsafe { &*(self.physics_mmap.as_ptr() as *const Physics) }
+    /// Lap and sector times, in a window of their own.
+    fn show_lap_timing(&mut self, ctx: &egui::Context, current_lap_time: Option<f64>) {
+        if !self.show_lap_timing {
+            return;
+        }
+        if let Some(standings) = self.sampler.standings() {
+            self.lap_timer.set_standings(&standings);
+        }
+        let timer = &self.lap_timer;
+        overlay_window(ctx, "lap_timing", "Lap Timing", [300.0, 102.0], |ui| {
+            widgets::lap_timing(ui, timer, current_lap_time)
+        });
     }
-    fn get_graphics(&self) -> &Graphics {
-        unsafe { &*(self.graphics_mmap.as_ptr() as *const Graphics) }
+
+    /// The delta bar, in a window of its own.
+    fn show_delta(&mut self, ctx: &egui::Context, statics: &data::Statics) {
+        if !self.show_delta {
+            return;
+        }
+        self.load_recorded_best(
+            data::parse_static_string(&statics.car_model),
+            data::parse_static_string(&statics._track),
+        );
+        let reference = self
+            .delta_reference
+            .trace(&self.lap_timer, self.recorded_best.as_ref());
+        let label = match reference {
+            Some(lap) => format!(
+                "{} {}",
+                self.delta_reference.name(),
+                widgets::laps::format_time(lap.lap_time())
+            ),
+            None => self.delta_reference.name().to_string(),
+        };
+        let (delta, trend) = (self.lap_delta.delta(), self.lap_delta.trend());
+        overlay_window(ctx, "delta", "Delta", [420.0, 64.0], |ui| {
+            widgets::delta_bar(ui, delta, trend, &label)
+        });
     }
-    fn get_statics(&self) -> &Statics {
-        unsafe { &*(self.statics_mmap.as_ptr() as *const Statics) }
+
+    /// Looks up the fastest recorded lap whenever the car or track changes,
+    /// on a thread of its own as it reads every session.
+    fn load_recorded_best(&mut self, car_model: String, track: String) {
+        if let Some(loader) = self.recorded_best_loader.take_if(|l| l.is_finished()) {
+            self.recorded_best = loader.join().ok().flatten();
+            if let Some(lap) = &self.recorded_best {
+                println!(
+                    "DELTA: recorded best {} for {} at {}",
+                    widgets::laps::format_time(lap.lap_time()),
+                    self.recorded_best_key.0,
+                    self.recorded_best_key.1
+                );
+            }
+        }
+        let key = (car_model, track);
+        if key.0.is_empty() || key == self.recorded_best_key {
+            return;
+        }
+        self.recorded_best_key = key.clone();
+        self.recorded_best = None;
+        let dir = recording::sessions_dir();
+        self.recorded_best_loader = Some(std::thread::spawn(move || {
+            recorded_best(&dir, &key.0, &key.1)
+        }));
     }
 }
 
+/// A window besides the main one, without decorations like it.
+fn overlay_window(
+    ctx: &egui::Context,
+    id: &str,
+    title: &str,
+    size: [f32; 2],
+    contents: impl Fn(&mut egui::Ui),
+) {
+    ctx.show_viewport_immediate(
+        egui::ViewportId::from_hash_of(id),
+        egui::ViewportBuilder::default()
+            .with_title(title)
+            .with_decorations(false)
+            .with_inner_size(size),
+        |ctx, class| {
+            // Backends without multiple windows draw it inside the main one
+            if class == egui::ViewportClass::Embedded {
+                egui::Window::new(title).show(ctx, &contents);
+            } else {
+                egui::CentralPanel::default()
+                    .frame(panel_frame())
+                    .show(ctx, &contents);
+            }
+        },
+    );
+}
+
+fn panel_frame() -> egui::Frame {
+    egui::Frame::NONE
+        .fill(egui::Color32::from_rgba_premultiplied(0, 0, 0, 100))
+        .inner_margin(10.0)
+}
+
 impl eframe::App for OverlayApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        if self.car_poll_timer.elapsed().as_secs() >= 2 {
-            let s = self.get_statics();
+        // Every sample since the last repaint goes into the graph, so its
+        // resolution follows the sampler rate rather than the display rate.
+        let (gas_history, brake_history) = (&mut self.gas_history, &mut self.brake_history);
+        let calibration = &mut self.calibration;
+        let lap_timer = &mut self.lap_timer;
+        let (lap_delta, delta_reference) = (&mut self.lap_delta, self.delta_reference);
+        let recorded_best = self.recorded_best.as_ref();
+        self.reader.drain(|sample| {
+            let s = sample.frame.telemetry(0.0);
+            gas_history.push_back((sample.t, s.throttle as f64, s.tc.active));
+            brake_history.push_back((sample.t, s.brake as f64, s.abs.active));
+            lap_timer.observe(&s);
+            let reference = delta_reference.trace(lap_timer, recorded_best);
+            lap_delta.observe(sample.t, &s, reference);
+            if let Some(calibration) = calibration {
+                calibration.observe(&sample);
+            }
+        });
+
+        let latest = self.reader.latest().filter(|_| self.sampler.connected());
+        let Some(Sample { frame, .. }) = latest else {
+            let text = match self.sampler.error() {
+                Some(e) => e.to_uppercase(),
+                None => "WAITING FOR SIMULATOR".to_string(),
+            };
+            egui::CentralPanel::default()
+                .frame(panel_frame())
+                .show(ctx, |ui| widgets::status_message(ui, &text));
+            ctx.request_repaint_after(std::time::Duration::from_millis(250));
+            return;
+        };
+
+        let stats = self.sampler.stats();
+        if ctx.input(|i| i.key_pressed(egui::Key::F3)) {
+            self.show_stats = !self.show_stats;
+        }
+        if ctx.input(|i| i.key_pressed(egui::Key::T)) {
+            self.show_lap_timing = !self.show_lap_timing;
+        }
+        if ctx.input(|i| i.key_pressed(egui::Key::D)) {
+            self.show_delta = !self.show_delta;
+        }
+        if ctx.input(|i| i.key_pressed(egui::Key::B)) {
+            self.delta_reference = self.delta_reference.next();
+            println!("DELTA: against {}", self.delta_reference.name());
+        }
+        for (key, view) in [
+            (egui::Key::R, StandingsView::Relative),
+            (egui::Key::L, StandingsView::Leaderboard),
+        ] {
+            if ctx.input(|i| i.key_pressed(key)) {
+                self.standings_view = (self.standings_view != Some(view)).then_some(view);
+            }
+        }
+
+        let force_reload = ctx.input(|i| i.key_pressed(egui::Key::F5));
+        if force_reload || self.car_poll_timer.elapsed().as_secs() >= 2 {
+            let reloaded = if force_reload {
+                self.cars.reload();
+                true
+            } else {
+                self.cars.reload_if_changed()
+            };
+            if reloaded {
+                // Re-resolve the current car against the new profiles
+                self.current_model_name.clear();
+            }
+
+            let s = &frame.statics;
             let raw_name = data::parse_static_string(&s.car_model[..]);
 
             if raw_name != self.current_model_name {
                 println!("Car Detected: '{}'", raw_name);
                 self.current_model_name = raw_name.clone();
 
-                if let Some(car) = data::get_car_by_name(&raw_name) {
-                    println!(" -> Match Found! Shift: {}", car.shift_rpm);
-                    self.cached_shift_rpm = car.shift_rpm;
+                self.unknown_car = None;
+                if let Some(car) = self.cars.get_by_name(&raw_name) {
+                    let class = car.class.map_or("?", |c| c.name());
+                    println!(
+                        " -> Match Found! id {} ({}) Shift: {}",
+                        car.car_id, class, car.shift_rpm
+                    );
                     self.cached_bb_offset = car.bb_offset;
+                    self.cached_profile = Some(car.clone());
                 } else {
-                    println!(" -> No Match in car_data.rs");
-                    self.cached_shift_rpm = 0;
+                    println!(" -> No car profile, using defaults");
                     self.cached_bb_offset = 0.0;
+                    self.cached_profile = None;
+                    if !raw_name.is_empty() {
+                        self.unknown_car = Some(raw_name);
+                    }
                 }
             }
             self.car_poll_timer = std::time::Instant::now();
         }
 
-        let (gas, brake, gear, speed_kmh, rpm, max_rpm, fuel, bb, tc_act, abs_act, ign) = {
-            let p = self.get_physics();
-            (
-                p.gas,
-                p.brake,
-                p.gear,
-                p.speed_kmh,
-                p.rpms,
-                p.current_max_rpm,
-                p.fuel,
-                p.brake_bias,
-                p.tc == 1.0,
-                p.abs == 1.0,
-                p.ignition_on,
-            )
-        };
-
-        let (tc, abs) = {
-            let g = self.get_graphics();
-            (g.tc, g.abs)
-        };
-
-        // --- RECORDING LOGIC (Fixed with Peak Tracking) ---
-        if ctx.input(|i| i.key_pressed(egui::Key::Space)) {
-            self.is_recording = !self.is_recording;
-            println!(
-                "RECORDING MODE: {}",
-                if self.is_recording { "ON" } else { "OFF" }
-            );
-
-            if gear >= 2 {
-                self.rec_last_gear = gear;
-                self.rec_peak_rpm = rpm;
+        let telemetry = frame.telemetry(self.cached_bb_offset);
+        let TelemetryFrame {
+            throttle: gas,
+            brake,
+            gear,
+            tc,
+            abs,
+            ignition_on: ign,
+            ..
+        } = telemetry;
+        let speed_kmh = telemetry.speed_kmh();
+        let rpm = telemetry.rpm as i32;
+        let max_rpm = telemetry.max_rpm as i32;
+
+        if let Some(control) = &self.replay {
+            let cleared = replay_keys(ctx, control);
+            if cleared {
+                self.gas_history.clear();
+                self.brake_history.clear();
             }
         }
 
-        if rpm > self.rec_peak_rpm {
-            self.rec_peak_rpm = rpm;
+        if let Some(recorder) = &self.recorder
+            && ctx.input(|i| i.key_pressed(egui::Key::Space))
+        {
+            recorder.toggle();
         }
-
-        if self.is_recording {
-            if gear >= 2 {
-                if gear > self.rec_last_gear && self.rec_last_gear >= 2 {
-                    let from_gear = self.rec_last_gear - 1;
-                    let to_gear = gear - 1;
-
-                    println!(
-                        "CAPTURED: Gear {} -> {} at {} RPM",
-                        from_gear, to_gear, self.rec_peak_rpm
-                    );
-
-                    self.rec_last_gear = gear;
-                    self.rec_peak_rpm = rpm;
-                } else if gear < self.rec_last_gear {
-                    self.rec_last_gear = gear;
-                    self.rec_peak_rpm = rpm;
+        let is_recording = self.recorder.as_ref().is_some_and(|r| r.is_recording());
+
+        if ctx.input(|i| i.key_pressed(egui::Key::C)) {
+            match self.calibration.take() {
+                Some(calibration) => {
+                    println!("CALIBRATION: OFF");
+                    self.calibration_review = Some((
+                        data::parse_static_string(&frame.statics.car_model),
+                        calibration.results(),
+                    ));
+                }
+                None => {
+                    println!("CALIBRATION: ON");
+                    self.calibration = Some(ShiftCalibration::new());
+                    self.calibration_review = None;
                 }
             }
-        } else if gear >= 2 && gear != self.rec_last_gear {
-            self.rec_last_gear = gear;
-            self.rec_peak_rpm = rpm;
         }
-        // --------------------------------
+        self.review_calibration(ctx);
+
+        if let Some(car_model) = &self.unknown_car
+            && widgets::unknown_car_notice(ctx, car_model)
+        {
+            match self.cars.create_profile(car_model) {
+                Ok(path) => println!("Created car profile {}", path.display()),
+                Err(e) => println!("Cannot create car profile for {}: {}", car_model, e),
+            }
+            self.current_model_name.clear();
+        }
+        widgets::profile_errors_notice(ctx, &self.cars.errors);
 
+        // Sims that only report how full the tank is show percent
+        let (fuel, fuel_unit) = match telemetry.fuel {
+            Some(litres) => (litres, ""),
+            None => (telemetry.fuel_percent.unwrap_or(0.0), "%"),
+        };
         if (fuel - self.last_fuel).abs() > 0.05 {
-            self.cache_fuel = format!("{:.1}", fuel);
+            self.cache_fuel = format!("{:.1}{}", fuel, fuel_unit);
             self.last_fuel = fuel;
         }
-        let bb_disp = if bb == 0.0 {
-            0.0
-        } else {
-            (bb * 100.0) + self.cached_bb_offset
-        };
+        let bb_disp = telemetry.brake_bias.unwrap_or(0.0);
 
         if (bb_disp - self.last_bb).abs() > 0.05 {
             self.cache_bb = format!("{:.1}", bb_disp);
             self.last_bb = bb_disp;
         }
-        if (tc as f32 - self.last_tc).abs() > 0.1 {
-            self.cache_tc = format!("{}", tc);
-            self.last_tc = tc as f32;
+        if (tc.level as f32 - self.last_tc).abs() > 0.1 {
+            self.cache_tc = format!("{}", tc.level);
+            self.last_tc = tc.level as f32;
         }
-        if (abs as f32 - self.last_abs).abs() > 0.1 {
-            self.cache_abs = format!("{}", abs);
-            self.last_abs = abs as f32;
+        if (abs.level as f32 - self.last_abs).abs() > 0.1 {
+            self.cache_abs = format!("{}", abs.level);
+            self.last_abs = abs.level as f32;
         }
         if gear != self.last_gear {
             self.cache_gear = match gear {
-                0 => "R".to_string(),
-                1 => "N".to_string(),
-                g => (g - 1).to_string(),
+                -1 => "R".to_string(),
+                0 => "N".to_string(),
+                g => g.to_string(),
             };
             self.last_gear = gear;
         }
@@ -246,7 +547,7 @@ impl eframe::App for OverlayApp {
             self.last_speed = speed_int;
         }
         if rpm != self.last_rpm || ign != self.last_ign {
-            self.cache_rpm = if ign != 1 {
+            self.cache_rpm = if !ign {
                 "IGNITION OFF".to_string()
             } else if rpm <= 0 {
                 "ENGINE OFF".to_string()
@@ -275,20 +576,7 @@ impl eframe::App for OverlayApp {
             self.last_brake_int = brake_pct;
         }
 
-        let now = std::time::Instant::now();
-        let t = now.duration_since(self.start_time).as_secs_f64();
-
-        if self.last_update.elapsed().as_millis() >= 3 {
-            self.gas_history.push_back((t, gas as f64, tc_act));
-            self.brake_history.push_back((t, brake as f64, abs_act));
-            self.last_update = now;
-
-            if self.gas_history.len() > 3000 {
-                self.gas_history.pop_front();
-                self.brake_history.pop_front();
-            }
-        }
-
+        let t = self.sampler.now();
         let min_time = t - self.time_window;
         while let Some(&(time, _, _)) = self.gas_history.front() {
             if time < min_time {
@@ -299,12 +587,8 @@ impl eframe::App for OverlayApp {
             }
         }
 
-        let panel_frame = egui::Frame::NONE
-            .fill(egui::Color32::from_rgba_premultiplied(0, 0, 0, 100))
-            .inner_margin(10.0);
-
         egui::CentralPanel::default()
-            .frame(panel_frame)
+            .frame(panel_frame())
             .show(ctx, |ui| {
                 ui.horizontal(|ui| {
                     widgets::gear_indicator(ui, &self.cache_gear);
@@ -316,11 +600,15 @@ impl eframe::App for OverlayApp {
                             0.0
                         };
 
-                        let (is_strobe, is_yellow) = if self.cached_shift_rpm > 0 {
-                            (
-                                rpm > self.cached_shift_rpm,
-                                rpm > (self.cached_shift_rpm - 200),
-                            )
+                        let profile = self.cached_profile.as_ref();
+                        let shift_rpm = profile.and_then(|p| p.upshift_rpm(gear));
+                        let downshift_pct = profile
+                            .and_then(|p| p.downshift_rpm(gear))
+                            .filter(|_| max_rpm > 0)
+                            .map(|r| (r as f32 / max_rpm as f32).clamp(0.0, 1.0));
+
+                        let (is_strobe, is_yellow) = if let Some(shift_rpm) = shift_rpm {
+                            (rpm > shift_rpm, rpm > (shift_rpm - 200))
                         } else {
                             let f_rpm = rpm as f32;
                             let f_max = max_rpm as f32;
@@ -333,7 +621,8 @@ impl eframe::App for OverlayApp {
                             is_yellow,
                             is_strobe,
                             &self.cache_rpm,
-                            ign == 1,
+                            ign,
+                            downshift_pct,
                         );
                         ui.add_space(5.0);
                         ui.horizontal(|ui| {
@@ -356,15 +645,48 @@ impl eframe::App for OverlayApp {
                         COLOR_GAS,
                         COLOR_BRAKE,
                     );
-                    widgets::pedal_bar(ui, brake, COLOR_BRAKE, abs_act, &self.cache_brake_text);
-                    widgets::pedal_bar(ui, gas, COLOR_GAS, tc_act, &self.cache_gas_text);
+                    widgets::pedal_bar(ui, brake, COLOR_BRAKE, abs.active, &self.cache_brake_text);
+                    widgets::pedal_bar(ui, gas, COLOR_GAS, tc.active, &self.cache_gas_text);
                 });
+
+                if let Some(control) = &self.replay {
+                    ui.add_space(5.0);
+                    let state = control.state();
+                    if let Some(fraction) = widgets::replay_bar(ui, &state) {
+                        control.seek_to(state.start + fraction * (state.end - state.start));
+                        self.gas_history.clear();
+                        self.brake_history.clear();
+                    }
+                }
+
+                if is_recording {
+                    widgets::rec_indicator(ui);
+                }
+                if let Some(calibration) = &self.calibration {
+                    widgets::calibration_indicator(ui, calibration.shift_count());
+                }
+                if self.show_stats {
+                    let text = format!(
+                        "SIM {:.0} Hz  READ {:.0} Hz  DROPPED {}  TORN {}",
+                        stats.packet_rate, stats.read_rate, stats.dropped, stats.torn
+                    );
+                    widgets::corner_text(ui, &text, egui::Color32::LIGHT_GRAY);
+                } else if stats.stale {
+                    widgets::corner_text(ui, "PAUSED", egui::Color32::YELLOW);
+                }
             });
+        self.show_standings(ctx);
+        self.show_lap_timing(ctx, telemetry.current_lap_time);
+        self.show_delta(ctx, &frame.statics);
         ctx.request_repaint();
     }
 }
 
 fn main() -> eframe::Result<()> {
+    let args = cli::Args::parse();
+    if let Some(files) = &args.analyze {
+        return analyze::run(files);
+    }
     let options = eframe::NativeOptions {
         renderer: eframe::Renderer::Wgpu,
         vsync: true,
@@ -373,25 +695,47 @@ fn main() -> eframe::Result<()> {
             // .with_always_on_top()
             // .with_mouse_passthrough(true)
             .with_decorations(false)
-            .with_inner_size([550.0, 250.0]),
+            .with_inner_size([550.0, if args.replay.is_some() { 275.0 } else { 250.0 }]),
         ..Default::default()
     };
 
-    let physics_file = OpenOptions::new()
-        .read(true)
-        .open("/dev/shm/acpmf_physics")
-        .expect("No Physics SHM");
-    let graphics_file = OpenOptions::new()
-        .read(true)
-        .open("/dev/shm/acpmf_graphics")
-        .expect("No Graphics SHM");
-    let statics_file = OpenOptions::new()
-        .read(true)
-        .open("/dev/shm/acpmf_static")
-        .expect("No Static SHM");
-    let physics_mmap = unsafe { Mmap::map(&physics_file).expect("Map Fail") };
-    let graphics_mmap = unsafe { Mmap::map(&graphics_file).expect("Map Fail") };
-    let statics_mmap = unsafe { Mmap::map(&statics_file).expect("Map Fail") };
+    let (source, replay): (Box<dyn TelemetrySource>, _) = match &args.replay {
+        Some(path) => match ReplaySource::open(path) {
+            Ok(replay) => {
+                let control = replay.control();
+                (Box::new(replay), Some(control))
+            }
+            Err(e) => {
+                eprintln!("Cannot replay {}: {}", path.display(), e);
+                std::process::exit(1);
+            }
+        },
+        None if args.synthetic => (Box::new(SyntheticSource::new()), None),
+        None => match args.sim {
+            Sim::Acc => {
+                let mut acc = AccShm::new(&args.shm_dir);
+                if let Some(addr) = args.broadcast {
+                    acc = acc.with_broadcast(BroadcastClient::new(addr, &args.broadcast_password));
+                }
+                (Box::new(acc), None)
+            }
+            Sim::Rf2 => (Box::new(Rf2Shm::new(&args.shm_dir)), None),
+            Sim::Pcars2 => (Box::new(Pcars2Shm::new(&args.shm_dir)), None),
+            Sim::F1 => (
+                Box::new(F1Udp::new(args.udp_addr(data::f1::DEFAULT_PORT))),
+                None,
+            ),
+            Sim::Forza => (
+                Box::new(ForzaUdp::new(args.udp_addr(data::forza::DEFAULT_PORT))),
+                None,
+            ),
+            Sim::OutGauge => {
+                let outgauge = args.udp_addr(data::outgauge::DEFAULT_OUTGAUGE_PORT);
+                let outsim = args.outsim_addr(data::outgauge::DEFAULT_OUTSIM_PORT);
+                (Box::new(OutGaugeUdp::new(outgauge, outsim)), None)
+            }
+        },
+    };
 
     eframe::run_native(
         "AC Overlay",
@@ -409,12 +753,7 @@ fn main() -> eframe::Result<()> {
                 .insert(0, "formula".to_owned());
             cc.egui_ctx.set_fonts(fonts);
 
-            Ok(Box::new(OverlayApp::new(
-                physics_mmap,
-                graphics_mmap,
-                statics_mmap,
-                cc,
-            )))
+            Ok(Box::new(OverlayApp::new(source, replay, args.rate_hz, cc)))
         }),
     )
 }
@@ -0,0 +1,18 @@
+use std::path::PathBuf;
+
+/// `$XDG_DATA_HOME/simtem`, falling back to `~/.local/share/simtem`.
+pub fn data_dir() -> PathBuf {
+    xdg_dir("XDG_DATA_HOME", ".local/share").join("simtem")
+}
+
+/// `$XDG_CONFIG_HOME/simtem`, falling back to `~/.config/simtem`.
+pub fn config_dir() -> PathBuf {
+    xdg_dir("XDG_CONFIG_HOME", ".config").join("simtem")
+}
+
+fn xdg_dir(var: &str, fallback: &str) -> PathBuf {
+    match std::env::var_os(var) {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME").unwrap_or_default()).join(fallback),
+    }
+}
@@ -0,0 +1,412 @@
+//! On-disk session format.
+//!
+//! ```text
+//! header   magic[8] version:u16 reserved:u16
+//!          physics_size:u32 graphics_size:u32 statics_size:u32 start_unix_ms:i64
+//! chunk*   compressed_len:u32 record_count:u32 first_t:f64 last_t:f64
+//!          deflate(record*)
+//! record   kind:u8 t:f64 payload
+//! ```
+//!
+//! Every chunk starts with a statics record and a full frame, so it can be
+//! decoded on its own when seeking. All integers are little endian.
+
+use crate::data::{Graphics, Physics, ShmPage, Statics};
+use crate::sampler::Sample;
+use crate::source::Frame;
+use flate2::Compression;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Error, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+pub const MAGIC: &[u8; 8] = b"SIMTEM\0\0";
+pub const VERSION: u16 = 1;
+const HEADER_LEN: u64 = 8 + 2 + 2 + 4 * 3 + 8;
+const CHUNK_HEADER_LEN: u64 = 4 + 4 + 8 + 8;
+
+pub const RECORD_STATICS: u8 = 1;
+pub const RECORD_FRAME: u8 = 2;
+/// Physics only, graphics unchanged since the previous record.
+pub const RECORD_PHYSICS: u8 = 3;
+
+const CHUNK_SECONDS: f64 = 1.0;
+
+pub struct SessionWriter {
+    out: BufWriter<File>,
+    chunk: Vec<u8>,
+    count: u32,
+    first_t: f64,
+    last_t: f64,
+    last_physics_id: Option<i32>,
+    last_graphics: Option<Graphics>,
+}
+
+impl SessionWriter {
+    pub fn create(path: &Path, start_unix_ms: i64) -> std::io::Result<Self> {
+        let mut out = BufWriter::new(File::create(path)?);
+        out.write_all(MAGIC)?;
+        out.write_all(&VERSION.to_le_bytes())?;
+        out.write_all(&0u16.to_le_bytes())?;
+        for size in [
+            size_of::<Physics>(),
+            size_of::<Graphics>(),
+            size_of::<Statics>(),
+        ] {
+            out.write_all(&(size as u32).to_le_bytes())?;
+        }
+        out.write_all(&start_unix_ms.to_le_bytes())?;
+
+        Ok(Self {
+            out,
+            chunk: Vec::with_capacity(256 * 1024),
+            count: 0,
+            first_t: 0.0,
+            last_t: 0.0,
+            last_physics_id: None,
+            last_graphics: None,
+        })
+    }
+
+    /// Appends a sample, skipping repeats of a physics packet already written.
+    pub fn write(&mut self, sample: &Sample) -> std::io::Result<()> {
+        let frame = &sample.frame;
+        if self.last_physics_id == Some(frame.physics._packet_id) {
+            return Ok(());
+        }
+        self.last_physics_id = Some(frame.physics._packet_id);
+
+        if self.count > 0 && sample.t - self.first_t >= CHUNK_SECONDS {
+            self.flush_chunk()?;
+        }
+
+        if self.count == 0 {
+            self.first_t = sample.t;
+            self.record(RECORD_STATICS, sample.t, &[frame.statics.as_bytes()]);
+            self.last_graphics = None;
+        }
+
+        let graphics_changed = self
+            .last_graphics
+            .is_none_or(|g| g._packet_id != frame.graphics._packet_id);
+        if graphics_changed {
+            self.record(
+                RECORD_FRAME,
+                sample.t,
+                &[frame.physics.as_bytes(), frame.graphics.as_bytes()],
+            );
+            self.last_graphics = Some(frame.graphics);
+        } else {
+            self.record(RECORD_PHYSICS, sample.t, &[frame.physics.as_bytes()]);
+        }
+        self.last_t = sample.t;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> std::io::Result<()> {
+        self.flush_chunk()?;
+        self.out.flush()
+    }
+
+    fn record(&mut self, kind: u8, t: f64, parts: &[&[u8]]) {
+        self.chunk.push(kind);
+        self.chunk.extend_from_slice(&t.to_le_bytes());
+        for part in parts {
+            self.chunk.extend_from_slice(part);
+        }
+        self.count += 1;
+    }
+
+    fn flush_chunk(&mut self) -> std::io::Result<()> {
+        if self.count == 0 {
+            return Ok(());
+        }
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::fast());
+        encoder.write_all(&self.chunk)?;
+        let compressed = encoder.finish()?;
+
+        self.out
+            .write_all(&(compressed.len() as u32).to_le_bytes())?;
+        self.out.write_all(&self.count.to_le_bytes())?;
+        self.out.write_all(&self.first_t.to_le_bytes())?;
+        self.out.write_all(&self.last_t.to_le_bytes())?;
+        self.out.write_all(&compressed)?;
+
+        self.chunk.clear();
+        self.count = 0;
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct ChunkInfo {
+    offset: u64,
+    compressed_len: u32,
+    pub record_count: u32,
+    pub first_t: f64,
+    pub last_t: f64,
+}
+
+/// Random access to a session file, one chunk at a time.
+pub struct SessionReader {
+    file: BufReader<File>,
+    pub start_unix_ms: i64,
+    pub chunks: Vec<ChunkInfo>,
+}
+
+impl SessionReader {
+    pub fn open(path: &Path) -> std::io::Result<Self> {
+        let mut file = BufReader::new(File::open(path)?);
+
+        let mut header = [0u8; HEADER_LEN as usize];
+        file.read_exact(&mut header)
+            .map_err(|_| invalid("not a SimTem session file"))?;
+        if &header[0..8] != MAGIC {
+            return Err(invalid("not a SimTem session file"));
+        }
+        let version = u16::from_le_bytes([header[8], header[9]]);
+        if version != VERSION {
+            return Err(invalid(&format!(
+                "session format version {} is not supported (expected {})",
+                version, VERSION
+            )));
+        }
+        let sizes = [12, 16, 20].map(|at| u32_at(&header, at) as usize);
+        if sizes
+            != [
+                size_of::<Physics>(),
+                size_of::<Graphics>(),
+                size_of::<Statics>(),
+            ]
+        {
+            return Err(invalid(
+                "session was recorded with a different telemetry layout",
+            ));
+        }
+        let start_unix_ms = i64::from_le_bytes(header[24..32].try_into().unwrap());
+
+        // Index the chunks by hopping over their compressed payloads
+        let mut chunks = Vec::new();
+        let mut offset = HEADER_LEN;
+        let mut chunk_header = [0u8; CHUNK_HEADER_LEN as usize];
+        loop {
+            match file.read_exact(&mut chunk_header) {
+                Ok(()) => {}
+                Err(e) if e.kind() == ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+            let chunk = ChunkInfo {
+                offset: offset + CHUNK_HEADER_LEN,
+                compressed_len: u32_at(&chunk_header, 0),
+                record_count: u32_at(&chunk_header, 4),
+                first_t: f64::from_le_bytes(chunk_header[8..16].try_into().unwrap()),
+                last_t: f64::from_le_bytes(chunk_header[16..24].try_into().unwrap()),
+            };
+            offset = chunk.offset + chunk.compressed_len as u64;
+            file.seek(SeekFrom::Start(offset))?;
+            chunks.push(chunk);
+        }
+        // A session cut short (crash, power loss) may end mid-chunk
+        let len = file.get_ref().metadata()?.len();
+        while chunks
+            .last()
+            .is_some_and(|c| c.offset + c.compressed_len as u64 > len)
+        {
+            chunks.pop();
+        }
+        if chunks.is_empty() {
+            return Err(invalid("session file contains no frames"));
+        }
+
+        Ok(Self {
+            file,
+            start_unix_ms,
+            chunks,
+        })
+    }
+
+    pub fn decode_chunk(&mut self, index: usize) -> std::io::Result<Vec<Sample>> {
+        let chunk = self.chunks[index];
+        self.file.seek(SeekFrom::Start(chunk.offset))?;
+        let mut compressed = vec![0u8; chunk.compressed_len as usize];
+        self.file.read_exact(&mut compressed)?;
+        let mut data = Vec::new();
+        DeflateDecoder::new(&compressed[..]).read_to_end(&mut data)?;
+
+        let mut samples = Vec::with_capacity(chunk.record_count as usize);
+        let mut statics = None;
+        let mut graphics = None;
+        let mut at = 0;
+        while at < data.len() {
+            let kind = data[at];
+            let t = f64::from_le_bytes(
+                data.get(at + 1..at + 9)
+                    .ok_or_else(|| invalid("truncated record"))?
+                    .try_into()
+                    .unwrap(),
+            );
+            at += 9;
+            let rest = &data[at..];
+            match kind {
+                RECORD_STATICS => {
+                    statics = Some(page::<Statics>(rest)?);
+                    at += size_of::<Statics>();
+                }
+                RECORD_FRAME | RECORD_PHYSICS => {
+                    let physics = page::<Physics>(rest)?;
+                    at += size_of::<Physics>();
+                    if kind == RECORD_FRAME {
+                        graphics = Some(page::<Graphics>(&data[at..])?);
+                        at += size_of::<Graphics>();
+                    }
+                    let (Some(statics), Some(graphics)) = (statics, graphics) else {
+                        return Err(invalid("frame before statics in chunk"));
+                    };
+                    samples.push(Sample {
+                        t,
+                        frame: Frame {
+                            physics,
+                            graphics,
+                            statics,
+                        },
+                    });
+                }
+                _ => return Err(invalid("unknown record kind")),
+            }
+        }
+        Ok(samples)
+    }
+}
+
+fn page<T: ShmPage>(bytes: &[u8]) -> std::io::Result<T> {
+    T::from_bytes(bytes).ok_or_else(|| invalid("truncated record"))
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+}
+
+fn invalid(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::write_static_string;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!(
+            "simtem-test-{}-{}.simtem",
+            name,
+            std::process::id()
+        ))
+    }
+
+    /// 40 samples at 20 Hz, a new graphics packet on every third one.
+    fn samples() -> Vec<Sample> {
+        let mut statics = Statics::zeroed();
+        write_static_string(&mut statics.car_model, "bmw_m4_gt4");
+        (0..40)
+            .map(|i| {
+                let mut frame = Frame {
+                    physics: Physics::zeroed(),
+                    graphics: Graphics::zeroed(),
+                    statics,
+                };
+                frame.physics._packet_id = i;
+                frame.physics.rpms = 3000 + i;
+                frame.graphics._packet_id = i / 3;
+                frame.graphics._completed_laps = i / 3;
+                Sample {
+                    t: i as f64 * 0.05,
+                    frame,
+                }
+            })
+            .collect()
+    }
+
+    fn write_session(path: &Path, samples: &[Sample]) {
+        let mut writer = SessionWriter::create(path, 1_700_000_000_000).unwrap();
+        for sample in samples {
+            writer.write(sample).unwrap();
+            // Repeats of a physics packet are dropped
+            writer.write(sample).unwrap();
+        }
+        writer.finish().unwrap();
+    }
+
+    fn read_all(reader: &mut SessionReader) -> Vec<Sample> {
+        (0..reader.chunks.len())
+            .flat_map(|i| reader.decode_chunk(i).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn samples_round_trip() {
+        let path = temp_path("round-trip");
+        let written = samples();
+        write_session(&path, &written);
+
+        let mut reader = SessionReader::open(&path).unwrap();
+        assert_eq!(reader.start_unix_ms, 1_700_000_000_000);
+        assert_eq!(reader.chunks.len(), 2);
+        assert_eq!(reader.chunks[0].first_t, 0.0);
+        assert_eq!(reader.chunks[1].last_t, 39.0 * 0.05);
+        // A statics record and one frame or physics record per sample
+        assert_eq!(reader.chunks[0].record_count, 1 + 20);
+        assert_eq!(reader.chunks[1].record_count, 1 + 20);
+
+        let read = read_all(&mut reader);
+        assert_eq!(read.len(), written.len());
+        for (read, written) in read.iter().zip(&written) {
+            assert_eq!(read.t, written.t);
+            assert_eq!(
+                read.frame.physics.as_bytes(),
+                written.frame.physics.as_bytes()
+            );
+            assert_eq!(
+                read.frame.graphics.as_bytes(),
+                written.frame.graphics.as_bytes()
+            );
+            assert_eq!(
+                read.frame.statics.as_bytes(),
+                written.frame.statics.as_bytes()
+            );
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn truncated_chunk_is_dropped() {
+        let path = temp_path("truncated");
+        let written = samples();
+        write_session(&path, &written);
+        let len = std::fs::metadata(&path).unwrap().len();
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_len(len - 10)
+            .unwrap();
+
+        let mut reader = SessionReader::open(&path).unwrap();
+        assert_eq!(reader.chunks.len(), 1);
+        let read = read_all(&mut reader);
+        assert_eq!(read.len(), 20);
+        assert_eq!(read.last().unwrap().frame.physics._packet_id, 19);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rejects_files_without_frames() {
+        let path = temp_path("empty");
+        SessionWriter::create(&path, 0).unwrap().finish().unwrap();
+        let e = SessionReader::open(&path).err().unwrap();
+        assert_eq!(e.kind(), ErrorKind::InvalidData);
+        std::fs::write(&path, b"not a session").unwrap();
+        assert!(SessionReader::open(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,11 @@
+mod format;
+pub use format::SessionReader;
+mod recorder;
+pub use recorder::Recorder;
+
+use std::path::PathBuf;
+
+/// Where sessions are written: `~/.local/share/simtem/sessions`.
+pub fn sessions_dir() -> PathBuf {
+    crate::paths::data_dir().join("sessions")
+}
@@ -0,0 +1,215 @@
+use super::format::SessionWriter;
+use crate::data::{AcStatus, parse_static_string};
+use crate::sampler::{Reader, Sample};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+enum Command {
+    Toggle,
+    /// Write what is left and finish the session, then end the thread.
+    Stop,
+}
+
+/// Streams every sample into a session file on its own thread.
+///
+/// Sessions start and stop on [`Recorder::toggle`], and start automatically
+/// when the car leaves the pits; those automatic sessions end when it comes
+/// back in. Dropping the recorder finishes the session in progress.
+pub struct Recorder {
+    commands: Sender<Command>,
+    recording: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Recorder {
+    pub fn spawn(mut reader: Reader<Sample>, dir: PathBuf) -> Self {
+        let (commands, rx) = mpsc::channel();
+        let recording = Arc::new(AtomicBool::new(false));
+
+        let mut state = State {
+            dir,
+            session: None,
+            latest: None,
+            was_in_pit: None,
+            recording: recording.clone(),
+        };
+        let thread = std::thread::Builder::new()
+            .name("recorder".to_string())
+            .spawn(move || {
+                loop {
+                    let stop = match rx.recv_timeout(POLL_INTERVAL) {
+                        Ok(Command::Toggle) => {
+                            state.toggle();
+                            false
+                        }
+                        Err(RecvTimeoutError::Timeout) => false,
+                        Ok(Command::Stop) | Err(RecvTimeoutError::Disconnected) => true,
+                    };
+                    reader.drain(|sample| state.on_sample(&sample));
+                    if stop {
+                        break;
+                    }
+                }
+                state.stop();
+            })
+            .expect("Failed to spawn recorder thread");
+
+        Self {
+            commands,
+            recording,
+            thread: Some(thread),
+        }
+    }
+
+    pub fn toggle(&self) {
+        let _ = self.commands.send(Command::Toggle);
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.load(Ordering::Acquire)
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        let _ = self.commands.send(Command::Stop);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+struct State {
+    dir: PathBuf,
+    session: Option<Session>,
+    latest: Option<Sample>,
+    was_in_pit: Option<bool>,
+    recording: Arc<AtomicBool>,
+}
+
+struct Session {
+    writer: SessionWriter,
+    path: PathBuf,
+    car_model: [u16; 33],
+    track: [u16; 33],
+    auto: bool,
+}
+
+impl State {
+    fn toggle(&mut self) {
+        if self.session.is_some() {
+            self.stop();
+        } else if let Some(sample) = self.latest {
+            self.start(&sample, false);
+        } else {
+            println!("RECORDER: no telemetry to record yet");
+        }
+    }
+
+    fn on_sample(&mut self, sample: &Sample) {
+        self.latest = Some(*sample);
+        let telemetry = sample.frame.telemetry(0.0);
+        let s = &sample.frame.statics;
+        let in_pit = telemetry.in_pit;
+
+        if let Some(session) = &self.session
+            && (session.car_model != s.car_model || session.track != s._track)
+        {
+            self.stop();
+        }
+
+        match (self.was_in_pit, in_pit) {
+            (Some(true), false)
+                if self.session.is_none() && telemetry.status == Some(AcStatus::Live) =>
+            {
+                self.start(sample, true);
+            }
+            (Some(false), true) if self.session.as_ref().is_some_and(|s| s.auto) => {
+                self.stop();
+            }
+            _ => {}
+        }
+        self.was_in_pit = Some(in_pit);
+
+        if let Some(session) = &mut self.session
+            && let Err(e) = session.writer.write(sample)
+        {
+            println!(
+                "RECORDER: write to {} failed: {}",
+                session.path.display(),
+                e
+            );
+            self.session = None;
+            self.recording.store(false, Ordering::Release);
+        }
+    }
+
+    fn start(&mut self, sample: &Sample, auto: bool) {
+        let s = &sample.frame.statics;
+        let path = session_path(
+            &self.dir,
+            &parse_static_string(&s.car_model),
+            &parse_static_string(&s._track),
+        );
+        let writer = std::fs::create_dir_all(&self.dir)
+            .and_then(|_| SessionWriter::create(&path, chrono::Utc::now().timestamp_millis()));
+        match writer {
+            Ok(writer) => {
+                println!("RECORDER: started {}", path.display());
+                self.session = Some(Session {
+                    writer,
+                    path,
+                    car_model: s.car_model,
+                    track: s._track,
+                    auto,
+                });
+                self.recording.store(true, Ordering::Release);
+            }
+            Err(e) => println!("RECORDER: cannot create {}: {}", path.display(), e),
+        }
+    }
+
+    fn stop(&mut self) {
+        let Some(session) = self.session.take() else {
+            return;
+        };
+        self.recording.store(false, Ordering::Release);
+        match session.writer.finish() {
+            Ok(()) => println!("RECORDER: saved {}", session.path.display()),
+            Err(e) => println!(
+                "RECORDER: finishing {} failed: {}",
+                session.path.display(),
+                e
+            ),
+        }
+    }
+}
+
+/// `<car>_<track>_<local date and time>.stm`
+fn session_path(dir: &Path, car: &str, track: &str) -> PathBuf {
+    let clean = |name: &str| -> String {
+        let name: String = name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        if name.is_empty() {
+            "unknown".to_string()
+        } else {
+            name
+        }
+    };
+    let date = chrono::Local::now().format("%Y-%m-%d_%H-%M-%S");
+    dir.join(format!("{}_{}_{}.stm", clean(car), clean(track), date))
+}
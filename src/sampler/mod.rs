@@ -0,0 +1,152 @@
+use crate::data::Standings;
+use crate::source::{Frame, PacketStats, TelemetrySource};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+mod ring;
+pub use ring::{Reader, Ring};
+
+pub const DEFAULT_RATE_HZ: f64 = 333.0;
+const RING_SECONDS: f64 = 6.0;
+
+#[derive(Copy, Clone)]
+pub struct Sample {
+    /// Seconds since the sampler started.
+    pub t: f64,
+    pub frame: Frame,
+}
+
+/// Polls a [`TelemetrySource`] at a fixed rate on its own thread, independent
+/// of the UI refresh rate, and publishes every read into a [`Ring`].
+/// Dropping the sampler stops the thread.
+pub struct Sampler {
+    ring: Arc<Ring<Sample>>,
+    shared: Arc<Shared>,
+    start: Instant,
+    thread: Option<JoinHandle<()>>,
+}
+
+struct Shared {
+    stop: AtomicBool,
+    connected: AtomicBool,
+    status: Mutex<(PacketStats, Option<String>)>,
+    standings: Mutex<Option<Standings>>,
+}
+
+impl Sampler {
+    pub fn spawn(mut source: Box<dyn TelemetrySource>, rate_hz: f64) -> Self {
+        let capacity = (rate_hz * RING_SECONDS).ceil() as usize;
+        let (mut writer, ring) = Ring::new(capacity);
+        let shared = Arc::new(Shared {
+            stop: AtomicBool::new(false),
+            connected: AtomicBool::new(false),
+            status: Mutex::new((PacketStats::default(), None)),
+            standings: Mutex::new(None),
+        });
+        let start = Instant::now();
+        let period = Duration::from_secs_f64(1.0 / rate_hz);
+
+        let thread_shared = shared.clone();
+        let thread = std::thread::Builder::new()
+            .name("sampler".to_string())
+            .spawn(move || {
+                let mut next = Instant::now();
+                while !thread_shared.stop.load(Ordering::Acquire) {
+                    let frame = source.read();
+                    if let Some(frame) = frame {
+                        let t = start.elapsed().as_secs_f64();
+                        writer.push(Sample { t, frame });
+                    }
+                    thread_shared
+                        .connected
+                        .store(frame.is_some(), Ordering::Release);
+                    *thread_shared.status.lock().unwrap() =
+                        (source.stats(), source.error().map(str::to_string));
+                    if let Some(standings) = source.standings() {
+                        *thread_shared.standings.lock().unwrap() = Some(standings);
+                    }
+
+                    next += period;
+                    let now = Instant::now();
+                    if next > now {
+                        std::thread::sleep(next - now);
+                    } else {
+                        // Fell behind (suspend, heavy load): don't try to catch up
+                        next = now;
+                    }
+                }
+            })
+            .expect("Failed to spawn sampler thread");
+
+        Self {
+            ring,
+            shared,
+            start,
+            thread: Some(thread),
+        }
+    }
+
+    /// Ends the polling thread after its current read; the ring and the last
+    /// status stay available.
+    pub fn stop(&mut self) {
+        self.shared.stop.store(true, Ordering::Release);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+
+    pub fn reader(&self) -> Reader<Sample> {
+        self.ring.reader()
+    }
+
+    /// Sampler clock, comparable with [`Sample::t`].
+    pub fn now(&self) -> f64 {
+        self.start.elapsed().as_secs_f64()
+    }
+
+    pub fn connected(&self) -> bool {
+        self.shared.connected.load(Ordering::Acquire)
+    }
+
+    pub fn stats(&self) -> PacketStats {
+        self.shared.status.lock().unwrap().0
+    }
+
+    pub fn error(&self) -> Option<String> {
+        self.shared.status.lock().unwrap().1.clone()
+    }
+
+    /// Latest standings published by the source, if it has any.
+    pub fn standings(&self) -> Option<Standings> {
+        self.shared.standings.lock().unwrap().clone()
+    }
+}
+
+impl Drop for Sampler {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::SyntheticSource;
+
+    #[test]
+    fn stop_ends_the_polling_thread() {
+        let mut sampler = Sampler::spawn(Box::new(SyntheticSource::new()), 1000.0);
+        let mut reader = sampler.reader();
+        std::thread::sleep(Duration::from_millis(50));
+        sampler.stop();
+        assert!(sampler.thread.is_none());
+
+        let mut polled = 0;
+        reader.drain(|_| polled += 1);
+        assert!(polled > 0);
+        std::thread::sleep(Duration::from_millis(20));
+        reader.drain(|_| panic!("sample after stop"));
+    }
+}
@@ -0,0 +1,194 @@
+use std::cell::UnsafeCell;
+use std::mem::MaybeUninit;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering, fence};
+
+/// Fixed-size broadcast ring: one writer, any number of independent readers.
+///
+/// Each slot is a seqlock, so the writer never waits on a reader; a reader
+/// that falls more than `capacity` behind simply loses the oldest items.
+pub struct Ring<T> {
+    slots: Box<[Slot<T>]>,
+    head: AtomicU64,
+}
+
+struct Slot<T> {
+    // 2n + 1 while item n is being written, 2n + 2 once it is complete
+    seq: AtomicU64,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+unsafe impl<T: Copy + Send> Sync for Ring<T> {}
+unsafe impl<T: Copy + Send> Send for Ring<T> {}
+
+impl<T: Copy + Send> Ring<T> {
+    pub fn new(capacity: usize) -> (Writer<T>, Arc<Self>) {
+        let slots = (0..capacity.max(1))
+            .map(|_| Slot {
+                seq: AtomicU64::new(0),
+                value: UnsafeCell::new(MaybeUninit::uninit()),
+            })
+            .collect();
+        let ring = Arc::new(Self {
+            slots,
+            head: AtomicU64::new(0),
+        });
+        (Writer { ring: ring.clone() }, ring)
+    }
+
+    /// Reader starting at the next item to be written.
+    pub fn reader(self: &Arc<Self>) -> Reader<T> {
+        Reader {
+            ring: self.clone(),
+            next: self.head.load(Ordering::Acquire),
+            missed: 0,
+        }
+    }
+
+    fn slot(&self, n: u64) -> &Slot<T> {
+        &self.slots[(n % self.slots.len() as u64) as usize]
+    }
+
+    fn get(&self, n: u64) -> Option<T> {
+        let slot = self.slot(n);
+        let seq = slot.seq.load(Ordering::Acquire);
+        if seq != 2 * n + 2 {
+            return None;
+        }
+        let value = unsafe { std::ptr::read_volatile(slot.value.get()) };
+        fence(Ordering::Acquire);
+        if slot.seq.load(Ordering::Relaxed) != seq {
+            return None;
+        }
+        Some(unsafe { value.assume_init() })
+    }
+}
+
+/// The single producer side of a [`Ring`].
+pub struct Writer<T> {
+    ring: Arc<Ring<T>>,
+}
+
+impl<T: Copy + Send> Writer<T> {
+    pub fn push(&mut self, value: T) {
+        let ring = &self.ring;
+        let n = ring.head.load(Ordering::Relaxed);
+        let slot = ring.slot(n);
+        slot.seq.store(2 * n + 1, Ordering::Relaxed);
+        fence(Ordering::Release);
+        unsafe { std::ptr::write_volatile(slot.value.get(), MaybeUninit::new(value)) };
+        slot.seq.store(2 * n + 2, Ordering::Release);
+        ring.head.store(n + 1, Ordering::Release);
+    }
+}
+
+pub struct Reader<T> {
+    ring: Arc<Ring<T>>,
+    next: u64,
+    /// Items overwritten before this reader got to them.
+    pub missed: u64,
+}
+
+impl<T: Copy + Send> Reader<T> {
+    /// Hands every item written since the last call to `f`, oldest first.
+    pub fn drain(&mut self, mut f: impl FnMut(T)) {
+        let head = self.ring.head.load(Ordering::Acquire);
+        let oldest = head.saturating_sub(self.ring.slots.len() as u64);
+        if self.next < oldest {
+            self.missed += oldest - self.next;
+            self.next = oldest;
+        }
+        while self.next < head {
+            match self.ring.get(self.next) {
+                Some(value) => f(value),
+                None => self.missed += 1,
+            }
+            self.next += 1;
+        }
+    }
+
+    /// Most recent item, without advancing the reader.
+    pub fn latest(&self) -> Option<T> {
+        let head = self.ring.head.load(Ordering::Acquire);
+        head.checked_sub(1).and_then(|n| self.ring.get(n))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drained(reader: &mut Reader<u64>) -> Vec<u64> {
+        let mut items = Vec::new();
+        reader.drain(|v| items.push(v));
+        items
+    }
+
+    #[test]
+    fn wraps_around_the_slots() {
+        let (mut writer, ring) = Ring::new(4);
+        let mut reader = ring.reader();
+        for round in 0..5 {
+            for i in 0..3 {
+                writer.push(round * 3 + i);
+            }
+            let expected: Vec<_> = (round * 3..round * 3 + 3).collect();
+            assert_eq!(drained(&mut reader), expected);
+        }
+        assert_eq!(reader.missed, 0);
+        assert_eq!(reader.latest(), Some(14));
+    }
+
+    #[test]
+    fn reader_falling_behind_counts_the_missed_items() {
+        let (mut writer, ring) = Ring::new(4);
+        let mut reader = ring.reader();
+        writer.push(0);
+        let mut late = ring.reader();
+        for i in 1..10 {
+            writer.push(i);
+        }
+        assert_eq!(drained(&mut reader), [6, 7, 8, 9]);
+        assert_eq!(reader.missed, 6);
+        assert_eq!(drained(&mut late), [6, 7, 8, 9]);
+        assert_eq!(late.missed, 5);
+        assert!(drained(&mut reader).is_empty());
+    }
+
+    #[test]
+    fn empty_ring_has_no_latest() {
+        let (mut writer, ring) = Ring::<u64>::new(0);
+        let mut reader = ring.reader();
+        assert_eq!(reader.latest(), None);
+        assert!(drained(&mut reader).is_empty());
+        writer.push(7);
+        assert_eq!(reader.latest(), Some(7));
+    }
+
+    #[test]
+    fn concurrent_reader_never_sees_torn_items() {
+        const ITEMS: u64 = 200_000;
+        let (mut writer, ring) = Ring::<[u64; 16]>::new(64);
+        let mut reader = ring.reader();
+        let thread = std::thread::spawn(move || {
+            for i in 0..ITEMS {
+                writer.push([i; 16]);
+            }
+        });
+
+        let (mut seen, mut last) = (0, None);
+        let mut check = |item: [u64; 16]| {
+            assert!(item.iter().all(|&v| v == item[0]), "torn item {:?}", item);
+            assert!(last < Some(item[0]), "{:?} after {:?}", item[0], last);
+            last = Some(item[0]);
+            seen += 1;
+        };
+        while !thread.is_finished() {
+            reader.drain(&mut check);
+        }
+        thread.join().unwrap();
+        reader.drain(&mut check);
+        assert_eq!(last, Some(ITEMS - 1));
+        assert_eq!(seen + reader.missed, ITEMS);
+    }
+}
@@ -0,0 +1,297 @@
+use super::BroadcastClient;
+use super::shm::{PacketTracker, ShmError, ShmFiles, check_len, read_consistent};
+use super::{Frame, PacketStats, TelemetrySource};
+use crate::data::{Graphics, Physics, Standings, Statics, ac1, parse_static_string};
+use memmap2::Mmap;
+use std::path::PathBuf;
+
+pub const SHM_DIR: &str = "/dev/shm";
+
+const FILE_NAMES: [&str; 3] = ["acpmf_physics", "acpmf_graphics", "acpmf_static"];
+/// `_sm_version` values whose layout matches the ACC structs in `src/data`.
+const SUPPORTED_VERSIONS: [&str; 2] = ["1.8", "1.9"];
+
+/// Which sim wrote the pages. AC and ACC share the file names but not the
+/// layout; AC pages are converted to the ACC layout on read.
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum Layout {
+    Acc,
+    Ac1,
+}
+
+/// ACC (or original Assetto Corsa) shared memory as exported by the Datalink
+/// bridge.
+///
+/// The sim is told apart by `_sm_version`, or by the mapped sizes while that
+/// is empty.
+pub struct AccShm {
+    files: ShmFiles,
+    /// Set once the current mapping passed the size checks: whether it is
+    /// large enough for the ACC layout (AC's pages are shorter).
+    fits_acc: Option<bool>,
+    tracker: PacketTracker,
+    last_graphics: Option<Graphics>,
+    checked_version: Option<[u16; 15]>,
+    layout: Option<Layout>,
+    error: Option<String>,
+    /// Standings from the broadcasting API, when enabled.
+    broadcast: Option<BroadcastClient>,
+}
+
+impl AccShm {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            files: ShmFiles::new(dir, &FILE_NAMES),
+            fits_acc: None,
+            tracker: PacketTracker::new(),
+            last_graphics: None,
+            checked_version: None,
+            layout: None,
+            error: None,
+            broadcast: None,
+        }
+    }
+
+    /// Also reads the standings through ACC's broadcasting API.
+    pub fn with_broadcast(mut self, client: BroadcastClient) -> Self {
+        self.broadcast = Some(client);
+        self
+    }
+
+    fn connect(&mut self) {
+        match self.files.poll() {
+            Ok(true) => {}
+            Ok(false) => return,
+            Err(e) => return self.set_error(e.into()),
+        }
+        println!("ACC SHM mapped from {}", self.files.dir().display());
+        self.tracker = PacketTracker::new();
+        self.last_graphics = None;
+        self.checked_version = None;
+        self.layout = None;
+        self.error = None;
+        self.fits_acc = match self.files.maps().map(check_sizes) {
+            Some(Ok(fits_acc)) => Some(fits_acc),
+            Some(Err(e)) => {
+                self.set_error(e);
+                None
+            }
+            None => None,
+        };
+    }
+
+    fn set_error(&mut self, e: ShmError) {
+        let msg = e.to_string();
+        if self.error.as_ref() != Some(&msg) {
+            println!("ACC SHM not usable: {}", msg);
+        }
+        self.error = Some(msg);
+    }
+
+    /// Works out the layout from `_sm_version` whenever it changes. An empty
+    /// version means the sim has not filled the page yet; the mapped size
+    /// decides until it does.
+    fn check_version(&mut self, raw: [u16; 15], fits_acc: bool) -> Option<Layout> {
+        if self.checked_version == Some(raw) {
+            return self.layout;
+        }
+        self.checked_version = Some(raw);
+        let layout = self.detect_layout(&parse_static_string(&raw), fits_acc);
+        if layout.is_some() && layout != self.layout {
+            let sim = match layout {
+                Some(Layout::Ac1) => "Assetto Corsa",
+                _ => "ACC",
+            };
+            println!("SHM layout: {}", sim);
+        }
+        self.layout = layout;
+        layout
+    }
+
+    fn detect_layout(&mut self, version: &str, fits_acc: bool) -> Option<Layout> {
+        let fallback = if fits_acc { Layout::Acc } else { Layout::Ac1 };
+        if version.is_empty() {
+            self.error = None;
+            return Some(fallback);
+        }
+        if ac1::SM_VERSIONS.contains(&version) {
+            self.error = None;
+            return Some(Layout::Ac1);
+        }
+        if !fits_acc {
+            self.set_error(ShmError::Version(version.to_string()));
+            return None;
+        }
+        if SUPPORTED_VERSIONS.contains(&version) {
+            self.error = None;
+            return Some(Layout::Acc);
+        }
+
+        // Newer ACC releases only append fields, so a later 1.x is read with
+        // the known prefix of the layout.
+        let newer_minor = version
+            .strip_prefix("1.")
+            .and_then(|minor| minor.parse::<u32>().ok())
+            .is_some_and(|minor| minor > 9);
+        if newer_minor {
+            println!("ACC SHM version {} is newer than known layouts", version);
+            self.error = None;
+            return Some(Layout::Acc);
+        }
+
+        self.set_error(ShmError::Version(version.to_string()));
+        None
+    }
+}
+
+/// Every file must hold at least the smaller AC layout; returns whether
+/// they are large enough for ACC's.
+fn check_sizes(maps: &[Mmap]) -> Result<bool, ShmError> {
+    check_len::<ac1::Physics>(FILE_NAMES[0], &maps[0])?;
+    check_len::<ac1::Graphics>(FILE_NAMES[1], &maps[1])?;
+    check_len::<ac1::Statics>(FILE_NAMES[2], &maps[2])?;
+    Ok(check_len::<Physics>(FILE_NAMES[0], &maps[0]).is_ok()
+        && check_len::<Graphics>(FILE_NAMES[1], &maps[1]).is_ok()
+        && check_len::<Statics>(FILE_NAMES[2], &maps[2]).is_ok())
+}
+
+/// The version string both layouts start their static page with.
+fn sm_version(statics: &Mmap) -> [u16; 15] {
+    unsafe { std::ptr::read_volatile(statics.as_ptr() as *const [u16; 15]) }
+}
+
+/// Reads one page. Only called with types the mapping was checked to fit.
+fn page<T: Copy>(mmap: &Mmap) -> (T, bool) {
+    debug_assert!(mmap.len() >= size_of::<T>());
+    unsafe { read_consistent(mmap.as_ptr() as *const T) }
+}
+
+fn statics<T: Copy>(mmap: &Mmap) -> T {
+    debug_assert!(mmap.len() >= size_of::<T>());
+    unsafe { std::ptr::read_volatile(mmap.as_ptr() as *const T) }
+}
+
+/// All three pages in the ACC layout, plus whether physics and graphics
+/// were copied consistently.
+fn read_pages(maps: &[Mmap], layout: Layout) -> (Physics, (Graphics, bool), Statics, bool) {
+    match layout {
+        Layout::Acc => {
+            let (physics, consistent) = page::<Physics>(&maps[0]);
+            let graphics = page::<Graphics>(&maps[1]);
+            (physics, graphics, statics::<Statics>(&maps[2]), consistent)
+        }
+        Layout::Ac1 => {
+            let statics = statics::<ac1::Statics>(&maps[2]);
+            let (physics, consistent) = page::<ac1::Physics>(&maps[0]);
+            let (graphics, graphics_ok) = page::<ac1::Graphics>(&maps[1]);
+            (
+                physics.to_acc(&statics),
+                (graphics.to_acc(), graphics_ok),
+                statics.to_acc(),
+                consistent,
+            )
+        }
+    }
+}
+
+impl TelemetrySource for AccShm {
+    fn read(&mut self) -> Option<Frame> {
+        self.connect();
+
+        let fits_acc = self.fits_acc?;
+        let version = sm_version(&self.files.maps()?[2]);
+        let layout = self.check_version(version, fits_acc)?;
+
+        let (physics, graphics, statics, consistent) = read_pages(self.files.maps()?, layout);
+        self.tracker.observe(physics._packet_id, consistent);
+
+        // Graphics only updates at ~60 Hz, so a torn copy is replaced by the
+        // last good one instead of mixing two updates.
+        let graphics = match graphics {
+            (g, true) => {
+                self.last_graphics = Some(g);
+                g
+            }
+            (g, false) => self.last_graphics.unwrap_or(g),
+        };
+
+        Some(Frame {
+            physics,
+            graphics,
+            statics,
+        })
+    }
+
+    fn stats(&self) -> PacketStats {
+        self.tracker.stats()
+    }
+
+    fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    fn standings(&mut self) -> Option<Standings> {
+        let broadcast = self.broadcast.as_mut()?;
+        broadcast.poll();
+        broadcast.standings()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::ShmPage;
+
+    #[test]
+    fn detects_the_layout_from_the_version() {
+        let mut shm = AccShm::new("/nonexistent");
+        let cases = [
+            ("", true, Some(Layout::Acc)),
+            ("", false, Some(Layout::Ac1)),
+            ("1.5", true, Some(Layout::Ac1)),
+            ("1.6", false, Some(Layout::Ac1)),
+            ("1.7", true, Some(Layout::Ac1)),
+            ("1.8", true, Some(Layout::Acc)),
+            ("1.9", true, Some(Layout::Acc)),
+            ("1.12", true, Some(Layout::Acc)),
+            ("1.9", false, None),
+            ("2.0", true, None),
+            ("1.x", true, None),
+        ];
+        for (version, fits_acc, layout) in cases {
+            assert_eq!(
+                shm.detect_layout(version, fits_acc),
+                layout,
+                "{:?} fits_acc {}",
+                version,
+                fits_acc
+            );
+            assert_eq!(shm.error.is_some(), layout.is_none(), "{:?}", version);
+        }
+    }
+
+    #[test]
+    fn reads_ac_pages_by_their_size() {
+        let mut physics = ac1::Physics::zeroed();
+        physics._packet_id = 3;
+        physics.rpms = 5000;
+        let mut statics = ac1::Statics::zeroed();
+        statics._max_rpm = 7000;
+
+        let dir = std::env::temp_dir().join(format!("simtem-test-ac1-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("acpmf_physics"), physics.as_bytes()).unwrap();
+        let graphics = ac1::Graphics::zeroed();
+        std::fs::write(dir.join("acpmf_graphics"), graphics.as_bytes()).unwrap();
+        std::fs::write(dir.join("acpmf_static"), statics.as_bytes()).unwrap();
+        let mut shm = AccShm::new(&dir);
+        let read = shm.read();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let read = read.expect("no frame from the AC pages");
+        assert_eq!(shm.layout, Some(Layout::Ac1));
+        assert_eq!(read.physics.rpms, 5000);
+        assert_eq!(read.physics.current_max_rpm, 7000);
+        assert_eq!(read.physics.ignition_on, 1);
+    }
+}
@@ -0,0 +1,189 @@
+use crate::data::Standings;
+use crate::data::broadcast::{BroadcastSession, Message, Request};
+use std::io;
+use std::net::{SocketAddr, UdpSocket};
+use std::time::{Duration, Instant};
+
+const DISPLAY_NAME: &str = "SimTem";
+/// Fast enough for a relative, slow enough to keep the game's load down.
+const UPDATE_INTERVAL_MS: i32 = 250;
+/// Registration is retried this often while the game does not answer.
+const RETRY_INTERVAL: Duration = Duration::from_secs(2);
+/// The game sends realtime updates several times a second while registered;
+/// silence this long means it quit or restarted.
+const TIMEOUT: Duration = Duration::from_secs(5);
+/// Lower bound between two entry list requests.
+const ENTRY_LIST_INTERVAL: Duration = Duration::from_secs(1);
+/// Larger than an entry list car with a full line-up of drivers.
+const MAX_MESSAGE: usize = 4096;
+
+/// Client of ACC's broadcasting API, for the data the shared memory lacks:
+/// names, cars and lap times of every car in the session.
+///
+/// Registers with the game, re-registers when it goes quiet, and keeps a
+/// [`BroadcastSession`] from the messages it receives. Non-blocking: `poll`
+/// only handles what already arrived.
+pub struct BroadcastClient {
+    server: SocketAddr,
+    connection_password: String,
+    socket: Option<UdpSocket>,
+    session: BroadcastSession,
+    last_register: Option<Instant>,
+    last_message: Option<Instant>,
+    last_entry_request: Option<Instant>,
+    /// Car updates arrived since the last standings.
+    changed: bool,
+    error: Option<String>,
+    buf: Box<[u8; MAX_MESSAGE]>,
+}
+
+impl BroadcastClient {
+    pub fn new(server: SocketAddr, connection_password: &str) -> Self {
+        Self {
+            server,
+            connection_password: connection_password.to_string(),
+            socket: None,
+            session: BroadcastSession::default(),
+            last_register: None,
+            last_message: None,
+            last_entry_request: None,
+            changed: false,
+            error: None,
+            buf: Box::new([0; MAX_MESSAGE]),
+        }
+    }
+
+    pub fn session(&self) -> &BroadcastSession {
+        &self.session
+    }
+
+    /// Why the game refused the registration, or the socket failed.
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Registers if needed and handles every message received since the
+    /// last call.
+    pub fn poll(&mut self) {
+        if let Err(e) = self.try_poll() {
+            self.set_error(format!("{}: {}", self.server, e));
+        }
+    }
+
+    /// The standings, when cars were updated since the last call.
+    pub fn standings(&mut self) -> Option<Standings> {
+        if !std::mem::take(&mut self.changed) {
+            return None;
+        }
+        Some(self.session.standings())
+    }
+
+    fn try_poll(&mut self) -> io::Result<()> {
+        if self.socket.is_none() {
+            let socket = UdpSocket::bind(("0.0.0.0", 0))?;
+            socket.connect(self.server)?;
+            socket.set_nonblocking(true)?;
+            self.socket = Some(socket);
+        }
+
+        if self.session.connection_id.is_some()
+            && self.last_message.is_some_and(|t| t.elapsed() > TIMEOUT)
+        {
+            println!(
+                "ACC broadcasting: no updates from {}, registering again",
+                self.server
+            );
+            self.session = BroadcastSession::default();
+            self.last_register = None;
+        }
+        if self.session.connection_id.is_none()
+            && self
+                .last_register
+                .is_none_or(|t| t.elapsed() >= RETRY_INTERVAL)
+        {
+            self.last_register = Some(Instant::now());
+            self.send(&Request::Register {
+                display_name: DISPLAY_NAME.to_string(),
+                connection_password: self.connection_password.clone(),
+                update_interval_ms: UPDATE_INTERVAL_MS,
+                command_password: String::new(),
+            })?;
+        }
+
+        loop {
+            let Some(socket) = &self.socket else {
+                return Ok(());
+            };
+            let len = match socket.recv(&mut self.buf[..]) {
+                Ok(len) => len,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                // Nothing listens on the port yet: the game is not running
+                Err(e) if e.kind() == io::ErrorKind::ConnectionRefused => break,
+                Err(e) => return Err(e),
+            };
+            if let Some(message) = Message::parse(&self.buf[..len]) {
+                self.last_message = Some(Instant::now());
+                self.handle(message)?;
+            }
+        }
+
+        if let Some(connection_id) = self.session.connection_id
+            && self.session.missing_entries()
+            && self
+                .last_entry_request
+                .is_none_or(|t| t.elapsed() >= ENTRY_LIST_INTERVAL)
+        {
+            self.last_entry_request = Some(Instant::now());
+            self.send(&Request::EntryList { connection_id })?;
+        }
+        Ok(())
+    }
+
+    fn handle(&mut self, message: Message) -> io::Result<()> {
+        match &message {
+            Message::RegistrationResult(r) if r.success => {
+                println!("ACC broadcasting: registered with {}", self.server);
+                self.error = None;
+                self.last_entry_request = Some(Instant::now());
+                self.send(&Request::EntryList {
+                    connection_id: r.connection_id,
+                })?;
+                self.send(&Request::TrackData {
+                    connection_id: r.connection_id,
+                })?;
+            }
+            Message::RegistrationResult(r) => {
+                self.set_error(format!("registration refused: {}", r.error));
+            }
+            Message::RealtimeCarUpdate(_) | Message::EntryListCar(_) => self.changed = true,
+            _ => {}
+        }
+        self.session.apply(message);
+        Ok(())
+    }
+
+    fn send(&self, request: &Request) -> io::Result<()> {
+        match &self.socket {
+            Some(socket) => match socket.send(&request.encode()) {
+                Err(e) if e.kind() == io::ErrorKind::ConnectionRefused => Ok(()),
+                result => result.map(|_| ()),
+            },
+            None => Ok(()),
+        }
+    }
+
+    fn set_error(&mut self, msg: String) {
+        if self.error.as_ref() != Some(&msg) {
+            println!("ACC broadcasting not usable: {}", msg);
+        }
+        self.error = Some(msg);
+    }
+}
+
+impl Drop for BroadcastClient {
+    fn drop(&mut self) {
+        if let Some(connection_id) = self.session.connection_id {
+            let _ = self.send(&Request::Unregister { connection_id });
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use super::shm::PacketTracker;
+use super::udp::UdpListener;
+use super::{Frame, PacketStats, TelemetrySource};
+use crate::data::Standings;
+use crate::data::f1::{self, Session};
+use std::net::SocketAddr;
+
+/// Written to `_sm_version` of the frames, to tell recordings apart.
+const SIM_TAG: &str = "f1";
+
+/// UDP telemetry of the F1 games, F1 23 and F1 24 formats.
+///
+/// Packets are folded into a [`Session`]; a frame is produced from whatever
+/// arrived so far, once the player's car telemetry is known.
+pub struct F1Udp {
+    listener: UdpListener,
+    session: Session,
+    tracker: PacketTracker,
+    frame_id: u32,
+    /// Lap data or participants changed since the last standings.
+    standings_changed: bool,
+    error: Option<String>,
+}
+
+impl F1Udp {
+    pub fn new(addr: SocketAddr) -> Self {
+        Self {
+            listener: UdpListener::new(addr),
+            session: Session::default(),
+            tracker: PacketTracker::new(),
+            frame_id: 0,
+            standings_changed: false,
+            error: None,
+        }
+    }
+}
+
+impl TelemetrySource for F1Udp {
+    fn read(&mut self) -> Option<Frame> {
+        let Self {
+            listener,
+            session,
+            tracker,
+            frame_id,
+            standings_changed,
+            error,
+        } = self;
+        let result = listener.drain(|packet| {
+            let Some(header) = session.apply(packet) else {
+                return;
+            };
+            match header.packet_id {
+                f1::PACKET_CAR_TELEMETRY => {
+                    *frame_id = header.frame_identifier;
+                    tracker.observe(*frame_id as i32, true);
+                }
+                f1::PACKET_LAP_DATA | f1::PACKET_PARTICIPANTS => *standings_changed = true,
+                _ => {}
+            }
+        });
+        match result {
+            Ok(()) => *error = None,
+            Err(e) => {
+                let msg = format!("cannot listen on {}: {}", listener.addr(), e);
+                if error.as_ref() != Some(&msg) {
+                    println!("F1 UDP not usable: {}", msg);
+                }
+                *error = Some(msg);
+            }
+        }
+
+        if !listener.is_live() {
+            return None;
+        }
+        let telemetry = session.telemetry()?;
+        Some(Frame::from_telemetry(
+            &telemetry,
+            *frame_id as i32,
+            SIM_TAG,
+            &session.car_model(),
+            &session.track(),
+        ))
+    }
+
+    fn stats(&self) -> PacketStats {
+        self.tracker.stats()
+    }
+
+    fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    fn standings(&mut self) -> Option<Standings> {
+        if !std::mem::take(&mut self.standings_changed) {
+            return None;
+        }
+        self.session.standings()
+    }
+}
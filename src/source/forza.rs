@@ -0,0 +1,81 @@
+use super::shm::PacketTracker;
+use super::udp::UdpListener;
+use super::{Frame, PacketStats, TelemetrySource};
+use crate::data::TelemetryFrame;
+use crate::data::forza::Packet;
+use std::net::SocketAddr;
+
+/// Written to `_sm_version` of the frames, to tell recordings apart.
+const SIM_TAG: &str = "forza";
+
+/// Forza "Data Out" UDP telemetry, in any of the sled, dash, Horizon and
+/// Motorsport (2023) formats.
+pub struct ForzaUdp {
+    listener: UdpListener,
+    last: Option<Packet>,
+    /// Forza packets carry no sequence number, so they are simply counted.
+    packets: i32,
+    tracker: PacketTracker,
+    error: Option<String>,
+}
+
+impl ForzaUdp {
+    pub fn new(addr: SocketAddr) -> Self {
+        Self {
+            listener: UdpListener::new(addr),
+            last: None,
+            packets: 0,
+            tracker: PacketTracker::new(),
+            error: None,
+        }
+    }
+}
+
+impl TelemetrySource for ForzaUdp {
+    fn read(&mut self) -> Option<Frame> {
+        let Self {
+            listener,
+            last,
+            packets,
+            tracker,
+            error,
+        } = self;
+        let result = listener.drain(|bytes| {
+            if let Some(packet) = Packet::parse(bytes) {
+                *last = Some(packet);
+                *packets = packets.wrapping_add(1);
+                tracker.observe(*packets, true);
+            }
+        });
+        match result {
+            Ok(()) => *error = None,
+            Err(e) => {
+                let msg = format!("cannot listen on {}: {}", listener.addr(), e);
+                if error.as_ref() != Some(&msg) {
+                    println!("Forza UDP not usable: {}", msg);
+                }
+                *error = Some(msg);
+            }
+        }
+
+        if !listener.is_live() {
+            return None;
+        }
+        let packet = self.last.as_ref()?;
+        Some(Frame::from_telemetry(
+            &TelemetryFrame::from_forza(packet),
+            self.packets,
+            SIM_TAG,
+            &packet.car_model(),
+            "",
+        ))
+    }
+
+    fn stats(&self) -> PacketStats {
+        self.tracker.stats()
+    }
+
+    fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+}
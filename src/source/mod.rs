@@ -0,0 +1,88 @@
+use crate::data::{
+    Graphics, Physics, Snapshot, Standings, Statics, TelemetryFrame, write_static_string,
+};
+
+mod acc;
+pub use acc::{AccShm, SHM_DIR};
+mod broadcast;
+pub use broadcast::BroadcastClient;
+mod f1;
+pub use f1::F1Udp;
+mod forza;
+pub use forza::ForzaUdp;
+mod outgauge;
+pub use outgauge::OutGaugeUdp;
+mod pcars2;
+pub use pcars2::Pcars2Shm;
+mod replay;
+pub use replay::{ReplayControl, ReplaySource, ReplayState};
+mod rf2;
+pub use rf2::Rf2Shm;
+mod shm;
+pub use shm::PacketStats;
+mod synthetic;
+pub use synthetic::{SyntheticCar, SyntheticSource};
+mod udp;
+
+/// One consistent copy of everything the overlay reads per frame.
+#[derive(Copy, Clone)]
+pub struct Frame {
+    pub physics: Physics,
+    pub graphics: Graphics,
+    pub statics: Statics,
+}
+
+impl Frame {
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot::new(&self.physics, &self.graphics)
+    }
+
+    /// The frame in simulator independent form; see [`TelemetryFrame::from_acc`].
+    pub fn telemetry(&self, bb_offset: f32) -> TelemetryFrame {
+        TelemetryFrame::from_acc(&self.physics, &self.graphics, &self.statics, bb_offset)
+    }
+
+    /// Carries another sim's telemetry in the ACC layout, so recording,
+    /// replay and analysis work the same for every backend. `sim` ends up in
+    /// `_sm_version`.
+    pub fn from_telemetry(
+        telemetry: &TelemetryFrame,
+        packet_id: i32,
+        sim: &str,
+        car_model: &str,
+        track: &str,
+    ) -> Self {
+        let (physics, graphics, mut statics) = telemetry.to_acc(packet_id);
+        write_static_string(&mut statics._sm_version, sim);
+        write_static_string(&mut statics.car_model, car_model);
+        write_static_string(&mut statics._track, track);
+        Self {
+            physics,
+            graphics,
+            statics,
+        }
+    }
+}
+
+/// Anything that can feed the overlay: the live ACC bridge, a recorded
+/// session or a synthetic generator.
+pub trait TelemetrySource: Send {
+    /// Latest snapshot, or `None` while the backend has nothing to offer.
+    fn read(&mut self) -> Option<Frame>;
+
+    /// Packet sequencing of the highest-rate stream, where the backend has one.
+    fn stats(&self) -> PacketStats {
+        PacketStats::default()
+    }
+
+    /// Why the backend cannot deliver data, when it is more than "not running yet".
+    fn error(&self) -> Option<&str> {
+        None
+    }
+
+    /// Every car in the session, where the backend has them. Returns a new
+    /// snapshot at most once per update of the sim, `None` in between.
+    fn standings(&mut self) -> Option<Standings> {
+        None
+    }
+}
@@ -0,0 +1,110 @@
+use super::shm::PacketTracker;
+use super::udp::UdpListener;
+use super::{Frame, PacketStats, TelemetrySource};
+use crate::data::TelemetryFrame;
+use crate::data::outgauge::{OutGauge, OutSim, Packet};
+use std::net::SocketAddr;
+
+/// Written to `_sm_version` of the frames, to tell recordings apart.
+const SIM_TAG: &str = "outgauge";
+
+/// OutGauge with optional OutSim, from Live for Speed or BeamNG.drive.
+///
+/// Both packets are accepted on either socket, so the game may send them to
+/// one port. Frames need OutGauge; OutSim only adds acceleration and
+/// orientation.
+pub struct OutGaugeUdp {
+    listeners: Vec<UdpListener>,
+    gauge: Option<OutGauge>,
+    sim: Option<OutSim>,
+    /// Highest rpm seen in the current car, standing in for the rev limit.
+    peak_rpm: f32,
+    /// OutGauge packets carry no sequence number, so they are simply counted.
+    packets: i32,
+    tracker: PacketTracker,
+    error: Option<String>,
+}
+
+impl OutGaugeUdp {
+    pub fn new(outgauge: SocketAddr, outsim: SocketAddr) -> Self {
+        let mut listeners = vec![UdpListener::new(outgauge)];
+        if outsim != outgauge {
+            listeners.push(UdpListener::new(outsim));
+        }
+        Self {
+            listeners,
+            gauge: None,
+            sim: None,
+            peak_rpm: 0.0,
+            packets: 0,
+            tracker: PacketTracker::new(),
+            error: None,
+        }
+    }
+}
+
+impl TelemetrySource for OutGaugeUdp {
+    fn read(&mut self) -> Option<Frame> {
+        let Self {
+            listeners,
+            gauge,
+            sim,
+            peak_rpm,
+            packets,
+            tracker,
+            error,
+        } = self;
+        let mut errors = Vec::new();
+        for listener in listeners.iter_mut() {
+            let result = listener.drain(|bytes| match Packet::parse(bytes) {
+                Some(Packet::Gauge(g)) => {
+                    if gauge.is_none_or(|last| last.car != g.car) {
+                        *peak_rpm = 0.0;
+                    }
+                    *peak_rpm = peak_rpm.max(g.rpm);
+                    *gauge = Some(g);
+                    *packets = packets.wrapping_add(1);
+                    tracker.observe(*packets, true);
+                }
+                Some(Packet::Sim(s)) => *sim = Some(s),
+                None => {}
+            });
+            if let Err(e) = result {
+                errors.push(format!("cannot listen on {}: {}", listener.addr(), e));
+            }
+        }
+        if errors.is_empty() {
+            *error = None;
+        } else {
+            let msg = errors.join(", ");
+            if error.as_ref() != Some(&msg) {
+                println!("OutGauge UDP not usable: {}", msg);
+            }
+            *error = Some(msg);
+        }
+
+        if !self.listeners.iter().any(|l| l.is_live()) {
+            return None;
+        }
+        let gauge = self.gauge.as_ref()?;
+        let telemetry = TelemetryFrame {
+            max_rpm: self.peak_rpm,
+            ..TelemetryFrame::from_outgauge(gauge, self.sim.as_ref())
+        };
+        Some(Frame::from_telemetry(
+            &telemetry,
+            self.packets,
+            SIM_TAG,
+            &gauge.car_model(),
+            "",
+        ))
+    }
+
+    fn stats(&self) -> PacketStats {
+        self.tracker.stats()
+    }
+
+    fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+}
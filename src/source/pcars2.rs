@@ -0,0 +1,187 @@
+use super::shm::{PacketTracker, ShmError, ShmFiles, check_len, read_sequenced};
+use super::{Frame, PacketStats, TelemetrySource};
+use crate::data::pcars2::{self, Ams2Extension, Ams2SharedMemory, SharedMemory};
+use crate::data::{Standings, TelemetryFrame, car_model_from_name, parse_byte_string};
+use memmap2::Mmap;
+use std::mem::offset_of;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+const FILE_NAMES: [&str; 1] = [pcars2::FILE_NAME];
+/// Written to `_sm_version` of the frames, to tell recordings apart.
+const SIM_TAG: &str = "pcars2";
+/// Standings change a few times per second at most; no need to rebuild the
+/// participant list on every read.
+const STANDINGS_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Project CARS 2 / Automobilista 2 shared memory.
+///
+/// The whole block is copied on every read and checked against
+/// `mSequenceNumber`. AMS2's longer block is told apart by the mapped size.
+pub struct Pcars2Shm {
+    files: ShmFiles,
+    /// Set once the current mapping passed the size checks: whether it holds
+    /// AMS2's extension.
+    is_ams2: Option<bool>,
+    tracker: PacketTracker,
+    last_standings: Option<Instant>,
+    standings: Option<Standings>,
+    error: Option<String>,
+}
+
+impl Pcars2Shm {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            files: ShmFiles::new(dir, &FILE_NAMES),
+            is_ams2: None,
+            tracker: PacketTracker::new(),
+            last_standings: None,
+            standings: None,
+            error: None,
+        }
+    }
+
+    fn connect(&mut self) {
+        match self.files.poll() {
+            Ok(true) => {}
+            Ok(false) => return,
+            Err(e) => return self.set_error(e.into()),
+        }
+        println!("PCARS2 SHM mapped from {}", self.files.dir().display());
+        self.tracker = PacketTracker::new();
+        self.last_standings = None;
+        self.error = None;
+        self.is_ams2 = match self.files.maps().map(check_sizes) {
+            Some(Ok(is_ams2)) => Some(is_ams2),
+            Some(Err(e)) => {
+                self.set_error(e);
+                None
+            }
+            None => None,
+        };
+    }
+
+    fn set_error(&mut self, e: ShmError) {
+        let msg = e.to_string();
+        if self.error.as_ref() != Some(&msg) {
+            println!("PCARS2 SHM not usable: {}", msg);
+        }
+        self.error = Some(msg);
+    }
+}
+
+/// The mapping must hold the PCARS2 block; returns whether it also holds
+/// AMS2's extension.
+fn check_sizes(maps: &[Mmap]) -> Result<bool, ShmError> {
+    check_len::<SharedMemory>(FILE_NAMES[0], &maps[0])?;
+    Ok(check_len::<Ams2SharedMemory>(FILE_NAMES[0], &maps[0]).is_ok())
+}
+
+/// The block and AMS2's extension where the mapping has one, plus the
+/// sequence number and whether the copy is consistent.
+fn read_block(mmap: &Mmap, is_ams2: bool) -> (SharedMemory, Option<Ams2Extension>, u32, bool) {
+    let seq_offset = offset_of!(SharedMemory, sequence_number);
+    if is_ams2 {
+        let (block, seq, consistent) =
+            unsafe { read_sequenced(mmap.as_ptr() as *const Ams2SharedMemory, seq_offset) };
+        (block.base, Some(block.ext), seq, consistent)
+    } else {
+        let (block, seq, consistent) =
+            unsafe { read_sequenced(mmap.as_ptr() as *const SharedMemory, seq_offset) };
+        (block, None, seq, consistent)
+    }
+}
+
+impl TelemetrySource for Pcars2Shm {
+    fn read(&mut self) -> Option<Frame> {
+        self.connect();
+        let is_ams2 = self.is_ams2?;
+        let (sm, ext, seq, consistent) = read_block(&self.files.maps()?[0], is_ams2);
+
+        // A zeroed block: the game has not written it yet
+        if sm.version == 0 {
+            return None;
+        }
+        if sm.version < pcars2::MIN_VERSION {
+            self.set_error(ShmError::Version(sm.version.to_string()));
+            return None;
+        }
+        // Two increments per update
+        self.tracker.observe((seq / 2) as i32, consistent);
+
+        if self
+            .last_standings
+            .is_none_or(|t| t.elapsed() >= STANDINGS_INTERVAL)
+        {
+            self.last_standings = Some(Instant::now());
+            self.standings = Some(sm.standings());
+        }
+
+        let telemetry = TelemetryFrame::from_pcars2(&sm, ext.as_ref());
+        let car_model = car_model_from_name(&parse_byte_string(&sm.car_name));
+        Some(Frame::from_telemetry(
+            &telemetry,
+            (seq / 2) as i32,
+            SIM_TAG,
+            &car_model,
+            &sm.track_name(),
+        ))
+    }
+
+    fn stats(&self) -> PacketStats {
+        self.tracker.stats()
+    }
+
+    fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    fn standings(&mut self) -> Option<Standings> {
+        self.standings.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::ShmPage;
+
+    /// Reads a PCARS2 block with the given sequence number from a file.
+    fn read_with_sequence(name: &str, sequence_number: u32) -> (Option<Frame>, PacketStats) {
+        let mut sm = SharedMemory::zeroed();
+        sm.version = pcars2::MIN_VERSION;
+        sm.game_state = 2;
+        sm.sequence_number = sequence_number;
+        sm.gear = 3;
+        sm.rpm = 6000.0;
+
+        let dir = std::env::temp_dir().join(format!(
+            "simtem-test-pcars2-{}-{}",
+            name,
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(pcars2::FILE_NAME), sm.as_bytes()).unwrap();
+        let mut shm = Pcars2Shm::new(&dir);
+        let frame = shm.read();
+        std::fs::remove_dir_all(&dir).unwrap();
+        (frame, shm.stats())
+    }
+
+    #[test]
+    fn packet_id_from_the_sequence_number() {
+        let (frame, stats) = read_with_sequence("even", 10);
+        let frame = frame.expect("no frame from the written block");
+        assert_eq!(frame.physics._packet_id, 5);
+        assert_eq!(frame.telemetry(0.0).gear, 3);
+        assert_eq!((stats.received, stats.torn), (1, 0));
+    }
+
+    #[test]
+    fn odd_sequence_number_is_a_torn_read() {
+        // Stuck mid-update: the copy is still used, but counted as torn
+        let (frame, stats) = read_with_sequence("odd", 11);
+        assert!(frame.is_some());
+        assert_eq!((stats.received, stats.torn), (1, 1));
+    }
+}
@@ -0,0 +1,197 @@
+use super::{Frame, TelemetrySource};
+use crate::recording::SessionReader;
+use crate::sampler::Sample;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+pub const MIN_SPEED: f64 = 0.25;
+pub const MAX_SPEED: f64 = 4.0;
+
+#[derive(Copy, Clone)]
+pub struct ReplayState {
+    pub playing: bool,
+    pub speed: f64,
+    /// Recorded time of the frame on screen.
+    pub position: f64,
+    pub start: f64,
+    pub end: f64,
+}
+
+/// Transport controls shared between the UI and the sampler thread that
+/// drives the [`ReplaySource`].
+pub struct ReplayControl {
+    state: Mutex<ControlState>,
+}
+
+struct ControlState {
+    replay: ReplayState,
+    seek: Option<f64>,
+    step: i32,
+}
+
+impl ReplayControl {
+    pub fn state(&self) -> ReplayState {
+        self.state.lock().unwrap().replay
+    }
+
+    pub fn toggle_play(&self) {
+        let mut s = self.state.lock().unwrap();
+        if !s.replay.playing && s.replay.position >= s.replay.end {
+            s.seek = Some(s.replay.start);
+        }
+        s.replay.playing = !s.replay.playing;
+    }
+
+    pub fn seek_to(&self, t: f64) {
+        let mut s = self.state.lock().unwrap();
+        s.seek = Some(t.clamp(s.replay.start, s.replay.end));
+    }
+
+    pub fn seek_by(&self, dt: f64) {
+        let position = self.state().position;
+        self.seek_to(position + dt);
+    }
+
+    pub fn set_speed(&self, speed: f64) {
+        self.state.lock().unwrap().replay.speed = speed.clamp(MIN_SPEED, MAX_SPEED);
+    }
+
+    /// Pauses and moves by `frames` recorded frames.
+    pub fn step(&self, frames: i32) {
+        let mut s = self.state.lock().unwrap();
+        s.replay.playing = false;
+        s.step += frames;
+    }
+}
+
+/// Plays a recorded session back in real time (scaled by the replay speed).
+pub struct ReplaySource {
+    reader: SessionReader,
+    control: Arc<ReplayControl>,
+    chunk: usize,
+    samples: Vec<Sample>,
+    index: usize,
+    last_read: Instant,
+    error: Option<String>,
+}
+
+impl ReplaySource {
+    pub fn open(path: &Path) -> std::io::Result<Self> {
+        let mut reader = SessionReader::open(path)?;
+        let samples = reader.decode_chunk(0)?;
+        let start = reader.chunks[0].first_t;
+        let end = reader.chunks[reader.chunks.len() - 1].last_t;
+        if let Some(date) = chrono::DateTime::from_timestamp_millis(reader.start_unix_ms) {
+            println!(
+                "Replaying {} recorded {} ({:.0} s)",
+                path.display(),
+                date.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M"),
+                end - start
+            );
+        }
+        let control = Arc::new(ReplayControl {
+            state: Mutex::new(ControlState {
+                replay: ReplayState {
+                    playing: true,
+                    speed: 1.0,
+                    position: start,
+                    start,
+                    end,
+                },
+                seek: None,
+                step: 0,
+            }),
+        });
+
+        Ok(Self {
+            reader,
+            control,
+            chunk: 0,
+            samples,
+            index: 0,
+            last_read: Instant::now(),
+            error: None,
+        })
+    }
+
+    pub fn control(&self) -> Arc<ReplayControl> {
+        self.control.clone()
+    }
+
+    fn load(&mut self, chunk: usize) -> std::io::Result<()> {
+        if chunk != self.chunk || self.samples.is_empty() {
+            self.samples = self.reader.decode_chunk(chunk)?;
+            self.chunk = chunk;
+        }
+        Ok(())
+    }
+
+    /// Moves to the last frame recorded at or before `t`.
+    fn locate(&mut self, t: f64) -> std::io::Result<()> {
+        let chunk = self
+            .reader
+            .chunks
+            .partition_point(|c| c.first_t <= t)
+            .saturating_sub(1);
+        self.load(chunk)?;
+        self.index = self.samples.partition_point(|s| s.t <= t).saturating_sub(1);
+        Ok(())
+    }
+
+    fn step(&mut self, frames: i32) -> std::io::Result<()> {
+        for _ in 0..frames.unsigned_abs() {
+            if frames > 0 {
+                if self.index + 1 < self.samples.len() {
+                    self.index += 1;
+                } else if self.chunk + 1 < self.reader.chunks.len() {
+                    self.load(self.chunk + 1)?;
+                    self.index = 0;
+                }
+            } else if self.index > 0 {
+                self.index -= 1;
+            } else if self.chunk > 0 {
+                self.load(self.chunk - 1)?;
+                self.index = self.samples.len().saturating_sub(1);
+            }
+        }
+        Ok(())
+    }
+
+    fn advance(&mut self) -> std::io::Result<()> {
+        let dt = self.last_read.elapsed().as_secs_f64();
+        self.last_read = Instant::now();
+
+        let control = self.control.clone();
+        let mut s = control.state.lock().unwrap();
+        if let Some(t) = s.seek.take() {
+            s.replay.position = t;
+            self.locate(t)?;
+        }
+        if s.step != 0 {
+            self.step(std::mem::take(&mut s.step))?;
+            s.replay.position = self.samples[self.index].t;
+        } else if s.replay.playing {
+            s.replay.position = (s.replay.position + dt * s.replay.speed).min(s.replay.end);
+            if s.replay.position >= s.replay.end {
+                s.replay.playing = false;
+            }
+            self.locate(s.replay.position)?;
+        }
+        Ok(())
+    }
+}
+
+impl TelemetrySource for ReplaySource {
+    fn read(&mut self) -> Option<Frame> {
+        if let Err(e) = self.advance() {
+            self.error = Some(format!("replay: {}", e));
+            return None;
+        }
+        self.samples.get(self.index).map(|s| s.frame)
+    }
+
+    fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+}
@@ -0,0 +1,169 @@
+use super::shm::{PacketTracker, ShmError, ShmFiles, check_len, read_versioned};
+use super::{Frame, PacketStats, TelemetrySource};
+use crate::data::rf2::{
+    self, MAX_VEHICLES, Scoring, ScoringInfo, Telemetry, VehicleScoring, VehicleTelemetry,
+};
+use crate::data::{Standings, TelemetryFrame, car_model_from_name, parse_byte_string};
+use memmap2::Mmap;
+use std::mem::offset_of;
+use std::path::PathBuf;
+
+const FILE_NAMES: [&str; 2] = [rf2::TELEMETRY_FILE, rf2::SCORING_FILE];
+/// Written to `_sm_version` of the frames, to tell recordings apart.
+const SIM_TAG: &str = "rf2";
+
+/// rFactor 2 / Le Mans Ultimate shared memory, as published by the rF2 shared
+/// memory map plugin.
+///
+/// Telemetry is copied for the player's car only, on every read. Scoring,
+/// which holds every car, is refreshed whenever the plugin publishes a new
+/// version, and also feeds the standings.
+pub struct Rf2Shm {
+    files: ShmFiles,
+    sizes_ok: bool,
+    tracker: PacketTracker,
+    scoring: Option<(ScoringInfo, Vec<VehicleScoring>)>,
+    scoring_version: Option<u32>,
+    standings: Option<Standings>,
+    error: Option<String>,
+}
+
+impl Rf2Shm {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            files: ShmFiles::new(dir, &FILE_NAMES),
+            sizes_ok: false,
+            tracker: PacketTracker::new(),
+            scoring: None,
+            scoring_version: None,
+            standings: None,
+            error: None,
+        }
+    }
+
+    fn connect(&mut self) {
+        match self.files.poll() {
+            Ok(true) => {}
+            Ok(false) => return,
+            Err(e) => return self.set_error(e.into()),
+        }
+        println!("rF2 SHM mapped from {}", self.files.dir().display());
+        self.tracker = PacketTracker::new();
+        self.scoring = None;
+        self.scoring_version = None;
+        self.standings = None;
+        self.error = None;
+        self.sizes_ok = match self.files.maps().map(check_sizes) {
+            Some(Ok(())) => true,
+            Some(Err(e)) => {
+                self.set_error(e);
+                false
+            }
+            None => false,
+        };
+    }
+
+    fn set_error(&mut self, e: ShmError) {
+        let msg = e.to_string();
+        if self.error.as_ref() != Some(&msg) {
+            println!("rF2 SHM not usable: {}", msg);
+        }
+        self.error = Some(msg);
+    }
+}
+
+fn check_sizes(maps: &[Mmap]) -> Result<(), ShmError> {
+    check_len::<Telemetry>(FILE_NAMES[0], &maps[0])?;
+    check_len::<Scoring>(FILE_NAMES[1], &maps[1])
+}
+
+/// The session info and the cars in it.
+fn read_scoring(base: *const u8) -> (ScoringInfo, Vec<VehicleScoring>) {
+    unsafe {
+        let info: ScoringInfo =
+            std::ptr::read_unaligned(base.add(offset_of!(Scoring, info)) as *const _);
+        let vehicles = base.add(offset_of!(Scoring, vehicles)) as *const VehicleScoring;
+        let count = (info.num_vehicles.max(0) as usize).min(MAX_VEHICLES);
+        let vehicles = (0..count)
+            .map(|i| std::ptr::read_unaligned(vehicles.add(i)))
+            .collect();
+        (info, vehicles)
+    }
+}
+
+/// The telemetry of car `id`, or of the first car while the player is not
+/// known yet.
+fn read_vehicle_telemetry(base: *const u8, id: Option<i32>) -> Option<VehicleTelemetry> {
+    unsafe {
+        let count: i32 =
+            std::ptr::read_volatile(base.add(offset_of!(Telemetry, num_vehicles)) as *const _);
+        let vehicles = base.add(offset_of!(Telemetry, vehicles)) as *const VehicleTelemetry;
+        let count = (count.max(0) as usize).min(MAX_VEHICLES);
+        let index = match id {
+            Some(id) => (0..count)
+                .find(|&i| std::ptr::read_volatile(vehicles.add(i) as *const i32) == id)?,
+            None if count > 0 => 0,
+            None => return None,
+        };
+        Some(std::ptr::read_unaligned(vehicles.add(index)))
+    }
+}
+
+impl TelemetrySource for Rf2Shm {
+    fn read(&mut self) -> Option<Frame> {
+        self.connect();
+        if !self.sizes_ok {
+            return None;
+        }
+        let maps = self.files.maps()?;
+
+        // Scoring only changes a few times a second: re-read it when the
+        // plugin published a new version, and keep the last one if torn.
+        let published = unsafe { std::ptr::read_volatile(maps[1].as_ptr().add(4) as *const u32) };
+        if self.scoring_version != Some(published) {
+            let ((info, vehicles), version, consistent) =
+                unsafe { read_versioned(&maps[1], read_scoring) };
+            if consistent {
+                self.standings = Some(info.standings(&vehicles));
+                self.scoring = Some((info, vehicles));
+                self.scoring_version = Some(version);
+            }
+        }
+
+        let player = self
+            .scoring
+            .as_ref()
+            .and_then(|(info, vehicles)| Some((info, vehicles.iter().find(|v| v.is_player != 0)?)));
+        let player_id = player.map(|(_, v)| v.id);
+        let (vehicle, version, consistent) =
+            unsafe { read_versioned(&maps[0], |base| read_vehicle_telemetry(base, player_id)) };
+        self.tracker.observe(version as i32, consistent);
+        let vehicle = vehicle?;
+
+        let telemetry = TelemetryFrame::from_rf2(&vehicle, player);
+        let track = match player {
+            Some((info, _)) => parse_byte_string(&info.track_name),
+            None => parse_byte_string(&vehicle.track_name),
+        };
+        let car_model = car_model_from_name(&parse_byte_string(&vehicle.vehicle_name));
+        Some(Frame::from_telemetry(
+            &telemetry,
+            version as i32,
+            SIM_TAG,
+            &car_model,
+            &track,
+        ))
+    }
+
+    fn stats(&self) -> PacketStats {
+        self.tracker.stats()
+    }
+
+    fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    fn standings(&mut self) -> Option<Standings> {
+        self.standings.take()
+    }
+}
@@ -0,0 +1,444 @@
+use memmap2::Mmap;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{Ordering, fence};
+use std::time::{Duration, Instant};
+
+const MAX_RETRIES: usize = 8;
+const STALE_AFTER: Duration = Duration::from_millis(500);
+const RETRY_INTERVAL: Duration = Duration::from_secs(1);
+const CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+pub enum ShmError {
+    Io(std::io::Error),
+    TooShort {
+        name: &'static str,
+        len: usize,
+        expected: usize,
+    },
+    Version(String),
+}
+
+impl fmt::Display for ShmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ShmError::Io(e) => write!(f, "{}", e),
+            ShmError::TooShort {
+                name,
+                len,
+                expected,
+            } => write!(
+                f,
+                "{} is {} bytes, expected at least {}",
+                name, len, expected
+            ),
+            ShmError::Version(v) => write!(f, "unsupported shared memory version '{}'", v),
+        }
+    }
+}
+
+impl From<std::io::Error> for ShmError {
+    fn from(e: std::io::Error) -> Self {
+        ShmError::Io(e)
+    }
+}
+
+/// A set of shared memory files mapped together.
+///
+/// The files are mapped lazily and remapped whenever the bridge recreates or
+/// truncates them, so the overlay can be started before the sim.
+pub struct ShmFiles {
+    dir: PathBuf,
+    names: &'static [&'static str],
+    maps: Option<Vec<Mmap>>,
+    // Kept open to check the length of what is mapped, not of what the path names now
+    files: Vec<File>,
+    // (inode, len) of each file at map time, in `names` order
+    ids: Vec<(u64, u64)>,
+    last_attempt: Option<Instant>,
+    last_check: Instant,
+}
+
+impl ShmFiles {
+    pub fn new(dir: impl Into<PathBuf>, names: &'static [&'static str]) -> Self {
+        Self {
+            dir: dir.into(),
+            names,
+            maps: None,
+            files: Vec::new(),
+            ids: Vec::new(),
+            last_attempt: None,
+            last_check: Instant::now(),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Maps the files if they are not yet (at most once per second) and
+    /// drops the mapping if any was replaced or shrunk behind our back.
+    ///
+    /// Call it before every read: touching a mapped page past the end of a
+    /// truncated file raises SIGBUS, so the mapped files' lengths are checked
+    /// on each call. Replaced files are only looked for every 500 ms, as the
+    /// old mapping stays readable until then.
+    ///
+    /// Returns true right after a new mapping was made; missing files are
+    /// not an error, the sim is just not running.
+    pub fn poll(&mut self) -> std::io::Result<bool> {
+        if self.maps.is_some() && self.shrunk() {
+            println!("SHM truncated, remapping {}", self.dir.display());
+            self.maps = None;
+            self.last_attempt = None;
+        }
+        if self.maps.is_some() && self.last_check.elapsed() >= CHECK_INTERVAL {
+            self.last_check = Instant::now();
+            if self.file_ids().ok().as_ref() != Some(&self.ids) {
+                println!("SHM changed on disk, remapping {}", self.dir.display());
+                self.maps = None;
+                self.last_attempt = None;
+            }
+        }
+        if self.maps.is_some()
+            || self
+                .last_attempt
+                .is_some_and(|t| t.elapsed() < RETRY_INTERVAL)
+        {
+            return Ok(false);
+        }
+        self.last_attempt = Some(Instant::now());
+
+        let opened = self.file_ids().and_then(|ids| {
+            let (files, maps) = self
+                .names
+                .iter()
+                .map(|name| open_map(&self.dir.join(name)))
+                .collect::<std::io::Result<(Vec<_>, Vec<_>)>>()?;
+            Ok((ids, files, maps))
+        });
+        match opened {
+            Ok((ids, files, maps)) => {
+                self.ids = ids;
+                self.files = files;
+                self.maps = Some(maps);
+                self.last_check = Instant::now();
+                Ok(true)
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// The mappings in `names` order.
+    pub fn maps(&self) -> Option<&[Mmap]> {
+        self.maps.as_deref()
+    }
+
+    /// True if a mapped file is now shorter than its mapping.
+    fn shrunk(&self) -> bool {
+        let Some(maps) = &self.maps else {
+            return false;
+        };
+        self.files.iter().zip(maps).any(|(file, mmap)| {
+            !file
+                .metadata()
+                .is_ok_and(|meta| meta.len() >= mmap.len() as u64)
+        })
+    }
+
+    fn file_ids(&self) -> std::io::Result<Vec<(u64, u64)>> {
+        self.names
+            .iter()
+            .map(|name| {
+                let meta = std::fs::metadata(self.dir.join(name))?;
+                Ok((meta.ino(), meta.len()))
+            })
+            .collect()
+    }
+}
+
+fn open_map(path: &Path) -> std::io::Result<(File, Mmap)> {
+    let file = OpenOptions::new().read(true).open(path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    Ok((file, mmap))
+}
+
+/// Refuses mappings too short to hold a `T`, so the casts below stay in bounds.
+pub fn check_len<T>(name: &'static str, mmap: &Mmap) -> Result<(), ShmError> {
+    let expected = std::mem::size_of::<T>();
+    if mmap.len() < expected {
+        return Err(ShmError::TooShort {
+            name,
+            len: mmap.len(),
+            expected,
+        });
+    }
+    Ok(())
+}
+
+/// Copies a `T` that starts with an `i32` packet id out of shared memory,
+/// retrying until the id reads the same before and after the copy.
+///
+/// Returns the copy and whether it is known to be consistent.
+///
+/// # Safety
+/// `ptr` must point to at least `size_of::<T>()` readable bytes, and every
+/// bit pattern must be a valid `T`.
+pub unsafe fn read_consistent<T: Copy>(ptr: *const T) -> (T, bool) {
+    let id_ptr = ptr as *const i32;
+    let mut copy = unsafe { std::ptr::read_volatile(ptr) };
+    for _ in 0..MAX_RETRIES {
+        let before = unsafe { std::ptr::read_volatile(id_ptr) };
+        fence(Ordering::Acquire);
+        copy = unsafe { std::ptr::read_volatile(ptr) };
+        fence(Ordering::Acquire);
+        let after = unsafe { std::ptr::read_volatile(id_ptr) };
+        if before == after && packet_id(&copy) == after {
+            return (copy, true);
+        }
+    }
+    (copy, false)
+}
+
+/// Runs `copy` on a buffer guarded by a pair of version counters at offsets
+/// 0 and 4 (the rF2 plugin's `mVersionUpdateBegin`/`mVersionUpdateEnd`),
+/// retrying until no update was in progress or started during the copy.
+///
+/// Returns the copy, the version it was taken at and whether it is known to
+/// be consistent.
+///
+/// # Safety
+/// `mmap` must be at least 8 bytes long and `copy` must stay in its bounds.
+pub unsafe fn read_versioned<R>(mmap: &Mmap, copy: impl Fn(*const u8) -> R) -> (R, u32, bool) {
+    let begin_ptr = mmap.as_ptr() as *const u32;
+    let end_ptr = unsafe { begin_ptr.add(1) };
+    let mut result = copy(mmap.as_ptr());
+    let mut version = 0;
+    for _ in 0..MAX_RETRIES {
+        let begin = unsafe { std::ptr::read_volatile(begin_ptr) };
+        let end = unsafe { std::ptr::read_volatile(end_ptr) };
+        fence(Ordering::Acquire);
+        result = copy(mmap.as_ptr());
+        fence(Ordering::Acquire);
+        let after = unsafe { std::ptr::read_volatile(begin_ptr) };
+        version = end;
+        if begin == end && after == begin {
+            return (result, version, true);
+        }
+    }
+    (result, version, false)
+}
+
+/// Copies a `T` guarded by a sequence counter at `seq_offset` that the
+/// writer increments before and after each update, so it is odd while an
+/// update is in progress (PCARS2's `mSequenceNumber`).
+///
+/// Returns the copy, the sequence number it was taken at and whether it is
+/// known to be consistent.
+///
+/// # Safety
+/// As for [`read_consistent`], and `seq_offset` must point at a `u32` within `T`.
+pub unsafe fn read_sequenced<T: Copy>(ptr: *const T, seq_offset: usize) -> (T, u32, bool) {
+    let seq_ptr = unsafe { (ptr as *const u8).add(seq_offset) as *const u32 };
+    let mut copy = unsafe { std::ptr::read_volatile(ptr) };
+    let mut seq = 0;
+    for _ in 0..MAX_RETRIES {
+        let before = unsafe { std::ptr::read_volatile(seq_ptr) };
+        fence(Ordering::Acquire);
+        copy = unsafe { std::ptr::read_volatile(ptr) };
+        fence(Ordering::Acquire);
+        seq = unsafe { std::ptr::read_volatile(seq_ptr) };
+        if before == seq && seq % 2 == 0 {
+            return (copy, seq, true);
+        }
+    }
+    (copy, seq, false)
+}
+
+fn packet_id<T>(value: &T) -> i32 {
+    unsafe { *(value as *const T as *const i32) }
+}
+
+/// Sequencing statistics derived from a stream of packet ids.
+#[derive(Copy, Clone, Default)]
+pub struct PacketStats {
+    /// No new packet for a while: sim paused, in a menu or closed.
+    pub stale: bool,
+    /// Distinct packets seen by the reader.
+    pub received: u64,
+    /// Packets the sim produced that the reader never saw.
+    pub dropped: u64,
+    /// Reads that could not get a consistent copy.
+    pub torn: u64,
+    /// Packets per second produced by the sim, over the last second.
+    pub packet_rate: f32,
+    /// Distinct packets per second seen by the reader, over the last second.
+    pub read_rate: f32,
+}
+
+pub struct PacketTracker {
+    stats: PacketStats,
+    last_id: Option<i32>,
+    last_advance: Instant,
+    window_start: Instant,
+    window_first_id: Option<i32>,
+    window_received: u64,
+}
+
+impl PacketTracker {
+    pub fn new() -> Self {
+        Self {
+            stats: PacketStats::default(),
+            last_id: None,
+            last_advance: Instant::now(),
+            window_start: Instant::now(),
+            window_first_id: None,
+            window_received: 0,
+        }
+    }
+
+    pub fn observe(&mut self, id: i32, consistent: bool) {
+        if !consistent {
+            self.stats.torn += 1;
+        }
+
+        match self.last_id {
+            Some(last) if id == last => {}
+            Some(last) if id > last => {
+                self.stats.received += 1;
+                self.stats.dropped += (id - last - 1) as u64;
+                self.window_received += 1;
+                self.last_advance = Instant::now();
+            }
+            // First packet, or the sim restarted and reset its counter
+            _ => {
+                self.stats.received += 1;
+                self.window_received += 1;
+                self.window_first_id = Some(id);
+                self.last_advance = Instant::now();
+            }
+        }
+        self.last_id = Some(id);
+        self.stats.stale = self.last_advance.elapsed() > STALE_AFTER;
+
+        let elapsed = self.window_start.elapsed();
+        if elapsed >= Duration::from_secs(1) {
+            let secs = elapsed.as_secs_f32();
+            let produced = match self.window_first_id {
+                Some(first) if id >= first => (id - first) as f32,
+                _ => 0.0,
+            };
+            self.stats.packet_rate = produced / secs;
+            self.stats.read_rate = self.window_received as f32 / secs;
+            self.window_start = Instant::now();
+            self.window_first_id = Some(id);
+            self.window_received = 0;
+        }
+    }
+
+    pub fn stats(&self) -> PacketStats {
+        self.stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Maps a file holding `bytes`; the file is gone once mapped.
+    fn mapped(name: &str, bytes: &[u8]) -> Mmap {
+        let path =
+            std::env::temp_dir().join(format!("simtem-test-{}-{}", name, std::process::id()));
+        std::fs::write(&path, bytes).unwrap();
+        let (_, mmap) = open_map(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        mmap
+    }
+
+    fn versioned(begin: u32, end: u32, value: u32) -> Vec<u8> {
+        [begin, end, value]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect()
+    }
+
+    fn read_value(base: *const u8) -> u32 {
+        unsafe { std::ptr::read_unaligned(base.add(8) as *const u32) }
+    }
+
+    #[test]
+    fn versioned_read_checks_begin_and_end() {
+        let mmap = mapped("versioned", &versioned(7, 7, 42));
+        assert_eq!(unsafe { read_versioned(&mmap, read_value) }, (42, 7, true));
+
+        // An update in progress: begin already moved on
+        let mmap = mapped("versioned-torn", &versioned(8, 7, 42));
+        assert_eq!(unsafe { read_versioned(&mmap, read_value) }, (42, 7, false));
+    }
+
+    #[test]
+    fn truncated_files_are_remapped_on_the_next_poll() {
+        let dir = std::env::temp_dir().join(format!("simtem-test-shm-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("page");
+        std::fs::write(&path, [1u8; 64]).unwrap();
+
+        let mut files = ShmFiles::new(&dir, &["page"]);
+        assert!(files.poll().unwrap());
+        assert!(!files.poll().unwrap());
+        assert_eq!(files.maps().unwrap()[0].len(), 64);
+
+        // Well within CHECK_INTERVAL of the mapping
+        let file = OpenOptions::new().write(true).open(&path).unwrap();
+        file.set_len(16).unwrap();
+        assert!(files.poll().unwrap());
+        assert_eq!(files.maps().unwrap()[0].len(), 16);
+
+        // Growing is harmless and waits for the periodic check
+        file.set_len(32).unwrap();
+        assert!(!files.poll().unwrap());
+        assert_eq!(files.maps().unwrap()[0].len(), 16);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    struct Page {
+        packet_id: i32,
+        value: f32,
+    }
+
+    #[test]
+    fn consistent_read_copies_the_page() {
+        let page = Page {
+            packet_id: 12,
+            value: 0.5,
+        };
+        assert_eq!(unsafe { read_consistent(&page) }, (page, true));
+    }
+
+    #[test]
+    fn tracker_counts_dropped_and_torn_packets() {
+        let mut tracker = PacketTracker::new();
+        tracker.observe(1, true);
+        tracker.observe(1, true);
+        let stats = tracker.stats();
+        assert_eq!((stats.received, stats.dropped, stats.torn), (1, 0, 0));
+
+        // 2 and 3 never seen, 5 read while being written
+        tracker.observe(4, true);
+        tracker.observe(5, false);
+        let stats = tracker.stats();
+        assert_eq!((stats.received, stats.dropped, stats.torn), (3, 2, 1));
+        assert!(!stats.stale);
+
+        // The sim restarted: its counter going back is not a drop
+        tracker.observe(1, true);
+        tracker.observe(2, true);
+        let stats = tracker.stats();
+        assert_eq!((stats.received, stats.dropped, stats.torn), (5, 2, 1));
+    }
+}
@@ -0,0 +1,372 @@
+use super::{Frame, TelemetrySource};
+use crate::data::{
+    AcStatus, Graphics, Physics, SessionType, ShmPage, Statics, write_static_string,
+};
+use std::f32::consts::TAU;
+use std::time::Instant;
+
+/// Track layout as (length in m, corner speed in km/h); `None` is a straight.
+const TRACK: [(f32, Option<f32>); 14] = [
+    (1100.0, None),
+    (80.0, Some(75.0)),
+    (450.0, None),
+    (300.0, Some(190.0)),
+    (350.0, None),
+    (100.0, Some(95.0)),
+    (400.0, None),
+    (150.0, Some(140.0)),
+    (120.0, Some(150.0)),
+    (650.0, None),
+    (90.0, Some(85.0)),
+    (500.0, None),
+    (250.0, Some(165.0)),
+    (300.0, None),
+];
+const PROFILE_STEP: f32 = 5.0;
+const TOP_SPEED: f32 = 260.0 / 3.6;
+const BRAKE_DECEL: f32 = 13.0;
+const CORNER_G: f32 = 1.5;
+const G: f32 = 9.81;
+
+/// rpm per m/s in each forward gear
+const GEAR_RATIOS: [f32; 6] = [333.0, 232.0, 177.0, 144.0, 121.0, 102.0];
+const FIRST_GEAR_ACCEL: f32 = 7.0;
+const DRAG: f32 = 0.000445;
+const MAX_RPM: i32 = 7400;
+const UPSHIFT_RPM: f32 = 7150.0;
+const DOWNSHIFT_RPM: f32 = 4300.0;
+const IDLE_RPM: f32 = 1500.0;
+const SHIFT_TIME: f64 = 0.05;
+const MAX_FUEL: f32 = 120.0;
+
+const PHYSICS_HZ: f64 = 333.0;
+const GRAPHICS_HZ: f64 = 60.0;
+/// What ACC reports for laps and sectors without a time yet.
+const NO_TIME: i32 = i32::MAX;
+
+/// A GT4 car lapping a fictional track, producing ACC shaped pages.
+///
+/// Everything is a pure function of the simulated time, so the same sequence
+/// of `step` calls always yields the same frames.
+pub struct SyntheticCar {
+    /// Target speed in m/s every `PROFILE_STEP` metres, braking zones included.
+    profile: Vec<f32>,
+    /// Signed corner radius every `PROFILE_STEP` metres, 0 on straights.
+    radius: Vec<f32>,
+    lap_length: f32,
+    time: f64,
+    distance: f32,
+    speed: f32,
+    gear: usize,
+    shift_until: f64,
+    fuel: f32,
+    lap_start: f64,
+    sector_start: f64,
+    physics: Physics,
+    graphics: Graphics,
+    statics: Statics,
+}
+
+impl SyntheticCar {
+    pub fn new() -> Self {
+        let lap_length: f32 = TRACK.iter().map(|(len, _)| len).sum();
+        let points = (lap_length / PROFILE_STEP) as usize;
+        let mut profile = vec![TOP_SPEED; points];
+        let mut radius = vec![0.0; points];
+
+        let mut start = 0.0;
+        for (i, (len, corner)) in TRACK.iter().enumerate() {
+            if let Some(kmh) = corner {
+                let v = kmh / 3.6;
+                let r = v * v / (CORNER_G * G);
+                let sign = if i % 4 < 2 { 1.0 } else { -1.0 };
+                let from = (start / PROFILE_STEP) as usize;
+                let to = (((start + len) / PROFILE_STEP) as usize).min(points);
+                for p in from..to {
+                    profile[p] = v;
+                    radius[p] = r * sign;
+                }
+            }
+            start += len;
+        }
+        // Braking zones: walk backwards twice so the last corner also
+        // constrains the run to the line
+        for i in (0..points * 2).rev() {
+            let next = profile[(i + 1) % points];
+            let limit = (next * next + 2.0 * BRAKE_DECEL * 0.8 * PROFILE_STEP).sqrt();
+            let p = &mut profile[i % points];
+            *p = p.min(limit);
+        }
+
+        let mut statics = Statics::zeroed();
+        write_static_string(&mut statics._sm_version, "1.9");
+        write_static_string(&mut statics._ac_version, "1.9");
+        write_static_string(&mut statics.car_model, "bmw_m4_gt4");
+        write_static_string(&mut statics._track, "synthetic");
+        write_static_string(&mut statics._player_name, "Synthetic");
+        write_static_string(&mut statics._player_surname, "Driver");
+        write_static_string(&mut statics._dry_tyres_name, "DHF");
+        write_static_string(&mut statics._wet_tyres_name, "WH");
+        statics._number_of_sessions = 1;
+        statics._num_cars = 1;
+        statics._sector_count = 3;
+        statics._max_rpm = MAX_RPM;
+        statics._max_fuel = MAX_FUEL;
+        statics._track_spline_lenth = lap_length;
+
+        let mut graphics = Graphics::zeroed();
+        graphics._status = AcStatus::Live as i32;
+        graphics._session_type = SessionType::Practice as i32;
+        graphics._position = 1;
+        graphics._active_cars = 1;
+        graphics._i_last_time = NO_TIME;
+        graphics._i_best_time = NO_TIME;
+        graphics.is_valid_lap = 1;
+        graphics.tc = 3;
+        graphics.abs = 2;
+
+        let mut physics = Physics::zeroed();
+        physics.current_max_rpm = MAX_RPM;
+        physics.brake_bias = 0.78;
+        physics.ignition_on = 1;
+        physics._is_engine_running = 1;
+
+        let mut car = Self {
+            profile,
+            radius,
+            lap_length,
+            time: 0.0,
+            distance: 0.0,
+            speed: 0.0,
+            gear: 0,
+            shift_until: 0.0,
+            fuel: 60.0,
+            lap_start: 0.0,
+            sector_start: 0.0,
+            physics,
+            graphics,
+            statics,
+        };
+        car.step(0.0);
+        car
+    }
+
+    /// Advances by `dt` seconds in physics sized steps.
+    pub fn advance(&mut self, mut dt: f64) {
+        let step = 1.0 / PHYSICS_HZ;
+        while dt > 0.0 {
+            self.step(dt.min(step));
+            dt -= step;
+        }
+    }
+
+    pub fn step(&mut self, dt: f64) {
+        self.time += dt;
+        let dt_s = dt as f32;
+        let point = (self.distance / PROFILE_STEP) as usize % self.profile.len();
+        let target = self.profile[point];
+
+        let (gas, brake) = if self.time < self.shift_until {
+            (0.0, 0.0)
+        } else if self.speed < target - 0.3 {
+            (1.0, 0.0)
+        } else if self.speed > target + 0.3 {
+            (0.0, ((self.speed - target) / 3.0).clamp(0.2, 1.0))
+        } else {
+            (0.5, 0.0)
+        };
+
+        let drive = FIRST_GEAR_ACCEL * GEAR_RATIOS[self.gear] / GEAR_RATIOS[0];
+        let accel = gas * drive - brake * BRAKE_DECEL - DRAG * self.speed * self.speed;
+        self.speed = (self.speed + accel * dt_s).max(0.0);
+        self.distance += self.speed * dt_s;
+
+        let mut rpm = self.speed * GEAR_RATIOS[self.gear];
+        if rpm >= UPSHIFT_RPM && self.gear + 1 < GEAR_RATIOS.len() && gas > 0.9 {
+            self.gear += 1;
+            self.shift_until = self.time + SHIFT_TIME;
+        } else if rpm < DOWNSHIFT_RPM && self.gear > 0 && gas < 0.9 {
+            self.gear -= 1;
+        }
+        rpm = (self.speed * GEAR_RATIOS[self.gear]).max(IDLE_RPM);
+        self.fuel = (self.fuel - (0.005 + 0.03 * gas) * dt_s).max(0.0);
+
+        let tc_active = gas == 1.0 && self.gear < 3 && (self.time * 37.0).sin() > 0.6;
+        let abs_active = brake > 0.7 && self.speed > 20.0 && (self.time * 53.0).sin() > 0.5;
+        let lateral = match self.radius[point] {
+            r if r != 0.0 => self.speed * self.speed / r / G,
+            _ => 0.0,
+        };
+
+        let p = &mut self.physics;
+        p._packet_id = (self.time * PHYSICS_HZ) as i32 + 1;
+        p.gas = gas;
+        p.brake = brake;
+        p.fuel = self.fuel;
+        p.gear = self.gear as i32 + 2;
+        p.rpms = rpm as i32;
+        p.speed_kmh = self.speed * 3.6;
+        p._acc_g = [lateral, 0.0, accel / G];
+        p._velocity = [0.0, 0.0, self.speed];
+        p._local_velocity = [0.0, 0.0, self.speed];
+        p.tc = if tc_active { 1.0 } else { 0.0 };
+        p.abs = if abs_active { 1.0 } else { 0.0 };
+        p._tc_in_action = tc_active as i32;
+        p._abs_in_action = abs_active as i32;
+
+        let graphics_id = (self.time * GRAPHICS_HZ) as i32 + 1;
+        if graphics_id != self.graphics._packet_id {
+            self.graphics._packet_id = graphics_id;
+            self.update_graphics();
+        }
+    }
+
+    fn update_graphics(&mut self) {
+        let now_ms = |since: f64| ((self.time - since) * 1000.0) as i32;
+        let g = &mut self.graphics;
+
+        if self.distance >= self.lap_length {
+            self.distance -= self.lap_length;
+            let lap_ms = now_ms(self.lap_start);
+            g._completed_laps += 1;
+            g._i_last_time = lap_ms;
+            g._i_best_time = g._i_best_time.min(lap_ms);
+            g._last_sector_time = now_ms(self.sector_start);
+            g.current_sector_index = 0;
+            self.lap_start = self.time;
+            self.sector_start = self.time;
+        }
+
+        let sector = ((self.distance / self.lap_length) * 3.0) as i32;
+        if sector > g.current_sector_index {
+            g._last_sector_time = now_ms(self.sector_start);
+            g._i_split = now_ms(self.lap_start);
+            g.current_sector_index = sector;
+            self.sector_start = self.time;
+        }
+
+        let fraction = self.distance / self.lap_length;
+        let track_radius = self.lap_length / TAU;
+        g.i_current_time = now_ms(self.lap_start);
+        g.normalized_car_position = fraction;
+        g._distance_traveled = g._completed_laps as f32 * self.lap_length + self.distance;
+        g._car_coordinates[0] = [
+            track_radius * (fraction * TAU).cos(),
+            0.0,
+            track_radius * (fraction * TAU).sin(),
+        ];
+        g._used_fuel = 60.0 - self.fuel;
+        if g._completed_laps > 0 {
+            g._fuel_x_lap = g._used_fuel / (g._completed_laps as f32 + fraction);
+        }
+    }
+
+    pub fn frame(&self) -> Frame {
+        Frame {
+            physics: self.physics,
+            graphics: self.graphics,
+            statics: self.statics,
+        }
+    }
+}
+
+impl Default for SyntheticCar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// [`SyntheticCar`] driven by the wall clock, for running the overlay
+/// without a sim.
+pub struct SyntheticSource {
+    car: SyntheticCar,
+    last_read: Instant,
+}
+
+impl SyntheticSource {
+    pub fn new() -> Self {
+        Self {
+            car: SyntheticCar::new(),
+            last_read: Instant::now(),
+        }
+    }
+}
+
+impl Default for SyntheticSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TelemetrySource for SyntheticSource {
+    fn read(&mut self) -> Option<Frame> {
+        self.car.advance(self.last_read.elapsed().as_secs_f64());
+        self.last_read = Instant::now();
+        Some(self.car.frame())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::AccShm;
+
+    #[test]
+    fn laps_the_track_like_a_car() {
+        let mut car = SyntheticCar::new();
+        let mut gears = [false; GEAR_RATIOS.len()];
+        let mut last = car.frame();
+        let mut sectors = 0;
+        while last.graphics._completed_laps < 3 {
+            car.advance(1.0 / GRAPHICS_HZ);
+            let frame = car.frame();
+            let (p, g) = (&frame.physics, &frame.graphics);
+            assert!(p.rpms <= p.current_max_rpm, "{} rpm", p.rpms);
+            assert!(p.fuel < last.physics.fuel, "fuel went from {}", p.fuel);
+            gears[(p.gear - 2) as usize] = true;
+
+            let laps = g._completed_laps - last.graphics._completed_laps;
+            let sector = g.current_sector_index;
+            match laps {
+                0 if sector == last.graphics.current_sector_index => {}
+                0 => assert_eq!(sector, last.graphics.current_sector_index + 1),
+                1 => {
+                    assert_eq!(sector, 0);
+                    // Timed on the same update as the lap it ends
+                    assert!((g._i_last_time - last.graphics.i_current_time).abs() < 50);
+                }
+                _ => panic!(
+                    "lap counter went from {} to {}",
+                    last.graphics._completed_laps, g._completed_laps
+                ),
+            }
+            if sector != last.graphics.current_sector_index {
+                sectors += 1;
+            }
+            last = frame;
+        }
+        assert_eq!(sectors, 9);
+        assert!(gears.iter().all(|&used| used), "gears used: {:?}", gears);
+        assert!(last.graphics._i_best_time <= last.graphics._i_last_time);
+    }
+
+    #[test]
+    fn acc_shm_reads_back_the_written_pages() {
+        let mut car = SyntheticCar::new();
+        car.advance(10.0);
+        let written = car.frame();
+
+        let dir = std::env::temp_dir().join(format!("simtem-test-shm-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("acpmf_physics"), written.physics.as_bytes()).unwrap();
+        std::fs::write(dir.join("acpmf_graphics"), written.graphics.as_bytes()).unwrap();
+        std::fs::write(dir.join("acpmf_static"), written.statics.as_bytes()).unwrap();
+        let read = AccShm::new(&dir).read();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let read = read.expect("no frame from the written files");
+        assert!(read.physics.as_bytes() == written.physics.as_bytes());
+        assert!(read.graphics.as_bytes() == written.graphics.as_bytes());
+        assert!(read.statics.as_bytes() == written.statics.as_bytes());
+    }
+}
@@ -0,0 +1,110 @@
+use std::io;
+use std::net::{SocketAddr, UdpSocket};
+use std::time::{Duration, Instant};
+
+const RETRY_INTERVAL: Duration = Duration::from_secs(1);
+/// The games stop sending in menus; after this long the sim counts as gone.
+const TIMEOUT: Duration = Duration::from_secs(2);
+/// Larger than any packet of the supported games.
+const MAX_PACKET: usize = 4096;
+
+/// A non-blocking UDP socket for sims that push their telemetry.
+///
+/// Bound lazily and retried every second while the address is unavailable,
+/// like the shared memory files.
+pub struct UdpListener {
+    addr: SocketAddr,
+    socket: Option<UdpSocket>,
+    last_attempt: Option<Instant>,
+    /// Why the last bind failed, reported again until a bind succeeds.
+    bind_error: Option<io::Error>,
+    last_packet: Option<Instant>,
+    buf: Box<[u8; MAX_PACKET]>,
+}
+
+impl UdpListener {
+    pub fn new(addr: SocketAddr) -> Self {
+        Self {
+            addr,
+            socket: None,
+            last_attempt: None,
+            bind_error: None,
+            last_packet: None,
+            buf: Box::new([0; MAX_PACKET]),
+        }
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Whether a packet arrived recently.
+    pub fn is_live(&self) -> bool {
+        self.last_packet.is_some_and(|t| t.elapsed() < TIMEOUT)
+    }
+
+    /// Hands every datagram received since the last call to `f`, oldest
+    /// first. Binds the socket first if needed; while it is not bound, the
+    /// error of the last attempt is returned.
+    pub fn drain(&mut self, mut f: impl FnMut(&[u8])) -> io::Result<()> {
+        if self.socket.is_none() {
+            if self
+                .last_attempt
+                .is_some_and(|t| t.elapsed() < RETRY_INTERVAL)
+            {
+                return match &self.bind_error {
+                    Some(e) => Err(io::Error::new(e.kind(), e.to_string())),
+                    None => Ok(()),
+                };
+            }
+            self.last_attempt = Some(Instant::now());
+            let socket = match bind(self.addr) {
+                Ok(socket) => socket,
+                Err(e) => {
+                    self.bind_error = Some(io::Error::new(e.kind(), e.to_string()));
+                    return Err(e);
+                }
+            };
+            println!("Listening for UDP telemetry on {}", self.addr);
+            self.socket = Some(socket);
+            self.bind_error = None;
+        }
+
+        let Some(socket) = &self.socket else {
+            return Ok(());
+        };
+        loop {
+            match socket.recv(&mut self.buf[..]) {
+                Ok(len) => {
+                    self.last_packet = Some(Instant::now());
+                    f(&self.buf[..len]);
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+                // Windows style "port unreachable" echoes, harmless here
+                Err(e) if e.kind() == io::ErrorKind::ConnectionReset => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+fn bind(addr: SocketAddr) -> io::Result<UdpSocket> {
+    let socket = UdpSocket::bind(addr)?;
+    socket.set_nonblocking(true)?;
+    Ok(socket)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bind_error_is_kept_until_the_retry() {
+        let taken = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let mut listener = UdpListener::new(taken.local_addr().unwrap());
+        assert!(listener.drain(|_| {}).is_err());
+        // Within the retry interval nothing is attempted, but the error stays
+        let e = listener.drain(|_| {}).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::AddrInUse);
+    }
+}
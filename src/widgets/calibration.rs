@@ -0,0 +1,57 @@
+use crate::analysis::GearResult;
+use eframe::egui;
+
+pub enum ReviewAction {
+    Save,
+    Discard,
+}
+
+/// Review of a finished shift calibration, before it is written to the profile.
+pub fn calibration_review(
+    ctx: &egui::Context,
+    car_model: &str,
+    results: &[GearResult],
+    current: &dyn Fn(i32) -> Option<i32>,
+) -> Option<ReviewAction> {
+    let mut action = None;
+    egui::Window::new("Shift calibration")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+        .show(ctx, |ui| {
+            ui.label(car_model);
+            egui::Grid::new("calibration_grid")
+                .striped(true)
+                .show(ui, |ui| {
+                    for heading in ["GEAR", "SHIFTS", "REJECTED", "CURRENT", "NEW"] {
+                        ui.label(heading);
+                    }
+                    ui.end_row();
+                    for r in results {
+                        ui.label(format!("{} > {}", r.gear, r.gear + 1));
+                        ui.label(r.accepted.to_string());
+                        ui.label(r.rejected.to_string());
+                        ui.label(rpm_text(current(r.gear)));
+                        ui.label(rpm_text(r.recommended));
+                        ui.end_row();
+                    }
+                });
+            ui.horizontal(|ui| {
+                let can_save = results.iter().any(|r| r.recommended.is_some());
+                if ui
+                    .add_enabled(can_save, egui::Button::new("Save to profile"))
+                    .clicked()
+                {
+                    action = Some(ReviewAction::Save);
+                }
+                if ui.button("Discard").clicked() {
+                    action = Some(ReviewAction::Discard);
+                }
+            });
+        });
+    action
+}
+
+fn rpm_text(rpm: Option<i32>) -> String {
+    rpm.map_or_else(|| "-".to_string(), |r| r.to_string())
+}
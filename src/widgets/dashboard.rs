@@ -7,6 +7,7 @@ pub fn rev_strip(
     is_strobe: bool,
     text: &str,
     ignition: bool,
+    downshift_marker: Option<f32>,
 ) {
     let bar_height = 30.0;
     let bar_width = 415.0;
@@ -44,11 +45,19 @@ pub fn rev_strip(
         painter.rect_filled(fill_rect, 0.0, color);
     } else if !ignition {
         painter.rect_filled(rect, 0.0, egui::Color32::RED);
-    }
-    else {
+    } else {
         painter.rect_filled(rect, 0.0, egui::Color32::DARK_GREEN);
     }
 
+    // Safe downshift point for the current gear
+    if let Some(marker) = downshift_marker {
+        let x = rect.left() + rect.width() * marker;
+        painter.line_segment(
+            [egui::pos2(x, rect.top()), egui::pos2(x, rect.bottom())],
+            egui::Stroke::new(2.0, egui::Color32::from_rgb(0, 160, 255)),
+        );
+    }
+
     painter.text(
         rect.center(),
         egui::Align2::CENTER_CENTER,
@@ -123,3 +132,49 @@ pub fn stat_box(ui: &mut egui::Ui, heading: &str, value: &str) {
         egui::Color32::WHITE,
     );
 }
+
+pub fn status_message(ui: &mut egui::Ui, text: &str) {
+    let rect = ui.available_rect_before_wrap();
+    ui.painter().text(
+        rect.center(),
+        egui::Align2::CENTER_CENTER,
+        text,
+        egui::FontId::proportional(24.0),
+        egui::Color32::LIGHT_GRAY,
+    );
+}
+
+pub fn corner_text(ui: &mut egui::Ui, text: &str, color: egui::Color32) {
+    let rect = ui.max_rect();
+    ui.painter().text(
+        rect.right_top(),
+        egui::Align2::RIGHT_TOP,
+        text,
+        egui::FontId::proportional(12.0),
+        color,
+    );
+}
+
+pub fn rec_indicator(ui: &mut egui::Ui) {
+    let pos = ui.max_rect().left_top() + egui::vec2(6.0, 6.0);
+    let painter = ui.painter();
+    painter.circle_filled(pos, 5.0, egui::Color32::RED);
+    painter.text(
+        pos + egui::vec2(9.0, 0.0),
+        egui::Align2::LEFT_CENTER,
+        "REC",
+        egui::FontId::proportional(12.0),
+        egui::Color32::RED,
+    );
+}
+
+pub fn calibration_indicator(ui: &mut egui::Ui, shifts: usize) {
+    let pos = ui.max_rect().left_top() + egui::vec2(50.0, 6.0);
+    ui.painter().text(
+        pos,
+        egui::Align2::LEFT_CENTER,
+        format!("CAL {}", shifts),
+        egui::FontId::proportional(12.0),
+        egui::Color32::from_rgb(0, 160, 255),
+    );
+}
@@ -0,0 +1,94 @@
+use eframe::egui;
+
+/// Delta at either end of the bar, in seconds.
+const DELTA_RANGE: f64 = 2.0;
+/// Trends below this (s/s) are shown as steady.
+const STEADY_TREND: f64 = 0.01;
+const COLOR_GAIN: egui::Color32 = egui::Color32::from_rgb(0, 200, 80);
+const COLOR_LOSS: egui::Color32 = egui::Color32::from_rgb(220, 40, 40);
+
+/// Bar filled from the centre: left and green when ahead of the reference
+/// lap, right and red when behind, with an arrow for the rate the delta
+/// is moving at.
+pub fn delta_bar(ui: &mut egui::Ui, delta: Option<f64>, trend: Option<f64>, reference: &str) {
+    let width = ui.available_width();
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(width, 16.0), egui::Sense::hover());
+    ui.painter().text(
+        rect.left_center(),
+        egui::Align2::LEFT_CENTER,
+        reference,
+        egui::FontId::proportional(11.0),
+        egui::Color32::LIGHT_GRAY,
+    );
+    if let Some(trend) = trend {
+        let text = if trend.abs() < STEADY_TREND {
+            "STEADY".to_string()
+        } else {
+            format!("{:+.2} s/s", trend)
+        };
+        ui.painter().text(
+            rect.right_center(),
+            egui::Align2::RIGHT_CENTER,
+            text,
+            egui::FontId::proportional(11.0),
+            egui::Color32::LIGHT_GRAY,
+        );
+    }
+
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(width, 28.0), egui::Sense::hover());
+    let painter = ui.painter();
+    painter.rect_filled(rect, 0.0, egui::Color32::from_gray(30));
+    let center = rect.center();
+    let Some(delta) = delta else {
+        painter.text(
+            center,
+            egui::Align2::CENTER_CENTER,
+            "NO REFERENCE LAP",
+            egui::FontId::proportional(14.0),
+            egui::Color32::GRAY,
+        );
+        return;
+    };
+
+    let color = if delta < 0.0 { COLOR_GAIN } else { COLOR_LOSS };
+    let fill = (delta / DELTA_RANGE).clamp(-1.0, 1.0) as f32 * rect.width() / 2.0;
+    let fill_rect = egui::Rect::from_x_y_ranges(
+        center.x.min(center.x + fill)..=center.x.max(center.x + fill),
+        rect.y_range(),
+    );
+    painter.rect_filled(fill_rect, 0.0, color);
+    painter.line_segment(
+        [
+            egui::pos2(center.x, rect.top()),
+            egui::pos2(center.x, rect.bottom()),
+        ],
+        egui::Stroke::new(1.0, egui::Color32::WHITE),
+    );
+    painter.text(
+        center,
+        egui::Align2::CENTER_CENTER,
+        format!("{:+.2}", delta),
+        egui::FontId::proportional(20.0),
+        egui::Color32::WHITE,
+    );
+
+    // Points the way the delta is moving: right while losing time
+    if let Some(trend) = trend.filter(|t| t.abs() >= STEADY_TREND) {
+        let (dir, color) = if trend > 0.0 {
+            (1.0, COLOR_LOSS)
+        } else {
+            (-1.0, COLOR_GAIN)
+        };
+        let tip = egui::pos2(center.x + dir * 58.0, center.y);
+        let back = tip.x - dir * 10.0;
+        painter.add(egui::Shape::convex_polygon(
+            vec![
+                tip,
+                egui::pos2(back, center.y - 7.0),
+                egui::pos2(back, center.y + 7.0),
+            ],
+            color,
+            egui::Stroke::new(1.0, egui::Color32::WHITE),
+        ));
+    }
+}
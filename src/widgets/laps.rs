@@ -0,0 +1,135 @@
+use crate::analysis::{LapTimer, Rating};
+use crate::data::SECTORS;
+use eframe::egui;
+
+const COLOR_SESSION_BEST: egui::Color32 = egui::Color32::from_rgb(190, 80, 255);
+const COLOR_PERSONAL_BEST: egui::Color32 = egui::Color32::from_rgb(0, 210, 90);
+const COLOR_SLOWER: egui::Color32 = egui::Color32::from_rgb(255, 210, 0);
+const COLOR_INVALID: egui::Color32 = egui::Color32::from_rgb(255, 70, 70);
+
+/// Current lap time, the sector splits and the last and best laps.
+///
+/// Sectors done this lap are coloured against the bests; the others show
+/// the last lap's, dimmed. Invalid laps are red.
+pub fn lap_timing(ui: &mut egui::Ui, timer: &LapTimer, current_lap_time: Option<f64>) {
+    let width = ui.available_width();
+
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(width, 30.0), egui::Sense::hover());
+    let painter = ui.painter();
+    let current_color = if timer.invalid() {
+        COLOR_INVALID
+    } else {
+        egui::Color32::WHITE
+    };
+    painter.text(
+        rect.left_center(),
+        egui::Align2::LEFT_CENTER,
+        current_lap_time.map_or("-:--.---".to_string(), format_time),
+        egui::FontId::proportional(24.0),
+        current_color,
+    );
+    if timer.invalid() {
+        painter.text(
+            rect.right_center(),
+            egui::Align2::RIGHT_CENTER,
+            "INVALID",
+            egui::FontId::proportional(14.0),
+            COLOR_INVALID,
+        );
+    }
+
+    ui.add_space(4.0);
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(width, 24.0), egui::Sense::hover());
+    let painter = ui.painter();
+    let current = timer.current_sectors();
+    let last = timer.last_lap();
+    let box_width = rect.width() / SECTORS as f32;
+    for (i, current) in current.into_iter().enumerate() {
+        let cell = egui::Rect::from_min_size(
+            egui::pos2(rect.left() + i as f32 * box_width, rect.top()),
+            egui::vec2(box_width - 4.0, rect.height()),
+        );
+        painter.rect_filled(cell, 2.0, egui::Color32::from_gray(40));
+        let (time, invalid, dim) = match (current, last) {
+            (Some(time), _) => (Some(time), timer.invalid(), false),
+            (None, Some(lap)) => (lap.sectors[i], lap.invalid, true),
+            (None, None) => (None, false, false),
+        };
+        let (text, color) = match time {
+            Some(time) => (
+                format!("S{} {}", i + 1, format_time(time)),
+                if invalid {
+                    COLOR_INVALID
+                } else {
+                    rating_color(timer.rate_sector(i, time))
+                },
+            ),
+            None => (format!("S{} -", i + 1), egui::Color32::GRAY),
+        };
+        let color = if dim {
+            color.gamma_multiply(0.5)
+        } else {
+            color
+        };
+        painter.text(
+            cell.center(),
+            egui::Align2::CENTER_CENTER,
+            text,
+            egui::FontId::proportional(13.0),
+            color,
+        );
+    }
+
+    ui.add_space(4.0);
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(width, 20.0), egui::Sense::hover());
+    let painter = ui.painter();
+    let font = egui::FontId::proportional(14.0);
+    let (last_text, last_color) = match last {
+        Some(lap) if lap.invalid => (format!("LAST {} INV", format_time(lap.time)), COLOR_INVALID),
+        Some(lap) => (
+            format!("LAST {}", format_time(lap.time)),
+            rating_color(timer.rate_lap(lap.time)),
+        ),
+        None => ("LAST -".to_string(), egui::Color32::GRAY),
+    };
+    painter.text(
+        rect.left_center(),
+        egui::Align2::LEFT_CENTER,
+        last_text,
+        font.clone(),
+        last_color,
+    );
+    let (best_text, best_color) = match timer.best_lap() {
+        Some(lap) => (
+            format!("BEST {}", format_time(lap.time)),
+            rating_color(timer.rate_lap(lap.time)),
+        ),
+        None => ("BEST -".to_string(), egui::Color32::GRAY),
+    };
+    painter.text(
+        rect.right_center(),
+        egui::Align2::RIGHT_CENTER,
+        best_text,
+        font,
+        best_color,
+    );
+}
+
+fn rating_color(rating: Rating) -> egui::Color32 {
+    match rating {
+        Rating::SessionBest => COLOR_SESSION_BEST,
+        Rating::PersonalBest => COLOR_PERSONAL_BEST,
+        Rating::Slower => COLOR_SLOWER,
+    }
+}
+
+/// `m:ss.mmm`, or `ss.mmm` under a minute.
+pub fn format_time(seconds: f64) -> String {
+    let ms = (seconds * 1000.0).round() as i64;
+    let (minutes, ms) = (ms / 60_000, ms % 60_000);
+    if minutes > 0 {
+        format!("{}:{:02}.{:03}", minutes, ms / 1000, ms % 1000)
+    } else {
+        format!("{}.{:03}", ms / 1000, ms % 1000)
+    }
+}
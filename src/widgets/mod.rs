@@ -1,4 +1,21 @@
 pub mod telemetry;
-pub use telemetry::{telemetry_graph, pedal_bar};
+pub use telemetry::{pedal_bar, telemetry_graph};
 pub mod dashboard;
-pub use dashboard::{rev_strip, gear_indicator, speedometer, stat_box};
+pub use dashboard::{
+    calibration_indicator, corner_text, gear_indicator, rec_indicator, rev_strip, speedometer,
+    stat_box, status_message,
+};
+pub mod replay;
+pub use replay::replay_bar;
+pub mod calibration;
+pub use calibration::{ReviewAction, calibration_review};
+pub mod shift_points;
+pub use shift_points::{shift_chart, shift_table};
+pub mod notice;
+pub use notice::{profile_errors_notice, unknown_car_notice};
+pub mod standings;
+pub use standings::{leaderboard, relative};
+pub mod laps;
+pub use laps::lap_timing;
+pub mod delta;
+pub use delta::delta_bar;
@@ -0,0 +1,47 @@
+use eframe::egui;
+
+/// Banner for a car without a profile. Returns true when "create profile"
+/// was clicked.
+pub fn unknown_car_notice(ctx: &egui::Context, car_model: &str) -> bool {
+    let mut create = false;
+    egui::Area::new(egui::Id::new("unknown_car_notice"))
+        .anchor(egui::Align2::CENTER_BOTTOM, egui::vec2(0.0, -4.0))
+        .show(ctx, |ui| {
+            egui::Frame::NONE
+                .fill(egui::Color32::from_rgba_premultiplied(40, 30, 0, 220))
+                .inner_margin(4.0)
+                .show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.colored_label(
+                            egui::Color32::YELLOW,
+                            format!("Unknown car '{}'", car_model),
+                        );
+                        create = ui.small_button("Create profile from defaults").clicked();
+                    });
+                });
+        });
+    create
+}
+
+/// Lists the problems found in the user's car profiles until they are fixed.
+pub fn profile_errors_notice(ctx: &egui::Context, errors: &[String]) {
+    if errors.is_empty() {
+        return;
+    }
+    egui::Area::new(egui::Id::new("profile_errors_notice"))
+        .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 4.0))
+        .show(ctx, |ui| {
+            egui::Frame::NONE
+                .fill(egui::Color32::from_rgba_premultiplied(50, 10, 10, 220))
+                .inner_margin(4.0)
+                .show(ui, |ui| {
+                    ui.colored_label(
+                        egui::Color32::LIGHT_RED,
+                        "Car profiles not loaded (F5 reloads):",
+                    );
+                    for e in errors {
+                        ui.label(e);
+                    }
+                });
+        });
+}
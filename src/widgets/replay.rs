@@ -0,0 +1,48 @@
+use crate::source::ReplayState;
+use eframe::egui;
+
+/// Progress bar with transport state. Returns the clicked position as a
+/// fraction of the session when the user clicks to seek.
+pub fn replay_bar(ui: &mut egui::Ui, state: &ReplayState) -> Option<f64> {
+    let bar_height = 20.0;
+    let bar_width = 530.0;
+    let (rect, response) =
+        ui.allocate_exact_size(egui::vec2(bar_width, bar_height), egui::Sense::click());
+    let painter = ui.painter();
+
+    let duration = (state.end - state.start).max(f64::EPSILON);
+    let elapsed = state.position - state.start;
+    let progress = (elapsed / duration).clamp(0.0, 1.0) as f32;
+
+    painter.rect_filled(rect, 0.0, egui::Color32::from_gray(30));
+    let fill_rect = egui::Rect::from_min_max(
+        rect.min,
+        egui::pos2(rect.left() + rect.width() * progress, rect.bottom()),
+    );
+    painter.rect_filled(fill_rect, 0.0, egui::Color32::from_gray(90));
+
+    let text = format!(
+        "{}  {} / {}  {}x",
+        if state.playing { "PLAY" } else { "PAUSE" },
+        clock(elapsed),
+        clock(duration),
+        state.speed
+    );
+    painter.text(
+        rect.center(),
+        egui::Align2::CENTER_CENTER,
+        text,
+        egui::FontId::proportional(12.0),
+        egui::Color32::WHITE,
+    );
+
+    let click = response
+        .interact_pointer_pos()
+        .filter(|_| response.clicked())?;
+    Some(((click.x - rect.left()) / rect.width()).clamp(0.0, 1.0) as f64)
+}
+
+fn clock(seconds: f64) -> String {
+    let total = seconds.max(0.0) as u64;
+    format!("{}:{:02}", total / 60, total % 60)
+}
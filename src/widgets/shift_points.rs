@@ -0,0 +1,44 @@
+use crate::analysis::{GearCurve, ShiftPoint};
+use eframe::egui;
+use egui_plot::{Legend, Line, Plot, VLine};
+
+/// Gear by gear upshift table next to the rpm currently in the profile.
+pub fn shift_table(ui: &mut egui::Ui, shifts: &[ShiftPoint], current: &dyn Fn(i32) -> Option<i32>) {
+    egui::Grid::new("shift_table").striped(true).show(ui, |ui| {
+        for heading in ["GEAR", "KM/H", "RPM", "CURRENT", ""] {
+            ui.label(heading);
+        }
+        ui.end_row();
+        for s in shifts {
+            ui.label(format!("{} > {}", s.gear, s.gear + 1));
+            ui.label(format!("{:.0}", s.speed_kmh));
+            ui.label(s.rpm.to_string());
+            ui.label(current(s.gear).map_or_else(|| "-".to_string(), |r| r.to_string()));
+            ui.label(if s.crossover { "" } else { "top of gear" });
+            ui.end_row();
+        }
+    });
+}
+
+/// Acceleration against speed for every gear, with the shift speeds marked.
+pub fn shift_chart(ui: &mut egui::Ui, curves: &[GearCurve], shifts: &[ShiftPoint]) {
+    Plot::new("shift_chart")
+        .legend(Legend::default())
+        .x_axis_label("km/h")
+        .y_axis_label("g")
+        .include_x(0.0)
+        .include_y(0.0)
+        .show(ui, |plot_ui| {
+            for curve in curves {
+                plot_ui.line(
+                    Line::new(format!("Gear {}", curve.gear), curve.points.clone()).width(2.0),
+                );
+            }
+            for s in shifts {
+                plot_ui.vline(
+                    VLine::new(format!("{} > {}", s.gear, s.gear + 1), s.speed_kmh)
+                        .color(egui::Color32::GRAY),
+                );
+            }
+        });
+}
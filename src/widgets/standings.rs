@@ -0,0 +1,105 @@
+use crate::data::CarGap;
+use eframe::egui;
+
+const ROW_HEIGHT: f32 = 20.0;
+const COLOR_PLAYER: egui::Color32 = egui::Color32::from_rgb(255, 210, 0);
+/// Cars laps ahead: about to lap the player.
+const COLOR_LAPS_AHEAD: egui::Color32 = egui::Color32::from_rgb(255, 110, 90);
+/// Cars laps behind: about to be lapped.
+const COLOR_LAPS_BEHIND: egui::Color32 = egui::Color32::from_rgb(90, 170, 255);
+
+/// Cars around the player on track, front to back, with the gap in seconds.
+/// Cars on another lap are coloured: red a lap up, blue a lap down.
+pub fn relative(ui: &mut egui::Ui, rows: &[CarGap]) {
+    for row in rows {
+        let color = if row.car.is_player {
+            COLOR_PLAYER
+        } else if row.laps > 0 {
+            COLOR_LAPS_AHEAD
+        } else if row.laps < 0 {
+            COLOR_LAPS_BEHIND
+        } else {
+            egui::Color32::WHITE
+        };
+        let gap = match row.seconds {
+            _ if row.car.is_player => String::new(),
+            Some(s) => format!("{:+.1}", s),
+            None => "-".to_string(),
+        };
+        standings_row(ui, row, color, &gap);
+    }
+}
+
+/// Every car by position, with the gap to the leader.
+pub fn leaderboard(ui: &mut egui::Ui, rows: &[CarGap]) {
+    for (i, row) in rows.iter().enumerate() {
+        let color = if row.car.is_player {
+            COLOR_PLAYER
+        } else {
+            egui::Color32::WHITE
+        };
+        let gap = match row.seconds {
+            _ if i == 0 => "LEADER".to_string(),
+            _ if row.laps < 0 => format!("+{} L", -row.laps),
+            Some(s) => format!("+{:.1}", -s),
+            None => "-".to_string(),
+        };
+        standings_row(ui, row, color, &gap);
+    }
+}
+
+/// Position, class, driver and gap; cars in the pits are dimmed.
+fn standings_row(ui: &mut egui::Ui, row: &CarGap, color: egui::Color32, gap: &str) {
+    let width = ui.available_width();
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(width, ROW_HEIGHT), egui::Sense::hover());
+    let painter = ui.painter();
+    let color = if row.car.in_pit {
+        color.gamma_multiply(0.5)
+    } else {
+        color
+    };
+    if row.car.is_player {
+        painter.rect_filled(rect, 0.0, egui::Color32::from_gray(50));
+    }
+
+    let font = egui::FontId::proportional(14.0);
+    let y = rect.center().y;
+    let position = if row.car.position > 0 {
+        row.car.position.to_string()
+    } else {
+        "-".to_string()
+    };
+    painter.text(
+        egui::pos2(rect.left() + 28.0, y),
+        egui::Align2::RIGHT_CENTER,
+        position,
+        font.clone(),
+        color,
+    );
+    painter.text(
+        egui::pos2(rect.left() + 36.0, y),
+        egui::Align2::LEFT_CENTER,
+        &row.car.class,
+        egui::FontId::proportional(11.0),
+        egui::Color32::LIGHT_GRAY,
+    );
+    let driver = if row.car.in_pit {
+        format!("{} (PIT)", row.car.driver)
+    } else {
+        row.car.driver.clone()
+    };
+    painter.text(
+        egui::pos2(rect.left() + 80.0, y),
+        egui::Align2::LEFT_CENTER,
+        driver,
+        font.clone(),
+        color,
+    );
+    painter.text(
+        egui::pos2(rect.right() - 4.0, y),
+        egui::Align2::RIGHT_CENTER,
+        gap,
+        font,
+        color,
+    );
+}